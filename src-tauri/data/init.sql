@@ -18,7 +18,10 @@ CREATE TABLE chains (
     ecosystem TEXT NOT NULL DEFAULT 'evm',
     is_active BOOLEAN NOT NULL DEFAULT TRUE,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    confirmation_blocks INTEGER NOT NULL DEFAULT 1,
+    multicall3_address TEXT DEFAULT '0xcA11bde05977b3631167028862bE2a173976CA11',
+    price_max_age_secs INTEGER NOT NULL DEFAULT 3600 -- Chainlink 价格的最大允许延迟（秒），0表示不检查
 );
 
 -- 插入chains表数据
@@ -47,6 +50,7 @@ CREATE TABLE rpc_providers (
     last_success_at DATETIME,
     failure_count INTEGER NOT NULL DEFAULT 0,
     avg_response_time_ms INTEGER,
+    max_batch_size INTEGER NOT NULL DEFAULT 20,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (chain_id) REFERENCES chains(id) ON DELETE CASCADE
@@ -221,6 +225,8 @@ CREATE TABLE tokens (
     is_active BOOLEAN NOT NULL DEFAULT TRUE,
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    price_source TEXT, -- 链上价格来源: chainlink / uniswap_v2 / stable
+    price_feed TEXT, -- Chainlink聚合器地址或Uniswap V2交易对地址
     FOREIGN KEY (chain_id) REFERENCES chains(id) ON DELETE CASCADE,
     UNIQUE(chain_id, token_key)
 );
@@ -302,6 +308,139 @@ CREATE TABLE monitor_history (
     FOREIGN KEY (monitor_config_id) REFERENCES monitor_configs(id) ON DELETE CASCADE
 );
 
+-- 创建transfer_jobs表
+CREATE TABLE transfer_jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    transfer_kind TEXT NOT NULL CHECK(transfer_kind IN ('base', 'token')),
+    config_json TEXT NOT NULL, -- JSON格式的转账配置
+    window_id TEXT,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'running', 'stopped', 'completed')),
+    total_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 创建transfer_job_items表
+CREATE TABLE transfer_job_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id INTEGER NOT NULL,
+    item_index INTEGER NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'sending', 'submitted', 'confirmed', 'failed')),
+    tx_hash TEXT,
+    nonce INTEGER,
+    raw_tx TEXT, -- 已签名的原始交易，广播中断时用于核对和重新广播
+    error_msg TEXT,
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (job_id) REFERENCES transfer_jobs(id) ON DELETE CASCADE,
+    UNIQUE(job_id, item_index)
+);
+
+-- 创建tx_replacements表
+CREATE TABLE tx_replacements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    original_hash TEXT NOT NULL,
+    replacement_hash TEXT NOT NULL UNIQUE,
+    from_address TEXT NOT NULL,
+    nonce INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN ('speed_up', 'cancel')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 创建tracked_receipts表
+CREATE TABLE tracked_receipts (
+    chain_key TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_hash TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (chain_key, tx_hash)
+);
+
+-- 创建wallet_activity_cache表
+CREATE TABLE wallet_activity_cache (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    address TEXT NOT NULL, -- 小写地址
+    nonce INTEGER NOT NULL DEFAULT 0,
+    last_block INTEGER NOT NULL DEFAULT 0, -- 已统计到的区块高度
+    tx_count INTEGER NOT NULL DEFAULT 0,
+    first_tx_at INTEGER, -- Unix时间戳（秒）
+    last_tx_at INTEGER,
+    contracts_json TEXT NOT NULL DEFAULT '[]', -- JSON数组格式，交互过的合约地址
+    active_days_json TEXT NOT NULL DEFAULT '[]', -- JSON数组格式，活跃日（自1970-01-01起的天数）
+    gas_spent_wei TEXT NOT NULL DEFAULT '0',
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(chain_key, address)
+);
+
+-- 创建balance_snapshots表
+CREATE TABLE balance_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    coin_key TEXT NOT NULL, -- 平台币为 base，代币为小写合约地址
+    coin_symbol TEXT NOT NULL,
+    block_number INTEGER, -- 历史余额查询的区块
+    label TEXT,
+    item_count INTEGER NOT NULL DEFAULT 0,
+    decimals INTEGER NOT NULL, -- 余额精度，明细余额为最小单位的整数
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 创建balance_snapshot_items表
+CREATE TABLE balance_snapshot_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    snapshot_id INTEGER NOT NULL,
+    address TEXT NOT NULL, -- 小写地址
+    balance TEXT, -- 最小单位的整数，查询失败时为空
+    nonce INTEGER,
+    FOREIGN KEY (snapshot_id) REFERENCES balance_snapshots(id) ON DELETE CASCADE,
+    UNIQUE(snapshot_id, address)
+);
+
+-- 创建native_transfers表
+CREATE TABLE native_transfers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    from_address TEXT NOT NULL, -- 小写地址
+    to_address TEXT NOT NULL, -- 小写地址
+    amount_wei TEXT NOT NULL,
+    nonce INTEGER, -- 由节点分配nonce时为空
+    tx_hash TEXT NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 创建address_book表
+CREATE TABLE address_book (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    address TEXT NOT NULL, -- EVM地址为小写
+    ecosystem TEXT NOT NULL DEFAULT 'evm',
+    label TEXT,
+    group_name TEXT,
+    tags_json TEXT NOT NULL DEFAULT '[]', -- JSON数组格式
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(ecosystem, address)
+);
+
+-- 创建solana_lookup_tables表
+CREATE TABLE solana_lookup_tables (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_key TEXT NOT NULL,
+    table_address TEXT NOT NULL, -- 地址查找表账户
+    authority TEXT NOT NULL, -- 有权扩展查找表的钱包
+    label TEXT,
+    address_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(chain_key, table_address)
+);
+
 -- 创建索引
 CREATE INDEX idx_rpc_providers_chain_id ON rpc_providers(chain_id);
 CREATE INDEX idx_rpc_providers_priority ON rpc_providers(priority);
@@ -315,9 +454,15 @@ CREATE INDEX idx_monitor_history_address ON monitor_history(wallet_address);
 CREATE INDEX idx_monitor_history_chain ON monitor_history(chain_key);
 CREATE INDEX idx_monitor_history_type ON monitor_history(monitor_type);
 CREATE INDEX idx_monitor_history_created_at ON monitor_history(created_at);
+CREATE INDEX idx_transfer_job_items_job_id ON transfer_job_items(job_id);
+CREATE INDEX idx_transfer_job_items_status ON transfer_job_items(status);
+CREATE INDEX idx_tx_replacements_original ON tx_replacements(chain_key, original_hash);
+CREATE INDEX idx_balance_snapshots_chain ON balance_snapshots(chain_key, coin_key);
+CREATE INDEX idx_native_transfers_from ON native_transfers(chain_key, from_address, created_at);
+CREATE INDEX idx_address_book_group ON address_book(ecosystem, group_name);
 
 -- 插入Solana链数据 (ID 26)
-INSERT OR IGNORE INTO chains (id, chain_key, chain_name, chain_id, native_currency_symbol, native_currency_name, native_currency_decimals, pic_data, scan_url, scan_api, verify_api, check_verify_api, is_active, created_at, updated_at) VALUES ('26', 'sol', 'Solana', '101', 'SOL', 'SOL', '9', 'sol.png', 'https://solscan.io', '', '', '', '1', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP);
+INSERT OR IGNORE INTO chains (id, chain_key, chain_name, chain_id, native_currency_symbol, native_currency_name, native_currency_decimals, pic_data, scan_url, scan_api, verify_api, check_verify_api, ecosystem, multicall3_address, is_active, created_at, updated_at) VALUES ('26', 'sol', 'Solana', '101', 'SOL', 'SOL', '9', 'sol.png', 'https://solscan.io', '', '', '', 'solana', NULL, '1', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP);
 
 -- 插入Solana基础代币 (ID 48)
 INSERT OR IGNORE INTO tokens (id, chain_id, token_key, token_name, symbol, contract_address, decimals, token_type, contract_type, abi, is_active, created_at, updated_at) VALUES ('48', '26', 'sol_base', 'SOL', 'SOL', NULL, '9', 'base', NULL, NULL, '1', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP);
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 地址簿服务
+pub struct AddressBookService<'a> {
+    pool: &'a SqlitePool,
+}
+
+fn tags_json(tags: &[String]) -> String {
+    serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl<'a> AddressBookService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 获取地址簿条目，可按生态、分组、标签过滤
+    pub async fn list_entries(
+        &self,
+        ecosystem: Option<&str>,
+        group_name: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<Vec<AddressBookEntry>> {
+        let entries = sqlx::query_as::<_, AddressBookEntry>(
+            r#"
+            SELECT * FROM address_book
+            WHERE (? IS NULL OR ecosystem = ?)
+              AND (? IS NULL OR group_name = ?)
+              AND (? IS NULL OR EXISTS (SELECT 1 FROM json_each(address_book.tags_json) WHERE value = ?))
+            ORDER BY group_name, id
+            "#
+        )
+        .bind(ecosystem)
+        .bind(ecosystem)
+        .bind(group_name)
+        .bind(group_name)
+        .bind(tag)
+        .bind(tag)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// 新增条目，同一生态下地址已存在时更新其备注、分组和标签
+    pub async fn upsert_entry(&self, request: &AddressBookEntryRequest) -> Result<i64> {
+        let now = Utc::now();
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO address_book (address, ecosystem, label, group_name, tags_json, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(ecosystem, address) DO UPDATE SET
+                label = excluded.label,
+                group_name = excluded.group_name,
+                tags_json = excluded.tags_json,
+                updated_at = excluded.updated_at
+            RETURNING id
+            "#
+        )
+        .bind(&request.address)
+        .bind(&request.ecosystem)
+        .bind(&request.label)
+        .bind(&request.group_name)
+        .bind(tags_json(&request.tags))
+        .bind(now)
+        .bind(now)
+        .fetch_one(self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// 修改条目
+    pub async fn update_entry(&self, id: i64, request: &AddressBookEntryRequest) -> Result<()> {
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE address_book
+            SET address = ?, ecosystem = ?, label = ?, group_name = ?, tags_json = ?, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(&request.address)
+        .bind(&request.ecosystem)
+        .bind(&request.label)
+        .bind(&request.group_name)
+        .bind(tags_json(&request.tags))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow::anyhow!("地址簿条目不存在: {id}"));
+        }
+
+        Ok(())
+    }
+
+    /// 批量删除条目
+    pub async fn delete_entries(&self, ids: &[i64]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+        for id in ids {
+            deleted += sqlx::query("DELETE FROM address_book WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(deleted)
+    }
+
+    /// 批量导入（同一事务内完成），返回 (新增数量, 更新数量)
+    pub async fn import_entries(&self, requests: &[AddressBookEntryRequest]) -> Result<(usize, usize)> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+        let (mut inserted, mut updated) = (0, 0);
+        for request in requests {
+            let exists = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM address_book WHERE ecosystem = ? AND address = ?"
+            )
+            .bind(&request.ecosystem)
+            .bind(&request.address)
+            .fetch_one(&mut *tx)
+            .await? > 0;
+
+            // 导入时未填写的列保留原有值
+            sqlx::query(
+                r#"
+                INSERT INTO address_book (address, ecosystem, label, group_name, tags_json, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(ecosystem, address) DO UPDATE SET
+                    label = COALESCE(excluded.label, label),
+                    group_name = COALESCE(excluded.group_name, group_name),
+                    tags_json = CASE WHEN excluded.tags_json = '[]' THEN tags_json ELSE excluded.tags_json END,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&request.address)
+            .bind(&request.ecosystem)
+            .bind(&request.label)
+            .bind(&request.group_name)
+            .bind(tags_json(&request.tags))
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+            if exists {
+                updated += 1;
+            } else {
+                inserted += 1;
+            }
+        }
+        tx.commit().await?;
+
+        Ok((inserted, updated))
+    }
+
+    /// 获取分组列表及每组地址数量
+    pub async fn list_groups(&self, ecosystem: Option<&str>) -> Result<Vec<AddressGroup>> {
+        let groups = sqlx::query_as::<_, AddressGroup>(
+            r#"
+            SELECT group_name, COUNT(*) AS address_count FROM address_book
+            WHERE group_name IS NOT NULL AND group_name != '' AND (? IS NULL OR ecosystem = ?)
+            GROUP BY group_name
+            ORDER BY group_name
+            "#
+        )
+        .bind(ecosystem)
+        .bind(ecosystem)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(groups)
+    }
+
+    /// 获取分组内的全部地址（按加入顺序）
+    pub async fn get_group_addresses(&self, ecosystem: &str, group_name: &str) -> Result<Vec<String>> {
+        let addresses = sqlx::query_scalar::<_, String>(
+            "SELECT address FROM address_book WHERE ecosystem = ? AND group_name = ? ORDER BY id"
+        )
+        .bind(ecosystem)
+        .bind(group_name)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(addresses)
+    }
+}
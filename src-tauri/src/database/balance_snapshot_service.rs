@@ -0,0 +1,127 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 余额快照服务
+pub struct BalanceSnapshotService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> BalanceSnapshotService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 保存快照及其全部明细（同一事务内完成），同一地址重复出现时保留最后一条
+    pub async fn create_snapshot(&self, request: CreateBalanceSnapshotRequest) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let snapshot_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO balance_snapshots (
+                chain_key, coin_key, coin_symbol, block_number, label, item_count, decimals, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#
+        )
+        .bind(&request.chain_key)
+        .bind(request.coin_key.to_lowercase())
+        .bind(&request.coin_symbol)
+        .bind(request.block_number)
+        .bind(&request.label)
+        .bind(request.items.len() as i64)
+        .bind(request.decimals)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for item in &request.items {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO balance_snapshot_items (snapshot_id, address, balance, nonce)
+                VALUES (?, ?, ?, ?)
+                "#
+            )
+            .bind(snapshot_id)
+            .bind(item.address.trim().to_lowercase())
+            .bind(&item.balance)
+            .bind(item.nonce)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(snapshot_id)
+    }
+
+    /// 获取快照列表（最新的在前），可按链过滤
+    pub async fn list_snapshots(&self, chain_key: Option<&str>) -> Result<Vec<BalanceSnapshot>> {
+        let snapshots = sqlx::query_as::<_, BalanceSnapshot>(
+            r#"
+            SELECT * FROM balance_snapshots
+            WHERE ? IS NULL OR chain_key = ?
+            ORDER BY created_at DESC, id DESC
+            "#
+        )
+        .bind(chain_key)
+        .bind(chain_key)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+
+    /// 获取单个快照
+    pub async fn get_snapshot(&self, snapshot_id: i64) -> Result<Option<BalanceSnapshot>> {
+        let snapshot = sqlx::query_as::<_, BalanceSnapshot>("SELECT * FROM balance_snapshots WHERE id = ?")
+            .bind(snapshot_id)
+            .fetch_optional(self.pool)
+            .await?;
+
+        Ok(snapshot)
+    }
+
+    /// 获取快照的全部明细
+    pub async fn get_snapshot_items(&self, snapshot_id: i64) -> Result<Vec<BalanceSnapshotItem>> {
+        let items = sqlx::query_as::<_, BalanceSnapshotItem>(
+            "SELECT * FROM balance_snapshot_items WHERE snapshot_id = ? ORDER BY id ASC"
+        )
+        .bind(snapshot_id)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// 修改快照备注
+    pub async fn update_label(&self, snapshot_id: i64, label: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE balance_snapshots SET label = ? WHERE id = ?")
+            .bind(label)
+            .bind(snapshot_id)
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 删除快照及其明细
+    pub async fn delete_snapshot(&self, snapshot_id: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM balance_snapshot_items WHERE snapshot_id = ?")
+            .bind(snapshot_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM balance_snapshots WHERE id = ?")
+            .bind(snapshot_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
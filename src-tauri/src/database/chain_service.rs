@@ -3,6 +3,9 @@ use sqlx::{SqlitePool, Row};
 use crate::database::models::*;
 use chrono::Utc;
 
+// Multicall3 在绝大多数EVM链上的部署地址（CREATE2确定性部署），新建EVM链时的默认值
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
 /// 链服务
 pub struct ChainService<'a> {
     pool: &'a SqlitePool,
@@ -41,6 +44,9 @@ impl<'a> ChainService<'a> {
                 verify_api: chain.verify_api.unwrap_or_default(),
                 check_verify_api: chain.check_verify_api.unwrap_or_default(),
                 rpc_urls,
+                confirmation_blocks: chain.confirmation_blocks,
+                multicall3_address: chain.multicall3_address,
+                price_max_age_secs: chain.price_max_age_secs,
             });
         }
 
@@ -59,6 +65,30 @@ impl<'a> ChainService<'a> {
         Ok(chain)
     }
 
+    /// 获取链配置的交易确认深度（链不存在时返回 None）
+    pub async fn get_confirmation_blocks(&self, chain_key: &str) -> Result<Option<i64>> {
+        let blocks = sqlx::query_scalar::<_, i64>(
+            "SELECT confirmation_blocks FROM chains WHERE chain_key = ?"
+        )
+        .bind(chain_key)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(blocks)
+    }
+
+    /// 获取链配置的Multicall3合约地址（未配置或已停用时返回 None）
+    pub async fn get_multicall3_address(&self, chain_key: &str) -> Result<Option<String>> {
+        let address = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT multicall3_address FROM chains WHERE chain_key = ?"
+        )
+        .bind(chain_key)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(address.flatten().filter(|a| !a.trim().is_empty()))
+    }
+
     /// 获取链的RPC URLs
     pub async fn get_chain_rpc_urls(&self, chain_id: i64) -> Result<Vec<String>> {
         let rows = sqlx::query(
@@ -99,8 +129,9 @@ impl<'a> ChainService<'a> {
             INSERT INTO chains (
                 chain_key, chain_name, ecosystem, chain_id, native_currency_symbol, 
                 native_currency_name, native_currency_decimals, pic_data,
-                scan_url, scan_api, verify_api, check_verify_api, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                scan_url, scan_api, verify_api, check_verify_api, confirmation_blocks, multicall3_address,
+                price_max_age_secs, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING id
             "#
         )
@@ -116,6 +147,14 @@ impl<'a> ChainService<'a> {
         .bind(&request.scan_api)
         .bind(&request.verify_api)
         .bind(&request.check_verify_api)
+        .bind(request.confirmation_blocks.unwrap_or(1).max(1))
+        .bind(match request.multicall3_address.as_deref() {
+            Some(address) => Some(address),
+            // 新增EVM链默认使用标准部署地址，查询时会检查合约是否存在
+            None if request.ecosystem == "evm" => Some(MULTICALL3_ADDRESS),
+            None => None,
+        })
+        .bind(request.price_max_age_secs.unwrap_or(3600).max(0))
         .bind(now)
         .bind(now)
         .fetch_one(self.pool)
@@ -178,7 +217,10 @@ impl<'a> ChainService<'a> {
             UPDATE chains SET 
                 chain_name = ?, ecosystem = ?, chain_id = ?, native_currency_symbol = ?, 
                 native_currency_name = ?, native_currency_decimals = ?, pic_data = ?,
-                scan_url = ?, scan_api = ?, verify_api = ?, check_verify_api = ?, updated_at = ?
+                scan_url = ?, scan_api = ?, verify_api = ?, check_verify_api = ?,
+                confirmation_blocks = COALESCE(?, confirmation_blocks),
+                multicall3_address = COALESCE(?, multicall3_address),
+                price_max_age_secs = COALESCE(?, price_max_age_secs), updated_at = ?
             WHERE id = ?
             "#
         )
@@ -193,6 +235,9 @@ impl<'a> ChainService<'a> {
         .bind(&request.scan_api)
         .bind(&request.verify_api)
         .bind(&request.check_verify_api)
+        .bind(request.confirmation_blocks.map(|n| n.max(1)))
+        .bind(&request.multicall3_address)
+        .bind(request.price_max_age_secs.map(|n| n.max(0)))
         .bind(now)
         .bind(chain.id)
         .execute(self.pool)
@@ -350,6 +395,122 @@ impl<'a> ChainService<'a> {
         Ok(token)
     }
 
+    /// 根据合约地址获取代币配置（地址不区分大小写）
+    pub async fn get_token_by_contract(&self, chain_key: &str, contract_address: &str) -> Result<Option<Token>> {
+        let token = sqlx::query_as::<_, Token>(
+            r#"
+            SELECT t.* FROM tokens t
+            JOIN chains c ON t.chain_id = c.id
+            WHERE c.chain_key = ? AND LOWER(t.contract_address) = LOWER(?)
+            LIMIT 1
+            "#
+        )
+        .bind(chain_key)
+        .bind(contract_address)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// 获取链的平台币配置
+    pub async fn get_base_token(&self, chain_key: &str) -> Result<Option<Token>> {
+        let token = sqlx::query_as::<_, Token>(
+            r#"
+            SELECT t.* FROM tokens t
+            JOIN chains c ON t.chain_id = c.id
+            WHERE c.chain_key = ? AND t.token_type = 'base'
+            ORDER BY t.id ASC
+            LIMIT 1
+            "#
+        )
+        .bind(chain_key)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// 获取链上已配置的所有代币合约地址
+    pub async fn get_token_contracts(&self, chain_key: &str) -> Result<Vec<String>> {
+        let contracts = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT t.contract_address FROM tokens t
+            JOIN chains c ON t.chain_id = c.id
+            WHERE c.chain_key = ? AND t.contract_address IS NOT NULL
+            "#
+        )
+        .bind(chain_key)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(contracts)
+    }
+
+    /// 批量添加代币（同一事务内），跳过合约地址已存在的代币；
+    /// token_key 冲突时追加合约地址前缀区分，返回实际添加数量
+    pub async fn add_tokens(&self, chain_key: &str, requests: Vec<CreateTokenRequest>) -> Result<usize> {
+        let chain = self.get_chain_by_key(chain_key).await?
+            .ok_or_else(|| anyhow::anyhow!("链不存在: {chain_key}"))?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut added = 0;
+        for request in requests {
+            let exists = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM tokens WHERE chain_id = ? AND LOWER(contract_address) = LOWER(?)"
+            )
+            .bind(chain.id)
+            .bind(&request.contract_address)
+            .fetch_one(&mut *tx)
+            .await?;
+            if exists > 0 {
+                continue;
+            }
+
+            let key_taken = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM tokens WHERE chain_id = ? AND token_key = ?"
+            )
+            .bind(chain.id)
+            .bind(&request.token_key)
+            .fetch_one(&mut *tx)
+            .await?;
+            let token_key = match &request.contract_address {
+                Some(contract) if key_taken > 0 => {
+                    let prefix: String = contract.trim_start_matches("0x").chars().take(6).collect();
+                    format!("{}_{}", request.token_key, prefix.to_lowercase())
+                }
+                _ => request.token_key.clone(),
+            };
+
+            let now = Utc::now();
+            sqlx::query(
+                r#"
+                INSERT INTO tokens (
+                    chain_id, token_key, token_name, symbol, contract_address,
+                    decimals, token_type, contract_type, abi, created_at, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(chain.id)
+            .bind(&token_key)
+            .bind(&request.token_name)
+            .bind(&request.symbol)
+            .bind(&request.contract_address)
+            .bind(request.decimals)
+            .bind(&request.token_type)
+            .bind(&request.contract_type)
+            .bind(&request.abi)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            added += 1;
+        }
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
     /// 根据链key和合约地址获取代币的decimals配置
     pub async fn get_token_decimals_by_contract(&self, chain_key: &str, contract_address: &str) -> Result<Option<i32>> {
         let decimals = sqlx::query_scalar::<_, i32>(
@@ -385,6 +546,56 @@ impl<'a> ChainService<'a> {
         Ok(decimals)
     }
     
+    /// 更新代币的链上价格来源，price_source 为空时清除配置
+    pub async fn update_token_price_source(
+        &self,
+        chain_key: &str,
+        token_key: &str,
+        price_source: Option<&str>,
+        price_feed: Option<&str>,
+    ) -> Result<()> {
+        let chain = self.get_chain_by_key(chain_key).await?
+            .ok_or_else(|| anyhow::anyhow!("链不存在: {chain_key}"))?;
+
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE tokens SET price_source = ?, price_feed = ?, updated_at = ?
+            WHERE chain_id = ? AND token_key = ?
+            "#
+        )
+        .bind(price_source)
+        .bind(price_feed)
+        .bind(Utc::now())
+        .bind(chain.id)
+        .bind(token_key)
+        .execute(self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(anyhow::anyhow!("代币不存在: {chain_key}/{token_key}"));
+        }
+
+        Ok(())
+    }
+
+    /// 获取链上所有配置了价格来源的代币
+    pub async fn get_priced_tokens(&self, chain_key: &str) -> Result<Vec<Token>> {
+        let tokens = sqlx::query_as::<_, Token>(
+            r#"
+            SELECT t.* FROM tokens t
+            JOIN chains c ON t.chain_id = c.id
+            WHERE c.chain_key = ? AND t.price_source IS NOT NULL AND t.price_source != ''
+            ORDER BY t.token_type DESC, t.token_name
+            "#
+        )
+        .bind(chain_key)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
     /// 更新代币的ABI
     pub async fn update_token_abi(&self, chain_key: &str, token_key: &str, abi: Option<String>) -> Result<()> {
         // 获取链信息验证链是否存在
@@ -515,18 +726,20 @@ impl<'a> ChainService<'a> {
     }
 
     /// 更新 RPC 提供商
-    pub async fn update_rpc_provider(&self, id: i64, rpc_url: &str, is_active: bool, priority: i32) -> Result<RpcProvider> {
+    pub async fn update_rpc_provider(&self, id: i64, rpc_url: &str, is_active: bool, priority: i32, max_batch_size: Option<i32>) -> Result<RpcProvider> {
         let now = Utc::now();
         sqlx::query(
             r#"
             UPDATE rpc_providers SET 
-                rpc_url = ?, is_active = ?, priority = ?, updated_at = ?
+                rpc_url = ?, is_active = ?, priority = ?,
+                max_batch_size = COALESCE(?, max_batch_size), updated_at = ?
             WHERE id = ?
             "#
         )
         .bind(rpc_url)
         .bind(is_active)
         .bind(priority)
+        .bind(max_batch_size.map(|size| size.max(1)))
         .bind(now)
         .bind(id)
         .execute(self.pool)
@@ -0,0 +1,67 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// Solana 地址查找表记录服务
+pub struct LookupTableService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> LookupTableService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 保存查找表（已存在时更新地址数量，未传入备注时保留原备注）
+    pub async fn save(
+        &self,
+        chain_key: &str,
+        table_address: &str,
+        authority: &str,
+        label: Option<&str>,
+        address_count: usize,
+    ) -> Result<()> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO solana_lookup_tables (chain_key, table_address, authority, label, address_count, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(chain_key, table_address) DO UPDATE SET
+                authority = excluded.authority,
+                label = COALESCE(excluded.label, label),
+                address_count = excluded.address_count,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(chain_key)
+        .bind(table_address)
+        .bind(authority)
+        .bind(label)
+        .bind(address_count as i64)
+        .bind(now)
+        .bind(now)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 获取链上的查找表，可按管理钱包过滤
+    pub async fn list(&self, chain_key: &str, authority: Option<&str>) -> Result<Vec<SolanaLookupTable>> {
+        let tables = sqlx::query_as::<_, SolanaLookupTable>(
+            r#"
+            SELECT * FROM solana_lookup_tables
+            WHERE chain_key = ? AND (? IS NULL OR authority = ?)
+            ORDER BY id
+            "#
+        )
+        .bind(chain_key)
+        .bind(authority)
+        .bind(authority)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(tables)
+    }
+}
@@ -1,6 +1,14 @@
 pub mod models;
 pub mod chain_service;
 pub mod rpc_service;
+pub mod transfer_job_service;
+pub mod tx_replacement_service;
+pub mod tracked_receipt_service;
+pub mod wallet_activity_service;
+pub mod balance_snapshot_service;
+pub mod native_transfer_service;
+pub mod address_book_service;
+pub mod lookup_table_service;
 
 use sqlx::{SqlitePool, sqlite::SqliteConnectOptions, Row};
 use anyhow::Result;
@@ -195,6 +203,87 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         println!("迁移完成: ecosystem 列已添加");
     }
 
+    // 检查chains表是否包含confirmation_blocks列（交易确认深度）
+    let confirmation_blocks_exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('chains') WHERE name = 'confirmation_blocks'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if confirmation_blocks_exists == 0 {
+        println!("正在迁移数据库: 添加 confirmation_blocks 列到 chains 表");
+        sqlx::query("ALTER TABLE chains ADD COLUMN confirmation_blocks INTEGER NOT NULL DEFAULT 1")
+            .execute(pool)
+            .await?;
+        println!("迁移完成: confirmation_blocks 列已添加");
+    }
+
+    // 检查chains表是否包含multicall3_address列（为空表示该链不使用Multicall3）
+    let multicall3_exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('chains') WHERE name = 'multicall3_address'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if multicall3_exists == 0 {
+        println!("正在迁移数据库: 添加 multicall3_address 列到 chains 表");
+        sqlx::query("ALTER TABLE chains ADD COLUMN multicall3_address TEXT DEFAULT '0xcA11bde05977b3631167028862bE2a173976CA11'")
+            .execute(pool)
+            .await?;
+        sqlx::query("UPDATE chains SET multicall3_address = NULL WHERE ecosystem != 'evm'")
+            .execute(pool)
+            .await?;
+        println!("迁移完成: multicall3_address 列已添加");
+    }
+
+    // 检查chains表是否包含price_max_age_secs列（Chainlink 价格的最大允许延迟）
+    let price_max_age_exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('chains') WHERE name = 'price_max_age_secs'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if price_max_age_exists == 0 {
+        println!("正在迁移数据库: 添加 price_max_age_secs 列到 chains 表");
+        sqlx::query("ALTER TABLE chains ADD COLUMN price_max_age_secs INTEGER NOT NULL DEFAULT 3600")
+            .execute(pool)
+            .await?;
+        println!("迁移完成: price_max_age_secs 列已添加");
+    }
+
+    // 检查tokens表是否包含price_source/price_feed列（链上价格来源配置）
+    let price_source_exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('tokens') WHERE name = 'price_source'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if price_source_exists == 0 {
+        println!("正在迁移数据库: 添加 price_source/price_feed 列到 tokens 表");
+        sqlx::query("ALTER TABLE tokens ADD COLUMN price_source TEXT")
+            .execute(pool)
+            .await?;
+        sqlx::query("ALTER TABLE tokens ADD COLUMN price_feed TEXT")
+            .execute(pool)
+            .await?;
+        println!("迁移完成: price_source/price_feed 列已添加");
+    }
+
+    // 检查rpc_providers表是否包含max_batch_size列（单次JSON-RPC批量请求的最大调用数）
+    let max_batch_size_exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('rpc_providers') WHERE name = 'max_batch_size'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if max_batch_size_exists == 0 {
+        println!("正在迁移数据库: 添加 max_batch_size 列到 rpc_providers 表");
+        sqlx::query("ALTER TABLE rpc_providers ADD COLUMN max_batch_size INTEGER NOT NULL DEFAULT 20")
+            .execute(pool)
+            .await?;
+        println!("迁移完成: max_batch_size 列已添加");
+    }
+
     // 数据修复：将 Solana 链的 ecosystem 设置为 solana
     // 即使列已存在，也执行此修复以确保历史数据正确
     sqlx::query("UPDATE chains SET ecosystem = 'solana' WHERE chain_key IN ('sol', 'solana') AND ecosystem != 'solana'")
@@ -202,12 +291,229 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         .await
         .ok(); // 忽略错误
 
+    // 数据修复：非EVM链没有Multicall3（旧版初始化脚本给Solana链写入了默认地址）
+    sqlx::query("UPDATE chains SET multicall3_address = NULL WHERE ecosystem != 'evm' AND multicall3_address IS NOT NULL")
+        .execute(pool)
+        .await
+        .ok(); // 忽略错误
+
     // 数据修复：确保没有空值
     sqlx::query("UPDATE chains SET ecosystem = 'evm' WHERE (ecosystem IS NULL OR ecosystem = '')")
         .execute(pool)
         .await
         .ok();
 
+    // 批量转账任务表（旧版本数据库中不存在）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS transfer_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            transfer_kind TEXT NOT NULL CHECK(transfer_kind IN ('base', 'token')),
+            config_json TEXT NOT NULL,
+            window_id TEXT,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'running', 'stopped', 'completed')),
+            total_count INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS transfer_job_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            job_id INTEGER NOT NULL,
+            item_index INTEGER NOT NULL,
+            from_address TEXT NOT NULL,
+            to_address TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'sending', 'submitted', 'confirmed', 'failed')),
+            tx_hash TEXT,
+            nonce INTEGER,
+            raw_tx TEXT,
+            error_msg TEXT,
+            attempt_count INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (job_id) REFERENCES transfer_jobs(id) ON DELETE CASCADE,
+            UNIQUE(job_id, item_index)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transfer_job_items_job_id ON transfer_job_items(job_id)")
+        .execute(pool)
+        .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transfer_job_items_status ON transfer_job_items(status)")
+        .execute(pool)
+        .await?;
+
+    // 交易加速/取消记录表（旧版本数据库中不存在）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tx_replacements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            original_hash TEXT NOT NULL,
+            replacement_hash TEXT NOT NULL UNIQUE,
+            from_address TEXT NOT NULL,
+            nonce INTEGER NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('speed_up', 'cancel')),
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_tx_replacements_original ON tx_replacements(chain_key, original_hash)")
+        .execute(pool)
+        .await?;
+
+    // 未达到确认深度的交易回执所在区块，用于重组检测（旧版本数据库中不存在）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tracked_receipts (
+            chain_key TEXT NOT NULL,
+            tx_hash TEXT NOT NULL,
+            block_number INTEGER NOT NULL,
+            block_hash TEXT NOT NULL,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (chain_key, tx_hash)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // 钱包活跃度缓存表（旧版本数据库中不存在）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS wallet_activity_cache (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            address TEXT NOT NULL,
+            nonce INTEGER NOT NULL DEFAULT 0,
+            last_block INTEGER NOT NULL DEFAULT 0,
+            tx_count INTEGER NOT NULL DEFAULT 0,
+            first_tx_at INTEGER,
+            last_tx_at INTEGER,
+            contracts_json TEXT NOT NULL DEFAULT '[]',
+            active_days_json TEXT NOT NULL DEFAULT '[]',
+            gas_spent_wei TEXT NOT NULL DEFAULT '0',
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(chain_key, address)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // 余额快照表（旧版本数据库中不存在）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS balance_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            coin_key TEXT NOT NULL,
+            coin_symbol TEXT NOT NULL,
+            block_number INTEGER,
+            label TEXT,
+            item_count INTEGER NOT NULL DEFAULT 0,
+            decimals INTEGER NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS balance_snapshot_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            snapshot_id INTEGER NOT NULL,
+            address TEXT NOT NULL,
+            balance TEXT,
+            nonce INTEGER,
+            FOREIGN KEY (snapshot_id) REFERENCES balance_snapshots(id) ON DELETE CASCADE,
+            UNIQUE(snapshot_id, address)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_balance_snapshots_chain ON balance_snapshots(chain_key, coin_key)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS native_transfers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            from_address TEXT NOT NULL,
+            to_address TEXT NOT NULL,
+            amount_wei TEXT NOT NULL,
+            nonce INTEGER,
+            tx_hash TEXT NOT NULL UNIQUE,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_native_transfers_from ON native_transfers(chain_key, from_address, created_at)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS address_book (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            address TEXT NOT NULL,
+            ecosystem TEXT NOT NULL DEFAULT 'evm',
+            label TEXT,
+            group_name TEXT,
+            tags_json TEXT NOT NULL DEFAULT '[]',
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(ecosystem, address)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_address_book_group ON address_book(ecosystem, group_name)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS solana_lookup_tables (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chain_key TEXT NOT NULL,
+            table_address TEXT NOT NULL,
+            authority TEXT NOT NULL,
+            label TEXT,
+            address_count INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(chain_key, table_address)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
     Ok(())
 }
 
@@ -529,6 +835,8 @@ pub async fn export_database_to_init_sql() -> Result<String, String> {
         "chains",           // 基础表，被其他表引用
         "rpc_providers",    // 依赖 chains
         "tokens",           // 依赖 chains
+        "transfer_jobs",    // 被 transfer_job_items 引用
+        "transfer_job_items", // 依赖 transfer_jobs
     ];
     
     // 按照预定义顺序添加存在的表
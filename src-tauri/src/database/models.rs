@@ -21,6 +21,9 @@ pub struct Chain {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub confirmation_blocks: i64, // 交易确认所需的区块数
+    pub multicall3_address: Option<String>, // 为空表示该链未部署Multicall3
+    pub price_max_age_secs: i64, // Chainlink 价格的最大允许延迟（秒），0表示不检查
 }
 
 /// RPC提供商配置模型
@@ -34,6 +37,7 @@ pub struct RpcProvider {
     pub last_success_at: Option<DateTime<Utc>>,
     pub failure_count: i32,
     pub avg_response_time_ms: Option<i32>,
+    pub max_batch_size: i32, // 单次批量请求的最大调用数，<=1 表示不使用批量请求
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -54,6 +58,8 @@ pub struct Token {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub price_source: Option<String>,  // 链上价格来源: "chainlink", "uniswap_v2", "stable"
+    pub price_feed: Option<String>,    // Chainlink聚合器地址或Uniswap V2交易对地址
 }
 
 /// 创建链的请求模型
@@ -72,6 +78,12 @@ pub struct CreateChainRequest {
     pub verify_api: Option<String>,
     pub check_verify_api: Option<String>,
     pub rpc_urls: Option<Vec<String>>,
+    #[serde(default)]
+    pub confirmation_blocks: Option<i64>,
+    #[serde(default)]
+    pub multicall3_address: Option<String>,
+    #[serde(default)]
+    pub price_max_age_secs: Option<i64>,
 }
 
 /// 更新链的请求模型
@@ -89,6 +101,12 @@ pub struct UpdateChainRequest {
     pub verify_api: Option<String>,
     pub check_verify_api: Option<String>,
     pub rpc_urls: Option<Vec<String>>,
+    #[serde(default)]
+    pub confirmation_blocks: Option<i64>,
+    #[serde(default)]
+    pub multicall3_address: Option<String>, // 传空字符串表示停用
+    #[serde(default)]
+    pub price_max_age_secs: Option<i64>,
 }
 
 /// 创建 RPC提供商的请求模型
@@ -144,4 +162,198 @@ pub struct ChainInfo {
     pub verify_api: String,
     pub check_verify_api: String,
     pub rpc_urls: Vec<String>,
+    pub confirmation_blocks: i64,
+    pub multicall3_address: Option<String>,
+    pub price_max_age_secs: i64,
+}
+
+/// 批量转账任务模型
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransferJob {
+    pub id: i64,
+    pub chain_key: String,
+    pub transfer_kind: String, // "base" or "token"
+    pub config_json: String,   // 创建任务时的转账配置（JSON）
+    pub window_id: Option<String>,
+    pub status: String,        // "pending", "running", "stopped", "completed"
+    pub total_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 批量转账任务明细模型
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransferJobItem {
+    pub id: i64,
+    pub job_id: i64,
+    pub item_index: i64,
+    pub from_address: String,
+    pub to_address: String,
+    pub status: String, // "pending", "sending", "submitted", "confirmed", "failed"
+    pub tx_hash: Option<String>,
+    pub nonce: Option<i64>,
+    #[serde(skip_serializing)]
+    pub raw_tx: Option<String>, // 已签名的原始交易，仅用于广播中断后的核对
+    pub error_msg: Option<String>,
+    pub attempt_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 创建批量转账任务明细的请求模型
+#[derive(Debug, Clone)]
+pub struct CreateTransferJobItemRequest {
+    pub from_address: String,
+    pub to_address: String,
+}
+
+/// 创建批量转账任务的请求模型
+#[derive(Debug, Clone)]
+pub struct CreateTransferJobRequest {
+    pub chain_key: String,
+    pub transfer_kind: String,
+    pub config_json: String,
+    pub window_id: Option<String>,
+    pub items: Vec<CreateTransferJobItemRequest>,
+}
+
+/// 交易加速/取消记录模型（原交易与替换交易的关联）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TxReplacement {
+    pub id: i64,
+    pub chain_key: String,
+    pub original_hash: String,
+    pub replacement_hash: String,
+    pub from_address: String,
+    pub nonce: i64,
+    pub kind: String, // "speed_up" or "cancel"
+    pub created_at: DateTime<Utc>,
+}
+
+/// 尚未达到确认深度的交易回执所在区块
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TrackedReceipt {
+    pub chain_key: String,
+    pub tx_hash: String,
+    pub block_number: i64,
+    pub block_hash: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 本地发出的平台币转账记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NativeTransfer {
+    pub id: i64,
+    pub chain_key: String,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount_wei: String,
+    pub nonce: Option<i64>, // 由节点分配nonce时为空
+    pub tx_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 地址簿条目
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AddressBookEntry {
+    pub id: i64,
+    pub address: String,
+    pub ecosystem: String, // "evm" or "solana"
+    pub label: Option<String>,
+    pub group_name: Option<String>,
+    pub tags_json: String, // JSON数组格式
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 新增或修改地址簿条目的请求模型
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressBookEntryRequest {
+    pub address: String,
+    pub ecosystem: String,
+    pub label: Option<String>,
+    pub group_name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 地址簿分组及地址数量
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AddressGroup {
+    pub group_name: String,
+    pub address_count: i64,
+}
+
+/// Solana 地址查找表记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SolanaLookupTable {
+    pub id: i64,
+    pub chain_key: String,
+    pub table_address: String,
+    pub authority: String,
+    pub label: Option<String>,
+    pub address_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 钱包活跃度缓存
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WalletActivityCache {
+    pub id: i64,
+    pub chain_key: String,
+    pub address: String,
+    pub nonce: i64,
+    pub last_block: i64,
+    pub tx_count: i64,
+    pub first_tx_at: Option<i64>,
+    pub last_tx_at: Option<i64>,
+    pub contracts_json: String,
+    pub active_days_json: String,
+    pub gas_spent_wei: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 余额快照（一次余额查询的结果）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BalanceSnapshot {
+    pub id: i64,
+    pub chain_key: String,
+    pub coin_key: String,            // 平台币为 "base"，代币为小写合约地址
+    pub coin_symbol: String,
+    pub block_number: Option<i64>,   // 历史余额查询的区块，查询最新区块时为空
+    pub label: Option<String>,
+    pub item_count: i64,
+    pub decimals: i64,               // 余额精度，明细余额为最小单位的整数
+    pub created_at: DateTime<Utc>,
+}
+
+/// 余额快照明细
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BalanceSnapshotItem {
+    pub id: i64,
+    pub snapshot_id: i64,
+    pub address: String,
+    pub balance: Option<String>, // 最小单位的整数，查询失败时为空
+    pub nonce: Option<i64>,
+}
+
+/// 创建余额快照明细的请求模型
+#[derive(Debug, Clone)]
+pub struct CreateBalanceSnapshotItemRequest {
+    pub address: String,
+    pub balance: Option<String>,
+    pub nonce: Option<i64>,
+}
+
+/// 创建余额快照的请求模型
+#[derive(Debug, Clone)]
+pub struct CreateBalanceSnapshotRequest {
+    pub chain_key: String,
+    pub coin_key: String,
+    pub coin_symbol: String,
+    pub block_number: Option<i64>,
+    pub label: Option<String>,
+    pub decimals: i64,
+    pub items: Vec<CreateBalanceSnapshotItemRequest>,
 }
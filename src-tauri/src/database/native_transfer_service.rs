@@ -0,0 +1,73 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use crate::database::models::*;
+
+/// 平台币转账发送记录服务，用于重复转账检测
+pub struct NativeTransferService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> NativeTransferService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 记录一笔已广播的平台币转账
+    pub async fn record(
+        &self,
+        chain_key: &str,
+        from_address: &str,
+        to_address: &str,
+        amount_wei: &str,
+        nonce: Option<u64>,
+        tx_hash: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO native_transfers (
+                chain_key, from_address, to_address, amount_wei, nonce, tx_hash, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(chain_key)
+        .bind(from_address.to_lowercase())
+        .bind(to_address.to_lowercase())
+        .bind(amount_wei)
+        .bind(nonce.map(|nonce| nonce as i64))
+        .bind(tx_hash.to_lowercase())
+        .bind(Utc::now())
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 获取钱包在指定时间之后发出的转账（按发送顺序）
+    pub async fn list_since(&self, chain_key: &str, from_address: &str, since: DateTime<Utc>) -> Result<Vec<NativeTransfer>> {
+        let transfers = sqlx::query_as::<_, NativeTransfer>(
+            r#"
+            SELECT * FROM native_transfers
+            WHERE chain_key = ? AND from_address = ? AND created_at >= ?
+            ORDER BY created_at ASC, id ASC
+            "#
+        )
+        .bind(chain_key)
+        .bind(from_address.to_lowercase())
+        .bind(since)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(transfers)
+    }
+
+    /// 清理指定时间之前的记录
+    pub async fn prune_before(&self, before: DateTime<Utc>) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM native_transfers WHERE created_at < ?")
+            .bind(before)
+            .execute(self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
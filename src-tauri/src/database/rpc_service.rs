@@ -63,6 +63,39 @@ impl<'a> RpcService<'a> {
         Ok(rpc_urls[index].clone())
     }
 
+    /// 获取指定链所有启用的RPC URL及其批量请求上限
+    pub async fn get_rpcs_with_batch_size(&self, chain_key: &str) -> Result<Vec<(String, usize)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rp.rpc_url, rp.max_batch_size
+            FROM rpc_providers rp
+            JOIN chains c ON rp.chain_id = c.id
+            WHERE c.chain_key = ? AND rp.is_active = TRUE
+            ORDER BY rp.priority ASC
+            "#
+        )
+        .bind(chain_key)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            let max_batch_size = row.get::<i32, _>("max_batch_size").max(1) as usize;
+            (row.get::<String, _>("rpc_url"), max_batch_size)
+        }).collect())
+    }
+
+    /// 获取指定链的随机RPC URL及其批量请求上限
+    pub async fn get_random_rpc_with_batch_size(&self, chain_key: &str) -> Result<(String, usize)> {
+        let mut rpcs = self.get_rpcs_with_batch_size(chain_key).await?;
+        if rpcs.is_empty() {
+            return Err(anyhow::anyhow!("没有可用的RPC提供商: {chain_key}"));
+        }
+
+        let mut rng = thread_rng();
+        let index = rng.gen_range(0..rpcs.len());
+        Ok(rpcs.swap_remove(index))
+    }
+
     /// 获取链的所有RPC URLs
     #[allow(dead_code)]
     pub async fn get_all_rpc_urls(&self, chain_key: &str) -> Result<Vec<String>> {
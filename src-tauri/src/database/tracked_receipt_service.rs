@@ -0,0 +1,62 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 交易回执跟踪服务（重组检测）
+pub struct TrackedReceiptService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TrackedReceiptService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 获取交易上次记录的回执区块
+    pub async fn get(&self, chain_key: &str, tx_hash: &str) -> Result<Option<TrackedReceipt>> {
+        let receipt = sqlx::query_as::<_, TrackedReceipt>(
+            "SELECT * FROM tracked_receipts WHERE chain_key = ? AND tx_hash = ?"
+        )
+        .bind(chain_key)
+        .bind(tx_hash.to_lowercase())
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(receipt)
+    }
+
+    /// 记录或更新交易回执所在区块
+    pub async fn upsert(&self, chain_key: &str, tx_hash: &str, block_number: u64, block_hash: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO tracked_receipts (chain_key, tx_hash, block_number, block_hash, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(chain_key, tx_hash) DO UPDATE SET
+                block_number = excluded.block_number,
+                block_hash = excluded.block_hash,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(chain_key)
+        .bind(tx_hash.to_lowercase())
+        .bind(block_number as i64)
+        .bind(block_hash.to_lowercase())
+        .bind(Utc::now())
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 停止跟踪（已达到确认深度或回执已消失）
+    pub async fn remove(&self, chain_key: &str, tx_hash: &str) -> Result<()> {
+        sqlx::query("DELETE FROM tracked_receipts WHERE chain_key = ? AND tx_hash = ?")
+            .bind(chain_key)
+            .bind(tx_hash.to_lowercase())
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
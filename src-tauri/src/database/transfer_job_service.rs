@@ -0,0 +1,258 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 批量转账任务服务
+pub struct TransferJobService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TransferJobService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 创建任务及其全部明细（同一事务内完成）
+    pub async fn create_job(&self, request: CreateTransferJobRequest) -> Result<i64> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let job_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO transfer_jobs (
+                chain_key, transfer_kind, config_json, window_id, status, total_count, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, 'pending', ?, ?, ?)
+            RETURNING id
+            "#
+        )
+        .bind(&request.chain_key)
+        .bind(&request.transfer_kind)
+        .bind(&request.config_json)
+        .bind(&request.window_id)
+        .bind(request.items.len() as i64)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (index, item) in request.items.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO transfer_job_items (
+                    job_id, item_index, from_address, to_address, status, created_at, updated_at
+                ) VALUES (?, ?, ?, ?, 'pending', ?, ?)
+                "#
+            )
+            .bind(job_id)
+            .bind(index as i64)
+            .bind(&item.from_address)
+            .bind(&item.to_address)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(job_id)
+    }
+
+    /// 根据ID获取任务
+    pub async fn get_job(&self, job_id: i64) -> Result<Option<TransferJob>> {
+        let job = sqlx::query_as::<_, TransferJob>(
+            "SELECT * FROM transfer_jobs WHERE id = ?"
+        )
+        .bind(job_id)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// 获取所有任务（最新的在前）
+    pub async fn list_jobs(&self) -> Result<Vec<TransferJob>> {
+        let jobs = sqlx::query_as::<_, TransferJob>(
+            "SELECT * FROM transfer_jobs ORDER BY id DESC"
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// 获取任务的全部明细（按原始顺序）
+    pub async fn get_job_items(&self, job_id: i64) -> Result<Vec<TransferJobItem>> {
+        let items = sqlx::query_as::<_, TransferJobItem>(
+            "SELECT * FROM transfer_job_items WHERE job_id = ? ORDER BY item_index ASC"
+        )
+        .bind(job_id)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// 根据ID获取单条明细
+    pub async fn get_item(&self, item_id: i64) -> Result<Option<TransferJobItem>> {
+        let item = sqlx::query_as::<_, TransferJobItem>(
+            "SELECT * FROM transfer_job_items WHERE id = ?"
+        )
+        .bind(item_id)
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(item)
+    }
+
+    /// 更新任务状态
+    pub async fn update_job_status(&self, job_id: i64, status: &str) -> Result<()> {
+        sqlx::query("UPDATE transfer_jobs SET status = ?, updated_at = ? WHERE id = ?")
+            .bind(status)
+            .bind(Utc::now())
+            .bind(job_id)
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 记录一次发送尝试
+    pub async fn increment_attempt(&self, item_id: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE transfer_job_items SET attempt_count = attempt_count + 1, updated_at = ? WHERE id = ?"
+        )
+        .bind(Utc::now())
+        .bind(item_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 广播前记录已签名交易，标记为发送中
+    ///
+    /// 仅当该行仍为待处理且尚未记录交易哈希时才会写入，返回是否写入成功。
+    pub async fn mark_item_sending(&self, item_id: i64, tx_hash: &str, nonce: u64, raw_tx: &str) -> Result<bool> {
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE transfer_job_items SET status = 'sending', tx_hash = ?, nonce = ?, raw_tx = ?, error_msg = NULL, updated_at = ?
+            WHERE id = ? AND status = 'pending' AND tx_hash IS NULL
+            "#
+        )
+        .bind(tx_hash)
+        .bind(nonce as i64)
+        .bind(raw_tx)
+        .bind(Utc::now())
+        .bind(item_id)
+        .execute(self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    /// 标记明细已提交上链
+    ///
+    /// 仅当该行处于发送中且记录的交易哈希一致时才会写入，返回是否写入成功。
+    pub async fn mark_item_submitted(&self, item_id: i64, tx_hash: &str) -> Result<bool> {
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE transfer_job_items SET status = 'submitted', error_msg = NULL, updated_at = ?
+            WHERE id = ? AND status = 'sending' AND tx_hash = ?
+            "#
+        )
+        .bind(Utc::now())
+        .bind(item_id)
+        .bind(tx_hash)
+        .execute(self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected > 0)
+    }
+
+    /// 节点明确拒绝了发送中的交易：清除签名记录并标记失败，之后可以重试
+    pub async fn mark_item_rejected(&self, item_id: i64, error_msg: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transfer_job_items SET status = 'failed', tx_hash = NULL, nonce = NULL, raw_tx = NULL, error_msg = ?, updated_at = ?
+            WHERE id = ? AND status = 'sending'
+            "#
+        )
+        .bind(error_msg)
+        .bind(Utc::now())
+        .bind(item_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记明细已确认（仅对已提交的行生效）
+    pub async fn mark_item_confirmed(&self, item_id: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transfer_job_items SET status = 'confirmed', error_msg = NULL, updated_at = ?
+            WHERE id = ? AND tx_hash IS NOT NULL
+            "#
+        )
+        .bind(Utc::now())
+        .bind(item_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记明细失败（保留已有的交易哈希）
+    pub async fn mark_item_failed(&self, item_id: i64, error_msg: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE transfer_job_items SET status = 'failed', error_msg = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(error_msg)
+        .bind(Utc::now())
+        .bind(item_id)
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 将未上链的失败明细重置为待处理，返回重置的行数
+    pub async fn reset_failed_items(&self, job_id: i64) -> Result<u64> {
+        let rows_affected = sqlx::query(
+            r#"
+            UPDATE transfer_job_items SET status = 'pending', updated_at = ?
+            WHERE job_id = ? AND status = 'failed' AND tx_hash IS NULL
+            "#
+        )
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
+    /// 删除任务及其全部明细
+    pub async fn delete_job(&self, job_id: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM transfer_job_items WHERE job_id = ?")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM transfer_jobs WHERE id = ?")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 交易加速/取消记录服务
+pub struct TxReplacementService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TxReplacementService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 记录一笔替换交易
+    pub async fn record(
+        &self,
+        chain_key: &str,
+        original_hash: &str,
+        replacement_hash: &str,
+        from_address: &str,
+        nonce: u64,
+        kind: &str,
+    ) -> Result<i64> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO tx_replacements (
+                chain_key, original_hash, replacement_hash, from_address, nonce, kind, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#
+        )
+        .bind(chain_key)
+        .bind(original_hash.to_lowercase())
+        .bind(replacement_hash.to_lowercase())
+        .bind(from_address)
+        .bind(nonce as i64)
+        .bind(kind)
+        .bind(Utc::now())
+        .fetch_one(self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// 获取某笔交易的全部替换交易（包含对替换交易的再次替换），按创建顺序排列
+    pub async fn get_replacements(&self, chain_key: &str, tx_hash: &str) -> Result<Vec<TxReplacement>> {
+        let replacements = sqlx::query_as::<_, TxReplacement>(
+            r#"
+            WITH RECURSIVE chain_of(hash) AS (
+                SELECT ?
+                UNION
+                SELECT r.replacement_hash FROM tx_replacements r
+                JOIN chain_of c ON r.original_hash = c.hash
+                WHERE r.chain_key = ?
+            )
+            SELECT r.* FROM tx_replacements r
+            JOIN chain_of c ON r.original_hash = c.hash
+            WHERE r.chain_key = ?
+            ORDER BY r.id ASC
+            "#
+        )
+        .bind(tx_hash.to_lowercase())
+        .bind(chain_key)
+        .bind(chain_key)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(replacements)
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::database::models::*;
+use chrono::Utc;
+
+/// 钱包活跃度缓存服务
+pub struct WalletActivityService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> WalletActivityService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 获取地址的缓存记录
+    pub async fn get(&self, chain_key: &str, address: &str) -> Result<Option<WalletActivityCache>> {
+        let cache = sqlx::query_as::<_, WalletActivityCache>(
+            "SELECT * FROM wallet_activity_cache WHERE chain_key = ? AND address = ?"
+        )
+        .bind(chain_key)
+        .bind(address.to_lowercase())
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(cache)
+    }
+
+    /// 写入或更新地址的缓存记录
+    pub async fn upsert(&self, cache: &WalletActivityCache) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO wallet_activity_cache (
+                chain_key, address, nonce, last_block, tx_count, first_tx_at, last_tx_at,
+                contracts_json, active_days_json, gas_spent_wei, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(chain_key, address) DO UPDATE SET
+                nonce = excluded.nonce,
+                last_block = excluded.last_block,
+                tx_count = excluded.tx_count,
+                first_tx_at = excluded.first_tx_at,
+                last_tx_at = excluded.last_tx_at,
+                contracts_json = excluded.contracts_json,
+                active_days_json = excluded.active_days_json,
+                gas_spent_wei = excluded.gas_spent_wei,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(&cache.chain_key)
+        .bind(cache.address.to_lowercase())
+        .bind(cache.nonce)
+        .bind(cache.last_block)
+        .bind(cache.tx_count)
+        .bind(cache.first_tx_at)
+        .bind(cache.last_tx_at)
+        .bind(&cache.contracts_json)
+        .bind(&cache.active_days_json)
+        .bind(&cache.gas_spent_wei)
+        .bind(Utc::now())
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
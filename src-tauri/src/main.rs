@@ -300,6 +300,7 @@ async fn main() {
             wallets_tool::ecosystems::ethereum::chain_config::update_coin,
             wallets_tool::ecosystems::ethereum::chain_config::update_chain_pic_urls,
             wallets_tool::ecosystems::ethereum::chain_config::update_token_abi,
+            wallets_tool::ecosystems::ethereum::chain_config::update_token_price_source,
             // chain management commands
             wallets_tool::ecosystems::ethereum::chain_config::add_chain,
             wallets_tool::ecosystems::ethereum::chain_config::update_chain,
@@ -318,6 +319,20 @@ async fn main() {
             // balance query functions
             wallets_tool::ecosystems::ethereum::simple_balance_query::query_balances_simple,
             wallets_tool::ecosystems::ethereum::simple_balance_query::query_balances_with_updates,
+            wallets_tool::ecosystems::ethereum::simple_balance_query::query_multi_token_balances,
+            wallets_tool::ecosystems::ethereum::activity_profile::query_wallet_activity_profiles,
+            wallets_tool::ecosystems::ethereum::token_discovery::discover_tokens,
+            wallets_tool::ecosystems::ethereum::token_discovery::add_discovered_tokens,
+            wallets_tool::ecosystems::ethereum::nft::query_nft_holdings,
+            wallets_tool::ecosystems::ethereum::nft::nft_transfer,
+            wallets_tool::ecosystems::ethereum::balance_snapshot::list_balance_snapshots,
+            wallets_tool::ecosystems::ethereum::balance_snapshot::get_balance_snapshot,
+            wallets_tool::ecosystems::ethereum::balance_snapshot::update_balance_snapshot_label,
+            wallets_tool::ecosystems::ethereum::balance_snapshot::delete_balance_snapshot,
+            wallets_tool::ecosystems::ethereum::balance_snapshot::diff_balance_snapshots,
+            wallets_tool::ecosystems::ethereum::pricing::query_token_usd_prices,
+            wallets_tool::ecosystems::ethereum::ens::resolve_recipient_names,
+            wallets_tool::ecosystems::ethereum::ens::release_recipient_names,
             wallets_tool::ecosystems::ethereum::simple_balance_query::stop_balance_query,
             wallets_tool::ecosystems::ethereum::simple_balance_query::reset_balance_query_stop,
             // window management functions
@@ -330,6 +345,16 @@ async fn main() {
             database::reload_database,
             database::check_database_schema,
             database::export_database_to_init_sql,
+            // address book functions
+            wallets_tool::address_book::list_address_book,
+            wallets_tool::address_book::add_address_book_entry,
+            wallets_tool::address_book::update_address_book_entry,
+            wallets_tool::address_book::delete_address_book_entries,
+            wallets_tool::address_book::list_address_groups,
+            wallets_tool::address_book::get_address_group_addresses,
+            wallets_tool::address_book::import_address_book_csv,
+            // address validation functions
+            wallets_tool::address_validation::validate_addresses,
             // transfer functions
             wallets_tool::transfer::base_coin_transfer,
             wallets_tool::transfer::base_coin_transfer_fast,
@@ -339,6 +364,20 @@ async fn main() {
             wallets_tool::transfer::check_wallet_recent_transfers,
             wallets_tool::transfer::stop_transfer,
             wallets_tool::transfer::reset_transfer_stop,
+            // transfer job functions
+            wallets_tool::ecosystems::ethereum::transfer_job::create_transfer_job,
+            wallets_tool::ecosystems::ethereum::transfer_job::resume_transfer_job,
+            wallets_tool::ecosystems::ethereum::transfer_job::get_transfer_job,
+            wallets_tool::ecosystems::ethereum::transfer_job::list_transfer_jobs,
+            wallets_tool::ecosystems::ethereum::transfer_job::delete_transfer_job,
+            // nonce manager functions
+            wallets_tool::ecosystems::ethereum::nonce_manager::reset_nonce_manager,
+            // tx replacement functions
+            wallets_tool::ecosystems::ethereum::tx_replacement::speed_up_transaction,
+            wallets_tool::ecosystems::ethereum::tx_replacement::cancel_transaction,
+            // dry run functions
+            wallets_tool::ecosystems::ethereum::dry_run::base_coin_transfer_dry_run,
+            wallets_tool::ecosystems::ethereum::dry_run::token_transfer_dry_run,
             // solana transfer functions
             wallets_tool::ecosystems::solana::transfer::sol_transfer,
             wallets_tool::ecosystems::solana::transfer::sol_token_transfer,
@@ -347,6 +386,14 @@ async fn main() {
             wallets_tool::ecosystems::solana::transfer::sol_check_recent_transfers,
             wallets_tool::ecosystems::solana::transfer::sol_check_transactions_status_batch,
             wallets_tool::ecosystems::solana::transfer::sol_query_balances_with_updates,
+            wallets_tool::ecosystems::solana::sns::sol_resolve_recipient_names,
+            wallets_tool::ecosystems::solana::sns::sol_release_recipient_names,
+            wallets_tool::ecosystems::solana::lookup_table::sol_create_lookup_table,
+            wallets_tool::ecosystems::solana::lookup_table::sol_extend_lookup_table,
+            wallets_tool::ecosystems::solana::lookup_table::sol_list_lookup_tables,
+            wallets_tool::ecosystems::solana::batch_transfer::sol_batch_transfer,
+            wallets_tool::ecosystems::solana::priority_fee::sol_estimate_priority_fees,
+            wallets_tool::ecosystems::solana::keypair::sol_derive_pubkeys,
             wallets_tool::ecosystems::solana::provider::test_solana_rpc_connection,
 
             // token transfer functions
@@ -0,0 +1,278 @@
+use serde::Serialize;
+use crate::database::{get_database_manager, address_book_service::AddressBookService, chain_service::ChainService};
+use crate::database::models::{AddressBookEntry, AddressBookEntryRequest, AddressGroup};
+use crate::wallets_tool::address_validation::normalize_address;
+
+/// CSV导入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressBookImportResult {
+    pub inserted: usize,
+    pub updated: usize,
+    pub errors: Vec<String>, // 格式错误的行，带行号
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+fn normalize_request(mut request: AddressBookEntryRequest) -> Result<AddressBookEntryRequest, String> {
+    request.ecosystem = request.ecosystem.trim().to_lowercase();
+    request.address = normalize_address(&request.ecosystem, &request.address)?;
+    request.label = non_empty(request.label);
+    request.group_name = non_empty(request.group_name);
+    let mut tags: Vec<String> = Vec::new();
+    for tag in request.tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    request.tags = tags;
+    Ok(request)
+}
+
+// CSV列的位置：address, label, group, tags
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CsvColumns {
+    address: usize,
+    label: Option<usize>,
+    group: Option<usize>,
+    tags: Option<usize>,
+}
+
+impl CsvColumns {
+    const DEFAULT: CsvColumns = CsvColumns { address: 0, label: Some(1), group: Some(2), tags: Some(3) };
+
+    // 首行包含地址列名时作为表头解析，否则按默认列顺序
+    fn from_header(fields: &[String]) -> Option<Self> {
+        let find = |names: &[&str]| {
+            fields.iter().position(|field| names.iter().any(|name| field.eq_ignore_ascii_case(name)))
+        };
+        Some(CsvColumns {
+            address: find(&["address", "地址"])?,
+            label: find(&["label", "备注", "名称"]),
+            group: find(&["group", "group_name", "分组"]),
+            tags: find(&["tags", "tag", "标签"]),
+        })
+    }
+}
+
+/// 解析CSV内容，返回请求列表与格式错误（带行号）；标签之间用 ; 或 | 分隔
+///
+/// 字段按 RFC 4180 解析，带引号的字段可以包含逗号和换行。
+fn parse_csv(
+    content: &str,
+    ecosystem: &str,
+    default_group: Option<&str>,
+) -> (Vec<AddressBookEntryRequest>, Vec<String>) {
+    // Excel 导出的 UTF-8 CSV 带 BOM
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    // csv 的行号不计空行，记录位置也停在前面的空行上，按字节偏移换算实际行号
+    let line_of = |position: Option<&csv::Position>| {
+        let Some(position) = position else { return 0 };
+        let bytes = content.as_bytes();
+        let mut start = position.byte() as usize;
+        while bytes.get(start).is_some_and(|b| matches!(b, b'\r' | b'\n')) {
+            start += 1;
+        }
+        bytes[..start].iter().filter(|&&b| b == b'\n').count() + 1
+    };
+
+    let mut requests = Vec::new();
+    let mut errors = Vec::new();
+    let mut columns = None;
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = line_of(e.position());
+                errors.push(format!("第 {line} 行: CSV格式错误: {e}"));
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line = line_of(record.position());
+        let fields: Vec<String> = record.iter().map(str::to_string).collect();
+
+        // 首个非空行包含地址列名时作为表头，否则按默认列顺序
+        let columns = match columns {
+            Some(columns) => columns,
+            None => match CsvColumns::from_header(&fields) {
+                Some(header) => {
+                    columns = Some(header);
+                    continue;
+                }
+                None => *columns.insert(CsvColumns::DEFAULT),
+            },
+        };
+
+        let column = |position: Option<usize>| position.and_then(|i| fields.get(i)).cloned();
+        let request = AddressBookEntryRequest {
+            address: column(Some(columns.address)).unwrap_or_default(),
+            ecosystem: ecosystem.to_string(),
+            label: column(columns.label),
+            group_name: non_empty(column(columns.group)).or_else(|| default_group.map(str::to_string)),
+            tags: column(columns.tags)
+                .map(|tags| tags.split([';', '|']).map(str::to_string).collect())
+                .unwrap_or_default(),
+        };
+        match normalize_request(request) {
+            Ok(request) => requests.push(request),
+            Err(e) => errors.push(format!("第 {line} 行: {e}")),
+        }
+    }
+    (requests, errors)
+}
+
+/// 把地址簿分组展开为地址列表，生态由链配置决定
+pub async fn resolve_group_addresses(chain_key: &str, group_name: &str) -> Result<Vec<String>, String> {
+    let pool = get_database_manager().get_pool();
+    let chain = ChainService::new(pool)
+        .get_chain_by_key(chain_key)
+        .await
+        .map_err(|e| format!("查询链配置失败: {e}"))?
+        .ok_or_else(|| format!("链不存在: {chain_key}"))?;
+    let addresses = AddressBookService::new(pool)
+        .get_group_addresses(&chain.ecosystem, group_name.trim())
+        .await
+        .map_err(|e| format!("查询地址分组失败: {e}"))?;
+    if addresses.is_empty() {
+        return Err(format!("地址分组为空或不存在: {group_name}"));
+    }
+    Ok(addresses)
+}
+
+/// 单笔转账命令使用地址簿分组时，按转账序号（从1开始）取分组中对应的接收地址；未指定分组时返回 None
+pub async fn group_recipient(chain_key: &str, group_name: Option<&str>, index: usize) -> Result<Option<String>, String> {
+    let Some(group_name) = group_name.map(str::trim).filter(|group| !group.is_empty()) else {
+        return Ok(None);
+    };
+    let addresses = resolve_group_addresses(chain_key, group_name).await?;
+    let count = addresses.len();
+    addresses
+        .into_iter()
+        .nth(index.saturating_sub(1))
+        .map(Some)
+        .ok_or_else(|| format!("地址分组 {group_name} 只有 {count} 个地址，没有第 {index} 个接收地址"))
+}
+
+// Tauri命令：获取地址簿，可按生态、分组、标签过滤
+#[tauri::command]
+pub async fn list_address_book(
+    ecosystem: Option<String>,
+    group_name: Option<String>,
+    tag: Option<String>,
+) -> Result<Vec<AddressBookEntry>, String> {
+    AddressBookService::new(get_database_manager().get_pool())
+        .list_entries(ecosystem.as_deref(), group_name.as_deref(), tag.as_deref())
+        .await
+        .map_err(|e| format!("查询地址簿失败: {e}"))
+}
+
+// Tauri命令：新增地址（地址已存在时更新）
+#[tauri::command]
+pub async fn add_address_book_entry(request: AddressBookEntryRequest) -> Result<i64, String> {
+    let request = normalize_request(request)?;
+    AddressBookService::new(get_database_manager().get_pool())
+        .upsert_entry(&request)
+        .await
+        .map_err(|e| format!("保存地址失败: {e}"))
+}
+
+// Tauri命令：修改地址
+#[tauri::command]
+pub async fn update_address_book_entry(id: i64, request: AddressBookEntryRequest) -> Result<(), String> {
+    let request = normalize_request(request)?;
+    AddressBookService::new(get_database_manager().get_pool())
+        .update_entry(id, &request)
+        .await
+        .map_err(|e| format!("修改地址失败: {e}"))
+}
+
+// Tauri命令：批量删除地址
+#[tauri::command]
+pub async fn delete_address_book_entries(ids: Vec<i64>) -> Result<u64, String> {
+    AddressBookService::new(get_database_manager().get_pool())
+        .delete_entries(&ids)
+        .await
+        .map_err(|e| format!("删除地址失败: {e}"))
+}
+
+// Tauri命令：获取分组列表
+#[tauri::command]
+pub async fn list_address_groups(ecosystem: Option<String>) -> Result<Vec<AddressGroup>, String> {
+    AddressBookService::new(get_database_manager().get_pool())
+        .list_groups(ecosystem.as_deref())
+        .await
+        .map_err(|e| format!("查询地址分组失败: {e}"))
+}
+
+// Tauri命令：获取某链上分组内的地址
+#[tauri::command]
+pub async fn get_address_group_addresses(chain: String, group_name: String) -> Result<Vec<String>, String> {
+    resolve_group_addresses(&chain, &group_name).await
+}
+
+// Tauri命令：从CSV内容批量导入地址，格式错误的行跳过并返回错误信息
+#[tauri::command]
+pub async fn import_address_book_csv(
+    content: String,
+    ecosystem: String,
+    default_group: Option<String>,
+) -> Result<AddressBookImportResult, String> {
+    let ecosystem = ecosystem.trim().to_lowercase();
+    let default_group = non_empty(default_group);
+    let (requests, errors) = parse_csv(&content, &ecosystem, default_group.as_deref());
+    let (inserted, updated) = AddressBookService::new(get_database_manager().get_pool())
+        .import_entries(&requests)
+        .await
+        .map_err(|e| format!("导入地址失败: {e}"))?;
+    Ok(AddressBookImportResult { inserted, updated, errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "地址,备注,分组,标签\n\
+            0x52908400098527886E0F7030069857D2E4169EE7,\"主钱包, 1号\",main,airdrop;hot\n\
+            \n\
+            0xinvalid,,,\n\
+            0x8617E340B3D01FA5F11F306F4090FD50E238070D,,,hot|hot\n";
+        let (requests, errors) = parse_csv(content, "evm", Some("default"));
+        assert_eq!(requests.len(), 2);
+        // 与批量地址校验一致，EVM地址保存为EIP-55校验和格式
+        assert_eq!(requests[0].address, "0x52908400098527886E0F7030069857D2E4169EE7");
+        assert_eq!(requests[1].address, "0x8617E340B3D01FA5F11F306F4090FD50E238070D");
+        assert_eq!(requests[0].label.as_deref(), Some("主钱包, 1号"));
+        assert_eq!(requests[0].group_name.as_deref(), Some("main"));
+        assert_eq!(requests[0].tags, ["airdrop", "hot"]);
+        assert_eq!(requests[1].group_name.as_deref(), Some("default"));
+        assert_eq!(requests[1].tags, ["hot"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("第 4 行"), "{}", errors[0]);
+
+        // 没有表头时按 地址,备注,分组,标签 的顺序
+        let (requests, _) = parse_csv("0x8617E340B3D01FA5F11F306F4090FD50E238070D,b,g", "evm", None);
+        assert_eq!(requests[0].group_name.as_deref(), Some("g"));
+
+        // 引号内的换行属于同一字段，后续行号按实际行计算
+        let content = "\u{feff}address,label\n\
+            0x52908400098527886E0F7030069857D2E4169EE7,\"第一行\n第二行\"\n\
+            0x52908400098527886E0F7030069857d2E4169EE7,\n";
+        let (requests, errors) = parse_csv(content, "evm", None);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].label.as_deref(), Some("第一行\n第二行"));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("第 4 行"), "{}", errors[0]);
+    }
+}
@@ -0,0 +1,317 @@
+use alloy_primitives::{Address, address};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::database::{get_database_manager, rpc_service::RpcService};
+use crate::wallets_tool::ecosystems::ethereum::ens::is_ens_name;
+use crate::wallets_tool::ecosystems::ethereum::rpc_batch::{RpcCall, http_client, send_batch};
+use crate::wallets_tool::ecosystems::solana::sns::is_sns_name;
+
+// 常见的EVM销毁地址（全零地址单独判断）
+const EVM_BURN_ADDRESSES: [Address; 1] = [address!("000000000000000000000000000000000000dEaD")];
+
+// Solana 的系统程序地址（全零公钥）与官方销毁地址
+const SOLANA_BURN_ADDRESSES: [&str; 2] = [
+    "11111111111111111111111111111111",
+    "1nc1nerator11111111111111111111111111111111",
+];
+
+// EIP-7702 委托后的EOA账户代码前缀，仍视为普通钱包
+const EIP7702_DELEGATION_PREFIX: &str = "0xef0100";
+
+/// 地址校验参数，chain 为空时不检查合约地址
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddressValidationParams {
+    pub ecosystem: String, // "evm" 或 "solana"
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub chain: Option<String>,
+    #[serde(default)]
+    pub window_id: Option<String>,
+}
+
+/// 单个问题，level 为 "error"（不能用于转账）或 "warning"（需要确认）
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AddressIssue {
+    pub code: String,
+    pub level: String,
+    pub message: String,
+}
+
+impl AddressIssue {
+    fn error(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), level: "error".to_string(), message: message.into() }
+    }
+
+    fn warning(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), level: "warning".to_string(), message: message.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressValidationItem {
+    pub index: usize,
+    pub input: String,
+    pub normalized: Option<String>, // EVM为EIP-55校验和格式，Solana为base58
+    pub valid: bool,
+    pub issues: Vec<AddressIssue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressValidationReport {
+    pub items: Vec<AddressValidationItem>,
+    pub valid_count: usize,
+    pub invalid_count: usize,
+    pub warning_count: usize,
+    pub normalized: Vec<String>, // 去重后的有效地址，保持输入顺序
+}
+
+fn looks_like_evm(value: &str) -> bool {
+    let hex = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"));
+    hex.is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn looks_like_solana(value: &str) -> bool {
+    Pubkey::from_str(value).is_ok()
+}
+
+// 校验单个EVM地址的格式，返回规范化地址与问题列表
+fn check_evm(value: &str) -> (Option<String>, Vec<AddressIssue>) {
+    if is_ens_name(value) {
+        return (None, vec![AddressIssue::warning("name", "ENS名称，将在转账开始前解析")]);
+    }
+    if !looks_like_evm(value) {
+        let issue = if looks_like_solana(value) {
+            AddressIssue::error("wrong_ecosystem", "这是Solana地址，不能用于EVM链")
+        } else {
+            AddressIssue::error("invalid", "不是有效的EVM地址（需要0x开头的40位十六进制）")
+        };
+        return (None, vec![issue]);
+    }
+
+    let hex = &value[2..];
+    let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    let address: Address = match value.parse() {
+        Ok(address) => address,
+        Err(e) => return (None, vec![AddressIssue::error("invalid", format!("地址解析失败: {e}"))]),
+    };
+
+    let mut issues = Vec::new();
+    // 全小写/全大写地址不带校验信息；大小写混合但校验和不符，多半是手误改错了字符
+    if mixed_case && Address::parse_checksummed(value, None).is_err() {
+        issues.push(AddressIssue::error(
+            "checksum_mismatch",
+            format!("EIP-55校验和不匹配，可能存在输入错误，正确格式应为 {}", address.to_checksum(None)),
+        ));
+    }
+    if address.is_zero() {
+        issues.push(AddressIssue::error("zero_address", "零地址，转入的资产将无法取回"));
+    } else if EVM_BURN_ADDRESSES.contains(&address) {
+        issues.push(AddressIssue::error("burn_address", "销毁地址，转入的资产将无法取回"));
+    }
+    (Some(address.to_checksum(None)), issues)
+}
+
+// 校验单个Solana地址的格式
+fn check_solana(value: &str) -> (Option<String>, Vec<AddressIssue>) {
+    if is_sns_name(value) {
+        return (None, vec![AddressIssue::warning("name", ".sol域名，将在转账开始前解析")]);
+    }
+    if looks_like_evm(value) {
+        return (None, vec![AddressIssue::error("wrong_ecosystem", "这是EVM地址，不能用于Solana")]);
+    }
+    let pubkey = match Pubkey::from_str(value) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return (None, vec![AddressIssue::error("invalid", "不是有效的Solana地址（需要32字节的base58编码）")]),
+    };
+
+    let mut issues = Vec::new();
+    if SOLANA_BURN_ADDRESSES.contains(&value) {
+        issues.push(AddressIssue::error("burn_address", "系统程序或销毁地址，转入的资产将无法取回"));
+    } else if !pubkey.is_on_curve() {
+        issues.push(AddressIssue::warning("off_curve", "地址不在ed25519曲线上（程序派生地址），没有对应私钥"));
+    }
+    (Some(pubkey.to_string()), issues)
+}
+
+/// 校验并规范化单个地址，格式与批量校验结果一致（EVM为EIP-55校验和格式，Solana为base58）；
+/// 存在错误级别的问题或输入为域名时返回错误
+pub fn normalize_address(ecosystem: &str, address: &str) -> Result<String, String> {
+    let address = address.trim();
+    let (normalized, issues) = match ecosystem {
+        "evm" => check_evm(address),
+        "solana" => check_solana(address),
+        other => return Err(format!("不支持的生态: {other}")),
+    };
+    if let Some(issue) = issues.iter().find(|issue| issue.level == "error") {
+        return Err(format!("{address}: {}", issue.message));
+    }
+    normalized.ok_or_else(|| format!("{address}: 需要填写地址，不支持域名"))
+}
+
+// 逐行做格式校验并标记重复地址
+fn validate_syntax(ecosystem: &str, addresses: &[String]) -> Result<Vec<AddressValidationItem>, String> {
+    let check = match ecosystem {
+        "evm" => check_evm,
+        "solana" => check_solana,
+        other => return Err(format!("不支持的生态: {other}")),
+    };
+
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    Ok(addresses
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let value = input.trim();
+            let (normalized, mut issues) = if value.is_empty() {
+                (None, vec![AddressIssue::error("empty", "地址为空")])
+            } else {
+                check(value)
+            };
+
+            // EVM地址不区分大小写，按规范化结果判断重复；名称按小写判断
+            let key = normalized.clone().unwrap_or_else(|| value.to_lowercase());
+            if !value.is_empty() {
+                match first_seen.get(&key) {
+                    Some(first) => issues.push(AddressIssue::warning("duplicate", format!("与第 {} 行重复", first + 1))),
+                    None => {
+                        first_seen.insert(key, index);
+                    }
+                }
+            }
+
+            AddressValidationItem {
+                index,
+                input: input.clone(),
+                valid: issues.iter().all(|issue| issue.level != "error"),
+                normalized,
+                issues,
+            }
+        })
+        .collect())
+}
+
+// 批量 eth_getCode，标记合约地址
+async fn flag_contracts(chain: &str, window_id: Option<&str>, items: &mut [AddressValidationItem]) -> Result<(), String> {
+    let targets: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.valid && !item.issues.iter().any(|issue| issue.code == "duplicate"))
+        .filter(|(_, item)| item.normalized.is_some())
+        .map(|(index, _)| index)
+        .collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let (rpc_url, max_batch_size) = RpcService::new(get_database_manager().get_pool())
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+    let calls: Vec<RpcCall> = targets
+        .iter()
+        .map(|index| RpcCall::new("eth_getCode", json!([items[*index].normalized, "latest"])))
+        .collect();
+    let results = send_batch(&http_client(window_id), &rpc_url, &calls, max_batch_size).await;
+
+    for (index, result) in targets.into_iter().zip(results) {
+        let item = &mut items[index];
+        match result {
+            Ok(code) => {
+                let code = code.as_str().unwrap_or("0x").to_lowercase();
+                if code != "0x" && !code.starts_with(EIP7702_DELEGATION_PREFIX) {
+                    item.issues.push(AddressIssue::warning("contract", "合约地址，请确认合约能够接收该资产"));
+                }
+            }
+            Err(e) => item.issues.push(AddressIssue::warning("code_check_failed", format!("查询合约代码失败: {e}"))),
+        }
+    }
+    Ok(())
+}
+
+// Tauri命令：批量校验并规范化地址，在转账开始前报告全部问题
+#[tauri::command]
+pub async fn validate_addresses(params: AddressValidationParams) -> Result<AddressValidationReport, String> {
+    let ecosystem = params.ecosystem.trim().to_lowercase();
+    let mut items = validate_syntax(&ecosystem, &params.addresses)?;
+    if ecosystem == "evm" && let Some(chain) = params.chain.as_deref().filter(|chain| !chain.is_empty()) {
+        flag_contracts(chain, params.window_id.as_deref(), &mut items).await?;
+    }
+
+    let valid_count = items.iter().filter(|item| item.valid).count();
+    let warning_count = items
+        .iter()
+        .filter(|item| item.valid && !item.issues.is_empty())
+        .count();
+    let mut normalized: Vec<String> = Vec::new();
+    for item in items.iter().filter(|item| item.valid) {
+        if let Some(address) = &item.normalized
+            && !normalized.contains(address)
+        {
+            normalized.push(address.clone());
+        }
+    }
+
+    Ok(AddressValidationReport {
+        invalid_count: items.len() - valid_count,
+        valid_count,
+        warning_count,
+        normalized,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(item: &AddressValidationItem) -> Vec<&str> {
+        item.issues.iter().map(|issue| issue.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_evm() {
+        let addresses: Vec<String> = [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0x52908400098527886e0f7030069857d2e4169ee7",
+            "0x52908400098527886E0F7030069857D2E4169Ee7",
+            "0x0000000000000000000000000000000000000000",
+            "Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb",
+            "0x1234",
+            "",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let items = validate_syntax("evm", &addresses).unwrap();
+        assert!(items[0].valid && items[0].issues.is_empty());
+        assert_eq!(codes(&items[1]), ["duplicate"]);
+        assert_eq!(items[1].normalized.as_deref(), Some("0x52908400098527886E0F7030069857D2E4169EE7"));
+        assert_eq!(codes(&items[2]), ["checksum_mismatch", "duplicate"]);
+        assert_eq!(codes(&items[3]), ["zero_address"]);
+        assert_eq!(codes(&items[4]), ["wrong_ecosystem"]);
+        assert_eq!(codes(&items[5]), ["invalid"]);
+        assert_eq!(codes(&items[6]), ["empty"]);
+    }
+
+    #[test]
+    fn test_validate_solana() {
+        let addresses: Vec<String> = [
+            "Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb",
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "11111111111111111111111111111111",
+            "bonfida.sol",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let items = validate_syntax("solana", &addresses).unwrap();
+        assert_eq!(codes(&items[0]), ["off_curve"]);
+        assert_eq!(codes(&items[1]), ["wrong_ecosystem"]);
+        assert_eq!(codes(&items[2]), ["burn_address"]);
+        assert_eq!(codes(&items[3]), ["name"]);
+        assert!(items[3].valid);
+    }
+}
@@ -0,0 +1,432 @@
+use alloy_primitives::{Address, U256};
+use chrono::{DateTime, Datelike, Utc};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+use tauri::Emitter;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Duration};
+use crate::database::{get_database_manager, chain_service::ChainService, rpc_service::RpcService};
+use crate::database::models::WalletActivityCache;
+use crate::database::wallet_activity_service::WalletActivityService;
+use super::alloy_utils::format_units;
+use super::rpc_batch::{RpcCall, http_client, parse_quantity, send_batch};
+
+// 区块浏览器 txlist 单页返回的最大条数
+const EXPLORER_PAGE_SIZE: usize = 1000;
+
+// 区块浏览器请求失败（多为速率限制）时的最大重试次数
+const EXPLORER_MAX_RETRIES: u32 = 3;
+
+// 活跃度查询参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityProfileParams {
+    pub chain: String,
+    pub addresses: Vec<String>,
+    #[serde(default = "default_thread_count")]
+    pub thread_count: usize,
+    #[serde(default)]
+    pub window_id: Option<String>,
+    #[serde(default)]
+    pub query_id: Option<String>,
+}
+
+fn default_thread_count() -> usize {
+    3
+}
+
+// 单个钱包的活跃度画像（只统计该地址发出的交易）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityProfile {
+    pub address: String,
+    pub nonce: Option<u64>,
+    pub tx_count: u64,
+    pub first_tx_at: Option<i64>, // Unix时间戳（秒）
+    pub last_tx_at: Option<i64>,
+    pub distinct_contracts: usize,
+    pub active_days: usize,
+    pub active_weeks: usize,
+    pub active_months: usize,
+    pub gas_spent: String, // 平台币数量
+    pub from_cache: bool,
+    pub exec_status: String, // "1"=执行中, "2"=成功, "3"=失败
+    pub error_msg: Option<String>,
+}
+
+// 区块浏览器 txlist 返回的交易（字段均为字符串）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ExplorerTx {
+    block_number: String,
+    time_stamp: String,
+    hash: String,
+    from: String,
+    to: String,
+    input: String,
+    gas_used: String,
+    gas_price: String,
+}
+
+// 累计的活跃度统计，可与缓存互相转换以便增量更新
+#[derive(Debug, Clone, Default)]
+struct ActivityState {
+    last_block: u64,
+    tx_count: u64,
+    first_tx_at: Option<i64>,
+    last_tx_at: Option<i64>,
+    contracts: BTreeSet<String>,
+    active_days: BTreeSet<i64>,
+    gas_spent: U256,
+}
+
+impl ActivityState {
+    fn from_cache(cache: &WalletActivityCache) -> Self {
+        Self {
+            last_block: cache.last_block.max(0) as u64,
+            tx_count: cache.tx_count.max(0) as u64,
+            first_tx_at: cache.first_tx_at,
+            last_tx_at: cache.last_tx_at,
+            contracts: serde_json::from_str(&cache.contracts_json).unwrap_or_default(),
+            active_days: serde_json::from_str(&cache.active_days_json).unwrap_or_default(),
+            gas_spent: cache.gas_spent_wei.parse().unwrap_or_default(),
+        }
+    }
+
+    fn to_cache(&self, chain: &str, address: &str, nonce: u64) -> WalletActivityCache {
+        WalletActivityCache {
+            id: 0,
+            chain_key: chain.to_string(),
+            address: address.to_lowercase(),
+            nonce: self.synced_nonce(nonce) as i64,
+            last_block: self.last_block as i64,
+            tx_count: self.tx_count as i64,
+            first_tx_at: self.first_tx_at,
+            last_tx_at: self.last_tx_at,
+            contracts_json: serde_json::to_string(&self.contracts).unwrap_or_else(|_| "[]".to_string()),
+            active_days_json: serde_json::to_string(&self.active_days).unwrap_or_else(|_| "[]".to_string()),
+            gas_spent_wei: self.gas_spent.to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    // 统计到的发出交易数达到链上 nonce 才算同步完整；区块浏览器尚未索引最新交易时
+    // 记录实际统计到的数量，下次查询时 nonce 不一致会继续增量拉取
+    fn synced_nonce(&self, nonce: u64) -> u64 {
+        nonce.min(self.tx_count)
+    }
+
+    // 统计一笔本地址发出的交易
+    fn apply(&mut self, tx: &ExplorerTx) {
+        let block = tx.block_number.parse::<u64>().unwrap_or_default();
+        let timestamp = tx.time_stamp.parse::<i64>().unwrap_or_default();
+        self.last_block = self.last_block.max(block);
+        self.tx_count += 1;
+        self.first_tx_at = Some(self.first_tx_at.map_or(timestamp, |t| t.min(timestamp)));
+        self.last_tx_at = Some(self.last_tx_at.map_or(timestamp, |t| t.max(timestamp)));
+        self.active_days.insert(timestamp.div_euclid(86400));
+
+        // 带调用数据且有接收方的交易视为合约交互
+        let has_input = !tx.input.is_empty() && tx.input != "0x";
+        if has_input && !tx.to.is_empty() {
+            self.contracts.insert(tx.to.to_lowercase());
+        }
+
+        let gas_used = tx.gas_used.parse::<U256>().unwrap_or_default();
+        let gas_price = tx.gas_price.parse::<U256>().unwrap_or_default();
+        self.gas_spent = self.gas_spent.saturating_add(gas_used.saturating_mul(gas_price));
+    }
+
+    fn profile(&self, address: &str, nonce: u64, native_decimals: u8, from_cache: bool) -> ActivityProfile {
+        let dates: Vec<_> = self
+            .active_days
+            .iter()
+            .filter_map(|day| DateTime::<Utc>::from_timestamp(day * 86400, 0))
+            .map(|time| time.date_naive())
+            .collect();
+        let weeks: HashSet<_> = dates.iter().map(|date| (date.iso_week().year(), date.iso_week().week())).collect();
+        let months: HashSet<_> = dates.iter().map(|date| (date.year(), date.month())).collect();
+
+        ActivityProfile {
+            address: address.to_string(),
+            nonce: Some(nonce),
+            tx_count: self.tx_count,
+            first_tx_at: self.first_tx_at,
+            last_tx_at: self.last_tx_at,
+            distinct_contracts: self.contracts.len(),
+            active_days: self.active_days.len(),
+            active_weeks: weeks.len(),
+            active_months: months.len(),
+            gas_spent: format_units(self.gas_spent, native_decimals),
+            from_cache,
+            exec_status: "2".to_string(),
+            error_msg: None,
+        }
+    }
+}
+
+// 请求一页 txlist；无交易时浏览器返回 status "0" 和空数组
+async fn fetch_tx_page(client: &Client, scan_api: &str, address: &str, start_block: u64) -> Result<Vec<ExplorerTx>, String> {
+    let mut url = url::Url::parse(scan_api).map_err(|e| format!("区块浏览器API地址格式错误: {e}"))?;
+    url.query_pairs_mut()
+        .append_pair("module", "account")
+        .append_pair("action", "txlist")
+        .append_pair("address", address)
+        .append_pair("startblock", &start_block.to_string())
+        .append_pair("page", "1")
+        .append_pair("offset", &EXPLORER_PAGE_SIZE.to_string())
+        .append_pair("sort", "asc");
+
+    let mut retry_count = 0;
+    loop {
+        let response: Value = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("区块浏览器请求失败: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("区块浏览器响应解析失败: {e}"))?;
+
+        match response.get("result") {
+            Some(Value::Array(_)) => {
+                let result = response.get("result").cloned().unwrap_or_default();
+                return serde_json::from_value(result).map_err(|e| format!("交易列表解析失败: {e}"));
+            }
+            // 出错时 result 为错误描述，如 "Max rate limit reached"
+            other => {
+                let message = other.and_then(Value::as_str).unwrap_or("未知错误").to_string();
+                retry_count += 1;
+                if retry_count > EXPLORER_MAX_RETRIES {
+                    return Err(format!("区块浏览器返回错误: {message}"));
+                }
+                println!("[WARN] 区块浏览器返回错误，{retry_count}秒后重试: {message}");
+                sleep(Duration::from_secs(retry_count as u64)).await;
+            }
+        }
+    }
+}
+
+// 获取 start_block 之后本地址发出的全部交易；翻页时从上一页最后一个区块继续，并按哈希去重
+async fn fetch_outgoing_txs(client: &Client, scan_api: &str, address: &str, start_block: u64) -> Result<Vec<ExplorerTx>, String> {
+    let owner = address.to_lowercase();
+    let mut seen = HashSet::new();
+    let mut txs = Vec::new();
+    let mut start = start_block;
+    loop {
+        let page = fetch_tx_page(client, scan_api, address, start).await?;
+        let full_page = page.len() >= EXPLORER_PAGE_SIZE;
+        let last_block = page.last().and_then(|tx| tx.block_number.parse::<u64>().ok());
+        for tx in page {
+            if tx.from.to_lowercase() == owner && seen.insert(tx.hash.to_lowercase()) {
+                txs.push(tx);
+            }
+        }
+        match last_block {
+            // 同一区块内的交易超过一页时无法继续翻页，避免死循环
+            Some(block) if full_page && block > start => start = block,
+            _ => return Ok(txs),
+        }
+    }
+}
+
+// 批量查询所有地址的 nonce
+async fn fetch_nonces(chain: &str, addresses: &[Address], window_id: Option<&str>) -> Vec<Result<u64, String>> {
+    let rpc = RpcService::new(get_database_manager().get_pool())
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"));
+    let (rpc_url, max_batch_size) = match rpc {
+        Ok(rpc) => rpc,
+        Err(e) => return vec![Err(e); addresses.len()],
+    };
+    let calls: Vec<RpcCall> = addresses
+        .iter()
+        .map(|address| RpcCall::new("eth_getTransactionCount", json!([format!("{address:?}"), "latest"])))
+        .collect();
+    send_batch(&http_client(window_id), &rpc_url, &calls, max_batch_size)
+        .await
+        .into_iter()
+        .map(|result| {
+            let value = result?;
+            parse_quantity(&value).ok_or_else(|| format!("nonce格式错误: {value}"))
+        })
+        .collect()
+}
+
+// 计算单个地址的画像：nonce 未变化时直接使用缓存，否则只拉取缓存之后的新交易
+async fn build_profile(
+    chain: &str,
+    scan_api: &str,
+    native_decimals: u8,
+    address: &str,
+    nonce: u64,
+    client: &Client,
+) -> Result<ActivityProfile, String> {
+    let service = WalletActivityService::new(get_database_manager().get_pool());
+    let cache = service.get(chain, address).await.map_err(|e| format!("读取缓存失败: {e}"))?;
+
+    let (mut state, start_block) = match &cache {
+        Some(cache) if cache.nonce == nonce as i64 => {
+            return Ok(ActivityState::from_cache(cache).profile(address, nonce, native_decimals, true));
+        }
+        Some(cache) if cache.nonce < nonce as i64 => {
+            let state = ActivityState::from_cache(cache);
+            let start = state.last_block + 1;
+            (state, start)
+        }
+        // 无缓存或 nonce 变小（如链重置）时重新统计
+        _ => (ActivityState::default(), 0),
+    };
+
+    // 没有发出过交易的地址无需请求区块浏览器
+    if nonce > 0 {
+        for tx in fetch_outgoing_txs(client, scan_api, address, start_block).await? {
+            state.apply(&tx);
+        }
+    }
+
+    service
+        .upsert(&state.to_cache(chain, address, nonce))
+        .await
+        .map_err(|e| format!("写入缓存失败: {e}"))?;
+    Ok(state.profile(address, nonce, native_decimals, false))
+}
+
+// Tauri命令：查询钱包链上活跃度画像
+#[tauri::command]
+pub async fn query_wallet_activity_profiles<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    params: ActivityProfileParams,
+) -> Result<Vec<ActivityProfile>, String> {
+    let window_id = params.window_id.clone().unwrap_or_default();
+    let query_id = params.query_id.clone().unwrap_or_default();
+
+    let chain_service = ChainService::new(get_database_manager().get_pool());
+    let chain = chain_service
+        .get_chain_by_key(&params.chain)
+        .await
+        .map_err(|e| format!("获取链信息失败: {e}"))?
+        .ok_or_else(|| format!("链不存在: {}", params.chain))?;
+    let scan_api = chain
+        .scan_api
+        .filter(|api| !api.trim().is_empty())
+        .ok_or_else(|| format!("链 {} 未配置区块浏览器API（scan_api）", params.chain))?;
+    let native_decimals = chain.native_currency_decimals as u8;
+
+    let emit = |index: usize, profile: &ActivityProfile| {
+        if let Err(e) = app_handle.emit("activity_profile_update", json!({
+            "index": index,
+            "item": profile,
+            "window_id": window_id,
+            "query_id": query_id
+        })) {
+            println!("发送活跃度查询进度事件失败: {e}");
+        }
+    };
+
+    // 地址无法解析的项目直接标记失败，其余批量查询 nonce
+    let mut profiles: Vec<ActivityProfile> = params
+        .addresses
+        .iter()
+        .map(|address| ActivityProfile {
+            address: address.trim().to_string(),
+            exec_status: "1".to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let mut valid = Vec::new();
+    for (index, profile) in profiles.iter_mut().enumerate() {
+        match profile.address.parse::<Address>() {
+            Ok(address) => valid.push((index, address)),
+            Err(e) => {
+                profile.exec_status = "3".to_string();
+                profile.error_msg = Some(format!("地址格式错误: {e}"));
+            }
+        }
+        emit(index, profile);
+    }
+
+    let addresses: Vec<Address> = valid.iter().map(|(_, address)| *address).collect();
+    let nonces = fetch_nonces(&params.chain, &addresses, Some(&window_id)).await;
+
+    let client = http_client(Some(&window_id));
+    let semaphore = Semaphore::new(params.thread_count.clamp(1, 10));
+    let tasks = valid.into_iter().zip(nonces).map(|((index, _), nonce)| {
+        let address = profiles[index].address.clone();
+        let semaphore = &semaphore;
+        let client = &client;
+        let chain = params.chain.as_str();
+        let scan_api = scan_api.as_str();
+        let emit = &emit;
+        async move {
+            let result = match nonce {
+                Ok(nonce) => {
+                    let _permit = semaphore.acquire().await.unwrap();
+                    build_profile(chain, scan_api, native_decimals, &address, nonce, client).await
+                }
+                Err(e) => Err(format!("查询nonce失败: {e}")),
+            };
+            let profile = result.unwrap_or_else(|e| ActivityProfile {
+                address: address.clone(),
+                exec_status: "3".to_string(),
+                error_msg: Some(e),
+                ..Default::default()
+            });
+            emit(index, &profile);
+            (index, profile)
+        }
+    });
+
+    for (index, profile) in join_all(tasks).await {
+        profiles[index] = profile;
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(block: &str, timestamp: &str, to: &str, input: &str) -> ExplorerTx {
+        ExplorerTx {
+            block_number: block.to_string(),
+            time_stamp: timestamp.to_string(),
+            to: to.to_string(),
+            input: input.to_string(),
+            gas_used: "21000".to_string(),
+            gas_price: "1000000000".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_activity_state() {
+        let mut state = ActivityState::default();
+        // 2024-01-01 (周一) 两笔，2024-01-08 一笔，2024-02-01 一笔
+        state.apply(&tx("100", "1704067200", "0xAAaa", "0x"));
+        state.apply(&tx("101", "1704070800", "0xaaaa", "0xa9059cbb"));
+        state.apply(&tx("200", "1704672000", "0xbbbb", "0x095ea7b3"));
+        state.apply(&tx("300", "1706745600", "0xaaaa", "0xa9059cbb"));
+
+        let profile = state.profile("0x1", 4, 18, false);
+        assert_eq!(profile.tx_count, 4);
+        assert_eq!(profile.first_tx_at, Some(1704067200));
+        assert_eq!(profile.last_tx_at, Some(1706745600));
+        assert_eq!(profile.distinct_contracts, 2);
+        assert_eq!(profile.active_days, 3);
+        assert_eq!(profile.active_weeks, 3);
+        assert_eq!(profile.active_months, 2);
+        assert_eq!(profile.gas_spent, "0.000084");
+
+        // 缓存往返后继续累计
+        let restored = ActivityState::from_cache(&state.to_cache("eth", "0x1", 4));
+        assert_eq!(restored.last_block, 300);
+        assert_eq!(restored.gas_spent, state.gas_spent);
+        assert_eq!(restored.active_days, state.active_days);
+
+        // 区块浏览器落后于链上 nonce 时不缓存链上 nonce
+        assert_eq!(state.to_cache("eth", "0x1", 6).nonce, 4);
+    }
+}
@@ -1,4 +1,8 @@
 use alloy_primitives::U256;
+use alloy_signer_local::PrivateKeySigner;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use crate::wallets_tool::security::SecureMemory;
 
 pub fn parse_ether_to_wei_f64(ether: f64) -> Result<U256, String> {
     let wei = (ether * 1e18) as u128;
@@ -24,9 +28,201 @@ pub fn format_wei_to_gwei(wei: U256) -> String {
     format!("{gwei:.2}")
 }
 
-pub fn u256_to_f64(wei: U256) -> f64 {
-    let wei_str = wei.to_string();
-    wei_str.parse::<f64>().unwrap_or(0.0)
+/// 从加密存储的私钥解析签名者（可带或不带0x前缀）
+pub fn parse_signer(private_key: &SecureMemory) -> Result<PrivateKeySigner, String> {
+    private_key.use_secret(|pk| {
+        let pk = pk.trim();
+        if pk.is_empty() {
+            return Err("私钥不能为空！".to_string());
+        }
+        let pk = pk.strip_prefix("0x").or_else(|| pk.strip_prefix("0X")).unwrap_or(pk);
+        pk.parse::<PrivateKeySigner>().map_err(|e| e.to_string())
+    })
+    .map_err(|e| format!("私钥解密失败: {e}"))?
+    .map_err(|e| format!("私钥格式错误: {e}"))
+}
+
+/// 十进制金额与最小单位互转时的舍入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// 向零舍去（转出金额默认使用，保证不超过用户给定值）
+    Down,
+    /// 向上进位（用于剩余数量等下限值，保证不少于用户给定值）
+    Up,
+}
+
+/// 十进制金额，内部以字符串保存，避免经过 f64 丢失精度
+///
+/// 反序列化同时兼容字符串与数字（旧版前端与已保存的任务配置传的是数字），序列化始终输出字符串。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecimalAmount(pub String);
+
+impl DecimalAmount {
+    pub fn as_str(&self) -> &str {
+        self.0.trim()
+    }
+
+    /// 按精度转换为最小单位
+    pub fn to_units(&self, decimals: u8, rounding: Rounding) -> Result<U256, String> {
+        parse_units(self.as_str(), decimals, rounding)
+    }
+}
+
+impl fmt::Display for DecimalAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for DecimalAmount {
+    fn from(value: &str) -> Self {
+        DecimalAmount(value.to_string())
+    }
+}
+
+impl Serialize for DecimalAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DecimalAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl serde::de::Visitor<'_> for AmountVisitor {
+            type Value = DecimalAmount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("十进制金额字符串或数字")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(DecimalAmount(v.trim().to_string()))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(DecimalAmount(v.to_string()))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(DecimalAmount(v.to_string()))
+            }
+
+            // f64 的 Display 输出能还原该值的最短十进制表示，不会出现科学计数法
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                if !v.is_finite() {
+                    return Err(E::custom("金额不是有效数字"));
+                }
+                Ok(DecimalAmount(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+fn pow10(exp: u8) -> U256 {
+    U256::from(10u8).pow(U256::from(exp))
+}
+
+/// 将十进制字符串精确转换为最小单位，如 ("1.5", 18) -> 1500000000000000000
+///
+/// 小数位数超过 `decimals` 时按 `rounding` 处理多出的部分。
+pub fn parse_units(amount: &str, decimals: u8, rounding: Rounding) -> Result<U256, String> {
+    let amount = amount.trim();
+    if amount.is_empty() {
+        return Err("金额不能为空".to_string());
+    }
+    let (int_part, frac_part) = amount.split_once('.').unwrap_or((amount, ""));
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part.bytes().all(|b| b.is_ascii_digit())
+        || !frac_part.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(format!("金额格式错误: {amount}"));
+    }
+
+    let keep = frac_part.len().min(decimals as usize);
+    let (kept, dropped) = frac_part.split_at(keep);
+    let digits = format!("{int_part}{kept}{}", "0".repeat(decimals as usize - keep));
+    let digits = digits.trim_start_matches('0');
+    let mut value = if digits.is_empty() {
+        U256::ZERO
+    } else {
+        U256::from_str_radix(digits, 10).map_err(|_| format!("金额超出范围: {amount}"))?
+    };
+
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => dropped.bytes().any(|b| b != b'0'),
+    };
+    if round_up {
+        value = value.checked_add(U256::from(1)).ok_or_else(|| format!("金额超出范围: {amount}"))?;
+    }
+    Ok(value)
+}
+
+/// 将最小单位精确格式化为十进制字符串（去掉小数末尾的0）
+pub fn format_units(value: U256, decimals: u8) -> String {
+    let digits = value.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+    let padded = format!("{digits:0>width$}", width = decimals + 1);
+    let (int_part, frac_part) = padded.split_at(padded.len() - decimals);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{int_part}.{frac_part}")
+    }
+}
+
+/// 将最小单位数值舍入到 `precision` 位小数
+pub fn round_units(value: U256, decimals: u8, precision: u8, rounding: Rounding) -> U256 {
+    if precision >= decimals {
+        return value;
+    }
+    let step = pow10(decimals - precision);
+    let remainder = value % step;
+    let floor = value - remainder;
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => !remainder.is_zero(),
+    };
+    if round_up {
+        floor.saturating_add(step)
+    } else {
+        floor
+    }
+}
+
+/// 在 [min, max] 内随机取一个恰好保留 `precision` 位小数的数值（均为最小单位）
+///
+/// 区间两端按精度向内取整，保证结果不越界；区间内不存在该精度的数值时返回错误。
+pub fn random_units_in_range(min: U256, max: U256, decimals: u8, precision: u8) -> Result<U256, String> {
+    if min > max {
+        return Err(format!(
+            "随机区间无效: 最小值 {} 大于最大值 {}",
+            format_units(min, decimals),
+            format_units(max, decimals)
+        ));
+    }
+    let step = if precision >= decimals { U256::from(1) } else { pow10(decimals - precision) };
+    let low = round_units(min, decimals, precision, Rounding::Up) / step;
+    let high = round_units(max, decimals, precision, Rounding::Down) / step;
+    if low > high {
+        return Err(format!(
+            "区间 [{}, {}] 内不存在 {precision} 位小数的数值",
+            format_units(min, decimals),
+            format_units(max, decimals)
+        ));
+    }
+    // 32字节随机数取模，偏差可以忽略
+    let span = high - low + U256::from(1);
+    let offset = U256::from_be_bytes(rand::random::<[u8; 32]>()) % span;
+    Ok((low + offset) * step)
 }
 
 #[cfg(test)]
@@ -62,4 +258,75 @@ mod tests {
         let wei = U256::from(500000000u128);
         assert_eq!(format_wei_to_gwei(wei), "0.50");
     }
+
+    #[test]
+    fn test_parse_units_exact() {
+        assert_eq!(parse_units("1.5", 18, Rounding::Down).unwrap(), U256::from(1_500_000_000_000_000_000u128));
+        assert_eq!(parse_units("0.1", 18, Rounding::Down).unwrap(), U256::from(100_000_000_000_000_000u128));
+        assert_eq!(parse_units(".25", 6, Rounding::Down).unwrap(), U256::from(250_000u64));
+        assert_eq!(parse_units("7.", 6, Rounding::Down).unwrap(), U256::from(7_000_000u64));
+        assert_eq!(parse_units("0", 18, Rounding::Down).unwrap(), U256::ZERO);
+        // f64 无法精确表示的大额金额
+        assert_eq!(
+            parse_units("123456789.123456789123456789", 18, Rounding::Down).unwrap(),
+            U256::from(123_456_789_123_456_789_123_456_789u128)
+        );
+        assert!(parse_units("", 18, Rounding::Down).is_err());
+        assert!(parse_units("1.2.3", 18, Rounding::Down).is_err());
+        assert!(parse_units("-1", 18, Rounding::Down).is_err());
+        assert!(parse_units("1e18", 18, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_parse_units_rounding() {
+        // 6位精度代币，多出的小数位按舍入方式处理
+        assert_eq!(parse_units("1.2345674", 6, Rounding::Down).unwrap(), U256::from(1_234_567u64));
+        assert_eq!(parse_units("1.2345674", 6, Rounding::Up).unwrap(), U256::from(1_234_568u64));
+        assert_eq!(parse_units("1.2345670000", 6, Rounding::Up).unwrap(), U256::from(1_234_567u64));
+        assert_eq!(parse_units("0.9", 0, Rounding::Down).unwrap(), U256::ZERO);
+        assert_eq!(parse_units("0.9", 0, Rounding::Up).unwrap(), U256::from(1u8));
+    }
+
+    #[test]
+    fn test_format_and_round_units() {
+        assert_eq!(format_units(U256::from(1_500_000_000_000_000_000u128), 18), "1.5");
+        assert_eq!(format_units(U256::from(1u8), 18), "0.000000000000000001");
+        assert_eq!(format_units(U256::from(2_000_000u64), 6), "2");
+        assert_eq!(format_units(U256::from(42u8), 0), "42");
+
+        let value = U256::from(1_234_567u64); // 1.234567 (6位精度)
+        assert_eq!(round_units(value, 6, 2, Rounding::Down), U256::from(1_230_000u64));
+        assert_eq!(round_units(value, 6, 2, Rounding::Up), U256::from(1_240_000u64));
+        assert_eq!(round_units(U256::from(1_230_000u64), 6, 2, Rounding::Up), U256::from(1_230_000u64));
+        assert_eq!(round_units(value, 6, 8, Rounding::Up), value);
+    }
+
+    #[test]
+    fn test_random_units_in_range() {
+        let min = parse_units("0.011", 18, Rounding::Down).unwrap();
+        let max = parse_units("0.039", 18, Rounding::Down).unwrap();
+        let step = pow10(16);
+        for _ in 0..100 {
+            let v = random_units_in_range(min, max, 18, 2).unwrap();
+            assert!(v >= min && v <= max);
+            assert!((v % step).is_zero());
+        }
+        // 区间内没有2位小数的数值
+        let max = parse_units("0.0119", 18, Rounding::Down).unwrap();
+        assert!(random_units_in_range(min, max, 18, 2).is_err());
+        assert!(random_units_in_range(max, min, 18, 2).is_err());
+        assert_eq!(random_units_in_range(min, min, 18, 18).unwrap(), min);
+    }
+
+    #[test]
+    fn test_decimal_amount_serde() {
+        let from_number: DecimalAmount = serde_json::from_str("0.1").unwrap();
+        let from_string: DecimalAmount = serde_json::from_str("\"0.1\"").unwrap();
+        assert_eq!(from_number, from_string);
+        let from_int: DecimalAmount = serde_json::from_str("3").unwrap();
+        assert_eq!(from_int.as_str(), "3");
+        assert_eq!(serde_json::to_string(&from_number).unwrap(), "\"0.1\"");
+        let list: [DecimalAmount; 2] = serde_json::from_str("[0.5, \"1.25\"]").unwrap();
+        assert_eq!(list[1].to_units(6, Rounding::Down).unwrap(), U256::from(1_250_000u64));
+    }
 }
@@ -0,0 +1,354 @@
+use alloy_primitives::{I256, U256};
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::database::{get_database_manager, balance_snapshot_service::BalanceSnapshotService, chain_service::ChainService};
+use crate::database::models::{
+    BalanceSnapshot, BalanceSnapshotItem, CreateBalanceSnapshotItemRequest, CreateBalanceSnapshotRequest,
+};
+use super::alloy_utils::format_units;
+
+/// 平台币快照的 coin_key
+pub const BASE_COIN_KEY: &str = "base";
+
+/// 单个地址在两个快照之间的变化
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiffItem {
+    pub address: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub delta: Option<String>,
+    pub nonce_before: Option<i64>,
+    pub nonce_after: Option<i64>,
+    pub status: String, // "new", "emptied", "changed", "unchanged", "unknown"（任一快照中查询失败）
+}
+
+/// 差异汇总，金额只统计两边都查询成功的地址
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotDiffTotals {
+    pub before: String,
+    pub after: String,
+    pub delta: String,
+    pub new_count: usize,
+    pub emptied_count: usize,
+    pub changed_count: usize,
+    pub unchanged_count: usize,
+    pub unknown_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    pub base: BalanceSnapshot,
+    pub target: BalanceSnapshot,
+    pub items: Vec<SnapshotDiffItem>,
+    pub totals: SnapshotDiffTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceSnapshotDetail {
+    pub snapshot: BalanceSnapshot,
+    pub items: Vec<BalanceSnapshotItem>,
+}
+
+// 快照中的币种名称：平台币取链的原生币符号，代币取代币配置的符号
+async fn coin_symbol(chain: &str, coin_key: &str) -> String {
+    let chain_service = ChainService::new(get_database_manager().get_pool());
+    if coin_key == BASE_COIN_KEY {
+        if let Ok(Some(chain)) = chain_service.get_chain_by_key(chain).await {
+            return chain.native_currency_symbol;
+        }
+    } else if let Ok(Some(token)) = chain_service.get_token_by_contract(chain, coin_key).await {
+        return token.symbol;
+    }
+    coin_key.to_string()
+}
+
+/// 保存一次余额查询的结果，items 为 (地址, 最小单位余额, nonce)，查询失败的余额为 None
+pub async fn save_balance_snapshot(
+    chain: &str,
+    coin_key: &str,
+    decimals: u8,
+    block_number: Option<u64>,
+    items: Vec<(String, Option<U256>, Option<u64>)>,
+) -> Result<i64, String> {
+    let request = CreateBalanceSnapshotRequest {
+        chain_key: chain.to_string(),
+        coin_key: coin_key.to_string(),
+        coin_symbol: coin_symbol(chain, coin_key).await,
+        block_number: block_number.map(|block| block as i64),
+        label: None,
+        decimals: decimals as i64,
+        items: items
+            .into_iter()
+            .map(|(address, balance, nonce)| CreateBalanceSnapshotItemRequest {
+                address: snapshot_address(&address),
+                balance: balance.map(|balance| balance.to_string()),
+                nonce: nonce.map(|nonce| nonce as i64),
+            })
+            .collect(),
+    };
+    BalanceSnapshotService::new(get_database_manager().get_pool())
+        .create_snapshot(request)
+        .await
+        .map_err(|e| format!("保存余额快照失败: {e}"))
+}
+
+// 快照中的地址统一为小写，不同大小写输入的同一地址才能逐地址比较
+fn snapshot_address(address: &str) -> String {
+    address.trim().to_lowercase()
+}
+
+// 明细余额按快照精度转换为完整精度的小数用于显示与比较
+fn display_balances(snapshot: &BalanceSnapshot, items: &mut [BalanceSnapshotItem]) {
+    for item in items {
+        item.balance = item
+            .balance
+            .as_deref()
+            .and_then(|raw| raw.parse::<U256>().ok())
+            .map(|units| format_units(units, snapshot.decimals as u8));
+    }
+}
+
+fn fraction_digits(value: &str) -> usize {
+    value.split_once('.').map_or(0, |(_, fraction)| fraction.len())
+}
+
+// 十进制字符串按 scale 位小数换算为整数
+fn to_scaled(value: &str, scale: usize) -> Option<I256> {
+    let value = value.trim();
+    let (int_part, frac_part) = value.split_once('.').unwrap_or((value, ""));
+    let digits = format!("{int_part}{frac_part:0<scale$}");
+    let units = U256::from_str_radix(if digits.is_empty() { "0" } else { &digits }, 10).ok()?;
+    I256::try_from(units).ok()
+}
+
+fn format_scaled(value: I256, scale: usize) -> String {
+    let formatted = format_units(value.unsigned_abs(), scale as u8);
+    if value.is_negative() {
+        format!("-{formatted}")
+    } else {
+        formatted
+    }
+}
+
+// 逐地址比较两个快照；只在一个快照中出现的地址按余额0处理
+fn diff_items(base: &[BalanceSnapshotItem], target: &[BalanceSnapshotItem]) -> (Vec<SnapshotDiffItem>, SnapshotDiffTotals) {
+    let scale = base
+        .iter()
+        .chain(target)
+        .filter_map(|item| item.balance.as_deref())
+        .map(fraction_digits)
+        .max()
+        .unwrap_or(0);
+
+    let target_by_address: HashMap<String, &BalanceSnapshotItem> =
+        target.iter().map(|item| (snapshot_address(&item.address), item)).collect();
+    let base_by_address: HashMap<String, &BalanceSnapshotItem> =
+        base.iter().map(|item| (snapshot_address(&item.address), item)).collect();
+
+    // 保持基准快照的地址顺序，目标快照中新增的地址排在后面
+    let mut addresses: Vec<String> = base.iter().map(|item| snapshot_address(&item.address)).collect();
+    addresses.extend(
+        target
+            .iter()
+            .map(|item| snapshot_address(&item.address))
+            .filter(|address| !base_by_address.contains_key(address)),
+    );
+
+    let mut totals = SnapshotDiffTotals::default();
+    let (mut total_before, mut total_after) = (I256::ZERO, I256::ZERO);
+    let items = addresses
+        .into_iter()
+        .map(|address| {
+            let before_item = base_by_address.get(&address);
+            let after_item = target_by_address.get(&address);
+            // 地址不在快照中视为余额0，在快照中但查询失败视为未知
+            let balance_of = |item: Option<&&BalanceSnapshotItem>| match item {
+                None => Some(I256::ZERO),
+                Some(item) => item.balance.as_deref().and_then(|balance| to_scaled(balance, scale)),
+            };
+            let before = balance_of(before_item);
+            let after = balance_of(after_item);
+
+            let (status, delta) = match (before, after) {
+                (Some(before), Some(after)) => {
+                    total_before += before;
+                    total_after += after;
+                    let status = if before == after {
+                        totals.unchanged_count += 1;
+                        "unchanged"
+                    } else if before.is_zero() {
+                        totals.new_count += 1;
+                        "new"
+                    } else if after.is_zero() {
+                        totals.emptied_count += 1;
+                        "emptied"
+                    } else {
+                        totals.changed_count += 1;
+                        "changed"
+                    };
+                    (status, Some(format_scaled(after - before, scale)))
+                }
+                _ => {
+                    totals.unknown_count += 1;
+                    ("unknown", None)
+                }
+            };
+
+            SnapshotDiffItem {
+                address,
+                before: before.map(|value| format_scaled(value, scale)),
+                after: after.map(|value| format_scaled(value, scale)),
+                delta,
+                nonce_before: before_item.and_then(|item| item.nonce),
+                nonce_after: after_item.and_then(|item| item.nonce),
+                status: status.to_string(),
+            }
+        })
+        .collect();
+
+    totals.before = format_scaled(total_before, scale);
+    totals.after = format_scaled(total_after, scale);
+    totals.delta = format_scaled(total_after - total_before, scale);
+    (items, totals)
+}
+
+async fn load_snapshot(service: &BalanceSnapshotService<'_>, snapshot_id: i64) -> Result<BalanceSnapshot, String> {
+    service
+        .get_snapshot(snapshot_id)
+        .await
+        .map_err(|e| format!("查询快照失败: {e}"))?
+        .ok_or_else(|| format!("快照不存在: {snapshot_id}"))
+}
+
+// Tauri命令：获取余额快照列表
+#[tauri::command]
+pub async fn list_balance_snapshots(chain: Option<String>) -> Result<Vec<BalanceSnapshot>, String> {
+    BalanceSnapshotService::new(get_database_manager().get_pool())
+        .list_snapshots(chain.as_deref())
+        .await
+        .map_err(|e| format!("查询快照列表失败: {e}"))
+}
+
+// Tauri命令：获取快照详情
+#[tauri::command]
+pub async fn get_balance_snapshot(snapshot_id: i64) -> Result<BalanceSnapshotDetail, String> {
+    let service = BalanceSnapshotService::new(get_database_manager().get_pool());
+    let snapshot = load_snapshot(&service, snapshot_id).await?;
+    let mut items = service.get_snapshot_items(snapshot_id).await
+        .map_err(|e| format!("查询快照明细失败: {e}"))?;
+    display_balances(&snapshot, &mut items);
+    Ok(BalanceSnapshotDetail { snapshot, items })
+}
+
+// Tauri命令：修改快照备注
+#[tauri::command]
+pub async fn update_balance_snapshot_label(snapshot_id: i64, label: Option<String>) -> Result<(), String> {
+    let label = label.map(|label| label.trim().to_string()).filter(|label| !label.is_empty());
+    BalanceSnapshotService::new(get_database_manager().get_pool())
+        .update_label(snapshot_id, label.as_deref())
+        .await
+        .map_err(|e| format!("修改快照备注失败: {e}"))
+}
+
+// Tauri命令：删除快照
+#[tauri::command]
+pub async fn delete_balance_snapshot(snapshot_id: i64) -> Result<(), String> {
+    BalanceSnapshotService::new(get_database_manager().get_pool())
+        .delete_snapshot(snapshot_id)
+        .await
+        .map_err(|e| format!("删除快照失败: {e}"))
+}
+
+// Tauri命令：比较两个快照（base 为较早的基准快照）
+#[tauri::command]
+pub async fn diff_balance_snapshots(base_id: i64, target_id: i64) -> Result<SnapshotDiff, String> {
+    let service = BalanceSnapshotService::new(get_database_manager().get_pool());
+    let base = load_snapshot(&service, base_id).await?;
+    let target = load_snapshot(&service, target_id).await?;
+    if base.chain_key != target.chain_key || base.coin_key != target.coin_key {
+        return Err(format!(
+            "两个快照的链或币种不一致: {}/{} 与 {}/{}",
+            base.chain_key, base.coin_symbol, target.chain_key, target.coin_symbol
+        ));
+    }
+
+    let mut base_items = service.get_snapshot_items(base_id).await
+        .map_err(|e| format!("查询快照明细失败: {e}"))?;
+    let mut target_items = service.get_snapshot_items(target_id).await
+        .map_err(|e| format!("查询快照明细失败: {e}"))?;
+    display_balances(&base, &mut base_items);
+    display_balances(&target, &mut target_items);
+    let (items, totals) = diff_items(&base_items, &target_items);
+
+    Ok(SnapshotDiff { base, target, items, totals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(address: &str, balance: Option<&str>) -> BalanceSnapshotItem {
+        BalanceSnapshotItem {
+            id: 0,
+            snapshot_id: 0,
+            address: address.to_string(),
+            balance: balance.map(str::to_string),
+            nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_items() {
+        let base = vec![
+            item("0xa", Some("1.5")),
+            item("0xb", Some("2")),
+            item("0xc", Some("0")),
+            item("0xd", Some("3.25")),
+            item("0xe", None),
+        ];
+        let target = vec![
+            item("0xa", Some("1.5")),
+            item("0xb", Some("0")),
+            item("0xc", Some("0.000001")),
+            item("0xd", Some("1")),
+            item("0xE", Some("1")),
+            item("0xf", Some("4")),
+        ];
+        let (items, totals) = diff_items(&base, &target);
+        let statuses: Vec<&str> = items.iter().map(|i| i.status.as_str()).collect();
+        assert_eq!(statuses, ["unchanged", "emptied", "new", "changed", "unknown", "new"]);
+        assert_eq!(items[1].delta.as_deref(), Some("-2"));
+        assert_eq!(items[3].delta.as_deref(), Some("-2.25"));
+        assert_eq!(items[5].before.as_deref(), Some("0"));
+        assert_eq!(totals.before, "6.75");
+        assert_eq!(totals.after, "6.500001");
+        assert_eq!(totals.delta, "-0.249999");
+        assert_eq!(totals.unknown_count, 1);
+    }
+
+    #[test]
+    fn test_display_balances() {
+        let snapshot = |decimals| BalanceSnapshot {
+            id: 0,
+            chain_key: "eth".to_string(),
+            coin_key: BASE_COIN_KEY.to_string(),
+            coin_symbol: "ETH".to_string(),
+            block_number: None,
+            label: None,
+            item_count: 2,
+            decimals,
+            created_at: chrono::Utc::now(),
+        };
+        // 最小单位保存，显示时保留完整精度（6位小数截断会丢失的变化也能比较出来）
+        let mut raw = vec![item("0xa", Some("1500000000000000001")), item("0xb", None)];
+        display_balances(&snapshot(18), &mut raw);
+        assert_eq!(raw[0].balance.as_deref(), Some("1.500000000000000001"));
+        assert_eq!(raw[1].balance, None);
+
+        let mut earlier = vec![item("0xa", Some("1500000000000000000")), item("0xb", Some("2000000000000000000"))];
+        display_balances(&snapshot(18), &mut earlier);
+        let (items, _) = diff_items(&earlier, &raw);
+        assert_eq!(items[0].delta.as_deref(), Some("0.000000000000000001"));
+        assert_eq!(items[1].status, "unknown");
+    }
+}
@@ -2,6 +2,7 @@ use serde_json::{json, Value};
 use tauri::command;
 use crate::database::{get_database_manager, chain_service::ChainService, models::*};
 use anyhow::Result;
+use super::pricing::PRICE_SOURCES;
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[command]
@@ -67,7 +68,9 @@ pub async fn get_coin_list(chain_key: &str) -> Result<Vec<Value>, String> {
                     "decimals": token.decimals,
                     "coin_type": token.token_type,
                     "contract_type": token.contract_type,
-                    "abi": token.abi
+                    "abi": token.abi,
+                    "price_source": token.price_source,
+                    "price_feed": token.price_feed
                 })
             }).collect();
 
@@ -179,6 +182,33 @@ pub async fn update_token_abi(chain: &str, token_key: &str, abi: Option<String>)
     Ok(())
 }
 
+#[command]
+pub async fn update_token_price_source(
+    chain: &str,
+    token_key: &str,
+    price_source: Option<String>,
+    price_feed: Option<String>,
+) -> Result<(), String> {
+    let price_source = price_source.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let price_feed = price_feed.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if let Some(source) = price_source.as_deref() {
+        if !PRICE_SOURCES.contains(&source) {
+            return Err(format!("不支持的价格来源: {source}"));
+        }
+        if source != "stable" && price_feed.is_none() {
+            return Err("请填写价格合约地址（Chainlink聚合器或Uniswap V2交易对）".to_string());
+        }
+    }
+
+    let db_manager = get_database_manager();
+    let chain_service = ChainService::new(db_manager.get_pool());
+
+    chain_service.update_token_price_source(chain, token_key, price_source.as_deref(), price_feed.as_deref()).await
+        .map_err(|e| format!("更新代币价格来源失败: {e}"))?;
+
+    Ok(())
+}
+
 /// 添加新链
 #[command]
 pub async fn add_chain(request_json: &str) -> Result<i64, String> {
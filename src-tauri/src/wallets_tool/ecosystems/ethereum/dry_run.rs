@@ -0,0 +1,461 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use alloy_primitives::{Address, Bytes, TxKind, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionRequest;
+use futures::future::join_all;
+use tokio::sync::Semaphore;
+use super::alloy_utils::{DecimalAmount, Rounding, format_units, format_wei_to_gwei, parse_signer};
+use super::ens::{is_ens_name, resolve_and_lock};
+use super::provider::AlloyProvider;
+use super::token_transfer::{TokenTransferConfig, TokenTransferUtils};
+use super::transfer::{
+    NATIVE_DECIMALS, TransferConfig, TransferItem, TransferUtils, create_provider, fixed_transfer_units,
+    random_transfer_units, remaining_transfer_units,
+};
+
+// 模拟时的并发数，避免对RPC造成瞬间压力
+const DRY_RUN_CONCURRENCY: usize = 5;
+
+// 单行模拟结果
+#[derive(Debug, Default, Serialize)]
+pub struct DryRunRow {
+    pub index: usize,
+    pub from_address: String,
+    pub to_address: String,
+    pub resolved_address: Option<String>,    // 接收地址为ENS名称时解析出的地址
+    pub balance: Option<String>,             // 原生币余额
+    pub token_balance: Option<String>,       // 代币余额（仅代币转账）
+    pub amount: Option<String>,              // 计划转账数量
+    pub gas_limit: Option<u64>,
+    pub fee: Option<String>,                 // 预计手续费（原生币）
+    pub balance_after: Option<String>,       // 转账后原生币余额
+    pub token_balance_after: Option<String>, // 转账后代币余额（仅代币转账）
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunTotals {
+    pub total_amount: String,
+    pub total_fee: String,
+    pub ok_count: usize,
+    pub failed_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub gas_price_gwei: String, // EIP-1559 模式下为 maxFeePerGas
+    pub rows: Vec<DryRunRow>,
+    pub totals: DryRunTotals,
+}
+
+// 每行模拟成功后的原始数值，用于累计余额检查和汇总
+#[derive(Default)]
+struct RowTotals {
+    amount: U256,
+    fee: U256,
+    balance: U256,       // 原生币余额
+    token_balance: U256, // 代币余额（仅代币转账）
+}
+
+// 计划使用的Gas Limit：自动模式调用 eth_estimateGas，随机模式按上限计算（最坏情况）
+async fn planned_gas_limit(
+    provider: &AlloyProvider,
+    limit_type: &str,
+    limit_count: u64,
+    limit_count_list: [u64; 2],
+    tx: TransactionRequest,
+) -> Result<u64, String> {
+    match limit_type {
+        "2" => Ok(limit_count),
+        "3" => Ok(limit_count_list[1]),
+        _ => provider.estimate_gas(tx).await.map_err(|e| format!("eth_estimateGas 失败: {e}")),
+    }
+}
+
+// 随机数量模式按区间上限计划（最坏情况），与随机Gas Limit一致；同时校验区间本身可用
+fn random_upper_units(range: &[DecimalAmount; 2], decimals: u8, precision: u8) -> Result<U256, String> {
+    random_transfer_units(range, decimals, precision)?;
+    range[1].to_units(decimals, Rounding::Down)
+}
+
+async fn plan_base_row(
+    provider: &AlloyProvider,
+    config: &TransferConfig,
+    gas_price: U256,
+    from: Address,
+    to: Address,
+    row: &mut DryRunRow,
+) -> Result<RowTotals, String> {
+    let balance = provider.get_balance(from).await.map_err(|e| format!("获取余额失败: {e}"))?;
+    row.balance = Some(format_units(balance, NATIVE_DECIMALS));
+
+    let preset_amount = match config.transfer_type.as_str() {
+        "1" | "4" => None,
+        "2" => Some(fixed_transfer_units(&config.transfer_amount, NATIVE_DECIMALS)?),
+        "3" => Some(random_upper_units(&config.transfer_amount_list, NATIVE_DECIMALS, config.amount_precision)?),
+        _ => return Err("无效的转账类型".to_string()),
+    };
+
+    let estimate_tx = TransactionRequest {
+        from: Some(from),
+        to: Some(to.into()),
+        value: Some(preset_amount.unwrap_or(U256::ZERO)),
+        ..Default::default()
+    };
+    let gas_limit = planned_gas_limit(provider, &config.limit_type, config.limit_count, config.limit_count_list, estimate_tx).await?;
+    let fee = gas_price * U256::from(gas_limit);
+    row.gas_limit = Some(gas_limit);
+    row.fee = Some(format_units(fee, NATIVE_DECIMALS));
+
+    let amount = match preset_amount {
+        Some(amount) => amount,
+        None if config.transfer_type == "1" => {
+            // 与实际转账一致：预留10%手续费安全边际
+            let reserved = fee + fee / U256::from(10);
+            if balance <= reserved {
+                return Err("余额不足支付Gas费用".to_string());
+            }
+            balance - reserved
+        }
+        None => {
+            if balance <= fee {
+                return Err("余额不足支付Gas费用".to_string());
+            }
+            remaining_transfer_units(balance - fee, &config.left_amount_list, NATIVE_DECIMALS, config.amount_precision)?.0
+        }
+    };
+    row.amount = Some(format_units(amount, NATIVE_DECIMALS));
+
+    if amount + fee > balance {
+        return Err(format!(
+            "余额不足：需要 {}（含手续费），当前 {}",
+            format_units(amount + fee, NATIVE_DECIMALS),
+            format_units(balance, NATIVE_DECIMALS)
+        ));
+    }
+
+    Ok(RowTotals { amount, fee, balance, token_balance: U256::ZERO })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn plan_token_row(
+    provider: &AlloyProvider,
+    config: &TokenTransferConfig,
+    contract_address: Address,
+    decimals: u8,
+    gas_price: U256,
+    from: Address,
+    to: Address,
+    row: &mut DryRunRow,
+) -> Result<RowTotals, String> {
+    let balance = provider.get_balance(from).await.map_err(|e| format!("获取余额失败: {e}"))?;
+    row.balance = Some(format_units(balance, NATIVE_DECIMALS));
+    let token_balance = TokenTransferUtils::get_token_balance(provider, contract_address, from).await
+        .map_err(|e| format!("获取代币余额失败: {e}"))?;
+    row.token_balance = Some(format_units(token_balance, decimals));
+
+    let amount = match config.transfer_type.as_str() {
+        "1" => token_balance,
+        "2" => fixed_transfer_units(&config.transfer_amount, decimals)?,
+        "3" => random_upper_units(&config.transfer_amount_list, decimals, config.amount_precision)?,
+        "4" => remaining_transfer_units(token_balance, &config.left_amount_list, decimals, config.amount_precision)?.0,
+        _ => return Err("无效的转账类型".to_string()),
+    };
+    row.amount = Some(format_units(amount, decimals));
+
+    if amount.is_zero() {
+        return Err("计划转账数量为0".to_string());
+    }
+    if amount > token_balance {
+        return Err("代币余额不足".to_string());
+    }
+
+    // transfer(address,uint256)
+    let mut data = Vec::with_capacity(68);
+    data.extend_from_slice(&[0xa9, 0x05, 0x9c, 0xbb]);
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(to.as_slice());
+    data.extend_from_slice(&amount.to_be_bytes::<32>());
+    let call_tx = TransactionRequest {
+        from: Some(from),
+        to: Some(TxKind::Call(contract_address)),
+        input: Bytes::from(data).into(),
+        ..Default::default()
+    };
+
+    // eth_call 模拟执行，返回 false 或回滚都视为失败
+    let output = provider.call(call_tx.clone()).await.map_err(|e| format!("eth_call 模拟失败: {e}"))?;
+    if output.len() >= 32 && output[..32].iter().all(|b| *b == 0) {
+        return Err("代币合约 transfer 返回 false".to_string());
+    }
+
+    let gas_limit = planned_gas_limit(provider, &config.limit_type, config.limit_count, config.limit_count_list, call_tx).await?;
+    let fee = gas_price * U256::from(gas_limit);
+    row.gas_limit = Some(gas_limit);
+    row.fee = Some(format_units(fee, NATIVE_DECIMALS));
+
+    if fee > balance {
+        return Err(format!(
+            "原生币余额不足支付Gas费用：需要 {}，当前 {}",
+            format_units(fee, NATIVE_DECIMALS),
+            format_units(balance, NATIVE_DECIMALS)
+        ));
+    }
+
+    Ok(RowTotals { amount, fee, balance, token_balance })
+}
+
+// 批量开始前解析并锁定接收地址中的ENS名称，结果按名称索引
+async fn resolve_item_names(chain: &str, window_id: Option<&str>, items: &[TransferItem]) -> HashMap<String, Result<Address, String>> {
+    let mut names: Vec<String> = items.iter().map(|item| item.to_addr.trim().to_string()).filter(|to| is_ens_name(to)).collect();
+    names.sort();
+    names.dedup();
+    let results = resolve_and_lock(chain, window_id, &names).await;
+    names.into_iter().zip(results).collect()
+}
+
+// 解析行的发送/接收地址，失败时直接记录到行上
+fn prepare_row(
+    index: usize,
+    item: &TransferItem,
+    names: &HashMap<String, Result<Address, String>>,
+) -> (DryRunRow, Option<(Address, Address)>) {
+    let mut row = DryRunRow {
+        index,
+        to_address: item.to_addr.trim().to_string(),
+        ..Default::default()
+    };
+    let wallet = match parse_signer(&item.private_key) {
+        Ok(w) => w,
+        Err(e) => {
+            row.error = Some(e);
+            return (row, None);
+        }
+    };
+    row.from_address = format!("{:?}", wallet.address());
+    if let Some(resolved) = names.get(&row.to_address) {
+        return match resolved {
+            Ok(to) => {
+                row.resolved_address = Some(format!("{to:?}"));
+                (row, Some((wallet.address(), *to)))
+            }
+            Err(e) => {
+                row.error = Some(e.clone());
+                (row, None)
+            }
+        };
+    }
+    match row.to_address.parse::<Address>() {
+        Ok(to) => (row, Some((wallet.address(), to))),
+        Err(e) => {
+            row.error = Some(format!("目标地址格式错误: {e}"));
+            (row, None)
+        }
+    }
+}
+
+// 同一钱包的多行按顺序累计支出并检查余额（单行只与完整余额比较，一对多时合计可能超出余额）
+// token_decimals 为 None 时是基础币转账，转账数量与手续费都从原生币余额中扣除
+fn apply_cumulative_balances(results: &mut [(DryRunRow, RowTotals)], token_decimals: Option<u8>) {
+    // 发送地址 -> (累计原生币支出, 累计代币支出)
+    let mut spent: HashMap<String, (U256, U256)> = HashMap::new();
+    for (row, totals) in results.iter_mut() {
+        if !row.ok {
+            continue;
+        }
+        let (native_spent, token_spent) = spent.entry(row.from_address.clone()).or_default();
+        let (native_cost, token_cost) = match token_decimals {
+            Some(_) => (totals.fee, totals.amount),
+            None => (totals.amount + totals.fee, U256::ZERO),
+        };
+        let native_total = *native_spent + native_cost;
+        let token_total = *token_spent + token_cost;
+
+        let error = if native_total > totals.balance {
+            Some(format!(
+                "原生币余额不足：与同一钱包前面的行合计需要 {}（含手续费），当前 {}",
+                format_units(native_total, NATIVE_DECIMALS),
+                format_units(totals.balance, NATIVE_DECIMALS)
+            ))
+        } else if token_total > totals.token_balance {
+            token_decimals.map(|decimals| format!(
+                "代币余额不足：与同一钱包前面的行合计需要 {}，当前 {}",
+                format_units(token_total, decimals),
+                format_units(totals.token_balance, decimals)
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            row.ok = false;
+            row.error = Some(error);
+            continue;
+        }
+
+        *native_spent = native_total;
+        *token_spent = token_total;
+        row.balance_after = Some(format_units(totals.balance - native_total, NATIVE_DECIMALS));
+        if let Some(decimals) = token_decimals {
+            row.token_balance_after = Some(format_units(totals.token_balance - token_total, decimals));
+        }
+    }
+}
+
+fn build_report(gas_price: U256, results: Vec<(DryRunRow, RowTotals)>, format_amount: impl Fn(U256) -> String) -> DryRunReport {
+    let mut total_amount = U256::ZERO;
+    let mut total_fee = U256::ZERO;
+    let mut ok_count = 0;
+    let mut rows = Vec::with_capacity(results.len());
+    for (row, totals) in results {
+        if row.ok {
+            ok_count += 1;
+            total_amount += totals.amount;
+            total_fee += totals.fee;
+        }
+        rows.push(row);
+    }
+    let failed_count = rows.len() - ok_count;
+
+    DryRunReport {
+        gas_price_gwei: format_wei_to_gwei(gas_price),
+        rows,
+        totals: DryRunTotals {
+            total_amount: format_amount(total_amount),
+            total_fee: format_units(total_fee, NATIVE_DECIMALS),
+            ok_count,
+            failed_count,
+        },
+    }
+}
+
+// Tauri命令：基础币批量转账试运行（不签名、不广播）
+#[tauri::command]
+pub async fn base_coin_transfer_dry_run(
+    items: Vec<TransferItem>,
+    config: TransferConfig,
+) -> Result<DryRunReport, String> {
+    let provider = create_provider(&config.chain, config.window_id.as_deref()).await
+        .map_err(|e| format!("获取RPC提供商失败: {e}"))?;
+    let gas_price = TransferUtils::get_gas_fee(&config, provider.clone()).await
+        .map_err(|e| format!("获取Gas Price失败: {e}"))?
+        .gas_price;
+    let names = resolve_item_names(&config.chain, config.window_id.as_deref(), &items).await;
+
+    let semaphore = Arc::new(Semaphore::new(DRY_RUN_CONCURRENCY));
+    let tasks = items.iter().enumerate().map(|(index, item)| {
+        let semaphore = semaphore.clone();
+        let provider = provider.clone();
+        let (config, names) = (&config, &names);
+        async move {
+            let (mut row, addresses) = prepare_row(index + 1, item, names);
+            let Some((from, to)) = addresses else {
+                return (row, RowTotals::default());
+            };
+            let _permit = semaphore.acquire().await.unwrap();
+            match plan_base_row(&provider, config, gas_price, from, to, &mut row).await {
+                Ok(totals) => {
+                    row.ok = true;
+                    (row, totals)
+                }
+                Err(e) => {
+                    row.error = Some(e);
+                    (row, RowTotals::default())
+                }
+            }
+        }
+    });
+    let mut results = join_all(tasks).await;
+    apply_cumulative_balances(&mut results, None);
+
+    Ok(build_report(gas_price, results, |v| format_units(v, NATIVE_DECIMALS)))
+}
+
+// Tauri命令：代币批量转账试运行（不签名、不广播）
+#[tauri::command]
+pub async fn token_transfer_dry_run(
+    items: Vec<TransferItem>,
+    config: TokenTransferConfig,
+) -> Result<DryRunReport, String> {
+    let provider = create_provider(&config.chain, config.window_id.as_deref()).await
+        .map_err(|e| format!("获取RPC提供商失败: {e}"))?;
+    let contract_address: Address = config.contract_address.trim().parse()
+        .map_err(|e| format!("代币合约地址格式错误: {e}"))?;
+    let (_, decimals) = TokenTransferUtils::get_token_info(&provider, contract_address).await
+        .map_err(|e| format!("获取代币信息失败: {e}"))?;
+
+    let transfer_config = TransferConfig::from(&config);
+    let gas_price = TransferUtils::get_gas_fee(&transfer_config, provider.clone()).await
+        .map_err(|e| format!("获取Gas Price失败: {e}"))?
+        .gas_price;
+    let names = resolve_item_names(&config.chain, config.window_id.as_deref(), &items).await;
+
+    let semaphore = Arc::new(Semaphore::new(DRY_RUN_CONCURRENCY));
+    let tasks = items.iter().enumerate().map(|(index, item)| {
+        let semaphore = semaphore.clone();
+        let provider = provider.clone();
+        let (config, names) = (&config, &names);
+        async move {
+            let (mut row, addresses) = prepare_row(index + 1, item, names);
+            let Some((from, to)) = addresses else {
+                return (row, RowTotals::default());
+            };
+            let _permit = semaphore.acquire().await.unwrap();
+            match plan_token_row(&provider, config, contract_address, decimals, gas_price, from, to, &mut row).await {
+                Ok(totals) => {
+                    row.ok = true;
+                    (row, totals)
+                }
+                Err(e) => {
+                    row.error = Some(e);
+                    (row, RowTotals::default())
+                }
+            }
+        }
+    });
+    let mut results = join_all(tasks).await;
+    apply_cumulative_balances(&mut results, Some(decimals));
+
+    Ok(build_report(gas_price, results, |v| format_units(v, decimals)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(from: &str, amount: u64, fee: u64, balance: u64, token_balance: u64) -> (DryRunRow, RowTotals) {
+        let row = DryRunRow { from_address: from.to_string(), ok: true, ..Default::default() };
+        let totals = RowTotals {
+            amount: U256::from(amount),
+            fee: U256::from(fee),
+            balance: U256::from(balance),
+            token_balance: U256::from(token_balance),
+        };
+        (row, totals)
+    }
+
+    #[test]
+    fn test_cumulative_balance() {
+        // 同一钱包三行各转 40 + 手续费 1，余额 100：第三行累计 123 超出余额
+        let mut results = vec![
+            planned("0xa", 40, 1, 100, 0),
+            planned("0xb", 40, 1, 50, 0),
+            planned("0xa", 40, 1, 100, 0),
+            planned("0xa", 40, 1, 100, 0),
+        ];
+        apply_cumulative_balances(&mut results, None);
+        let ok: Vec<bool> = results.iter().map(|(row, _)| row.ok).collect();
+        assert_eq!(ok, vec![true, true, true, false]);
+        assert_eq!(results[2].0.balance_after.as_deref(), Some(format_units(U256::from(18), NATIVE_DECIMALS).as_str()));
+        assert!(results[3].0.error.as_deref().unwrap().contains("合计"));
+
+        // 代币转账：代币按数量累计，原生币只累计手续费
+        let mut results = vec![planned("0xa", 60, 1, 10, 100), planned("0xa", 60, 1, 10, 100)];
+        apply_cumulative_balances(&mut results, Some(6));
+        assert!(results[0].0.ok);
+        assert!(!results[1].0.ok);
+        assert_eq!(build_report(U256::ZERO, results, |v| format_units(v, 6)).totals.ok_count, 1);
+    }
+}
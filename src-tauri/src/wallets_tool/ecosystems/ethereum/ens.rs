@@ -0,0 +1,348 @@
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use alloy_primitives::{Address, B256, address, keccak256};
+use ens_normalize_rs::EnsNameNormalizer;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use crate::database::{get_database_manager, rpc_service::RpcService};
+use super::rpc_batch::{RpcCall, http_client, send_batch};
+
+sol! {
+    interface IENSRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IENSResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+// ENS注册表在主网及官方测试网上的地址相同
+const ENS_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+// 已锁定的解析结果按批次（窗口ID）隔离：窗口 -> (链, 名称) -> 地址。
+// 批量开始前解析一次，之后同一批次内同一名称始终使用该地址，批次结束后释放；
+// 没有窗口ID的调用无法释放，只解析不锁定
+type LockedBatch = HashMap<(String, String), Address>;
+static LOCKED_NAMES: LazyLock<Mutex<HashMap<String, LockedBatch>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// ENSIP-15 规范化表在首次使用时加载
+static NORMALIZER: LazyLock<EnsNameNormalizer> = LazyLock::new(EnsNameNormalizer::default);
+
+/// 名称解析结果
+#[derive(Debug, Clone, Serialize)]
+pub struct NameResolution {
+    pub name: String,
+    pub address: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+/// 是否为ENS名称（非十六进制地址且包含 .）
+pub fn is_ens_name(value: &str) -> bool {
+    let value = value.trim();
+    value.contains('.') && value.parse::<Address>().is_err()
+}
+
+/// 按 ENSIP-15 规范化名称，含非法或易混淆字符的名称返回错误
+pub fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim().trim_end_matches('.');
+    NORMALIZER.normalize(name).map_err(|e| format!("{name} 不是有效的ENS名称: {e}"))
+}
+
+/// ENS namehash：从顶级域开始逐级 keccak256(node ++ keccak256(label))
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(node.as_slice());
+        data[32..].copy_from_slice(keccak256(label.as_bytes()).as_slice());
+        node = keccak256(data);
+    }
+    node
+}
+
+// 地址对应的反向解析节点: <小写十六进制地址>.addr.reverse
+fn reverse_node(address: &Address) -> B256 {
+    namehash(&format!("{}.addr.reverse", alloy_primitives::hex::encode(address.as_slice())))
+}
+
+struct EnsClient {
+    client: Client,
+    rpc_url: String,
+    max_batch_size: usize,
+}
+
+impl EnsClient {
+    async fn new(chain: &str, window_id: Option<&str>) -> Result<Self, String> {
+        let (rpc_url, max_batch_size) = RpcService::new(get_database_manager().get_pool())
+            .get_random_rpc_with_batch_size(chain)
+            .await
+            .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+        Ok(Self { client: http_client(window_id), rpc_url, max_batch_size })
+    }
+
+    // 批量 eth_call，返回解码前的原始数据
+    async fn calls(&self, calls: &[(Address, Vec<u8>)]) -> Vec<Result<Vec<u8>, String>> {
+        let calls: Vec<RpcCall> = calls
+            .iter()
+            .map(|(to, data)| {
+                RpcCall::new("eth_call", json!([{
+                    "to": format!("{to:?}"),
+                    "data": format!("0x{}", alloy_primitives::hex::encode(data)),
+                }, "latest"]))
+            })
+            .collect();
+        send_batch(&self.client, &self.rpc_url, &calls, self.max_batch_size)
+            .await
+            .into_iter()
+            .map(|result| {
+                let value = result?;
+                value
+                    .as_str()
+                    .and_then(|hex| alloy_primitives::hex::decode(hex).ok())
+                    .ok_or_else(|| format!("RPC返回格式错误: {value}"))
+            })
+            .collect()
+    }
+
+    // 查询各节点的解析器，未设置解析器（或该链没有ENS注册表）时为 None
+    async fn resolvers(&self, nodes: &[B256]) -> Vec<Result<Option<Address>, String>> {
+        let calls: Vec<(Address, Vec<u8>)> = nodes
+            .iter()
+            .map(|node| (ENS_REGISTRY, IENSRegistry::resolverCall { node: *node }.abi_encode()))
+            .collect();
+        self.calls(&calls)
+            .await
+            .into_iter()
+            .map(|result| {
+                let data = result?;
+                Ok(IENSRegistry::resolverCall::abi_decode_returns(&data).ok().filter(|resolver| !resolver.is_zero()))
+            })
+            .collect()
+    }
+
+    async fn resolve(&self, names: &[String]) -> Vec<Result<Address, String>> {
+        let nodes: Vec<B256> = names.iter().map(|name| namehash(name)).collect();
+        let resolvers = self.resolvers(&nodes).await;
+
+        let lookups: Vec<(usize, (Address, Vec<u8>))> = resolvers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resolver)| {
+                let resolver = (*resolver.as_ref().ok()?)?;
+                Some((index, (resolver, IENSResolver::addrCall { node: nodes[index] }.abi_encode())))
+            })
+            .collect();
+        let calls: Vec<(Address, Vec<u8>)> = lookups.iter().map(|(_, call)| call.clone()).collect();
+        let mut addresses: HashMap<usize, Result<Vec<u8>, String>> =
+            lookups.iter().map(|(index, _)| *index).zip(self.calls(&calls).await).collect();
+
+        names
+            .iter()
+            .zip(resolvers)
+            .enumerate()
+            .map(|(index, (name, resolver))| match resolver {
+                Err(e) => Err(format!("解析 {name} 失败: {e}")),
+                Ok(None) => Err(format!("{name} 未注册或当前链不支持ENS")),
+                Ok(Some(_)) => {
+                    let data = addresses.remove(&index).unwrap_or_else(|| Err("缺少RPC响应".to_string()))?;
+                    IENSResolver::addrCall::abi_decode_returns(&data)
+                        .ok()
+                        .filter(|address| !address.is_zero())
+                        .ok_or_else(|| format!("{name} 未设置解析地址"))
+                }
+            })
+            .collect()
+    }
+
+    // 反向解析地址的主名称，并正向校验名称确实指回该地址
+    async fn reverse(&self, addresses: &[Address]) -> Vec<Option<String>> {
+        let nodes: Vec<B256> = addresses.iter().map(reverse_node).collect();
+        let resolvers = self.resolvers(&nodes).await;
+        let lookups: Vec<(usize, (Address, Vec<u8>))> = resolvers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resolver)| {
+                let resolver = (*resolver.as_ref().ok()?)?;
+                Some((index, (resolver, IENSResolver::nameCall { node: nodes[index] }.abi_encode())))
+            })
+            .collect();
+        let calls: Vec<(Address, Vec<u8>)> = lookups.iter().map(|(_, call)| call.clone()).collect();
+
+        let mut names: Vec<Option<String>> = vec![None; addresses.len()];
+        for ((index, _), result) in lookups.iter().zip(self.calls(&calls).await) {
+            names[*index] = result
+                .ok()
+                .and_then(|data| IENSResolver::nameCall::abi_decode_returns(&data).ok())
+                .and_then(|name| normalize_name(&name).ok())
+                .filter(|name| !name.is_empty());
+        }
+
+        let claimed: Vec<(usize, String)> =
+            names.iter().enumerate().filter_map(|(index, name)| Some((index, name.clone()?))).collect();
+        let claimed_names: Vec<String> = claimed.iter().map(|(_, name)| name.clone()).collect();
+        let forward = self.resolve(&claimed_names).await;
+        for ((index, _), resolved) in claimed.iter().zip(forward) {
+            if resolved.ok() != Some(addresses[*index]) {
+                names[*index] = None;
+            }
+        }
+        names
+    }
+}
+
+/// 解析一批ENS名称，结果与传入顺序一致，不锁定
+pub async fn resolve_names(chain: &str, window_id: Option<&str>, names: &[String]) -> Vec<Result<Address, String>> {
+    let normalized: Vec<Result<String, String>> = names.iter().map(|name| normalize_name(name)).collect();
+    let valid: Vec<String> = normalized.iter().filter_map(|name| name.as_ref().ok().cloned()).collect();
+    let mut resolved = if valid.is_empty() {
+        Vec::new()
+    } else {
+        match EnsClient::new(chain, window_id).await {
+            Ok(client) => client.resolve(&valid).await,
+            Err(e) => vec![Err(e); valid.len()],
+        }
+    }
+    .into_iter();
+    normalized
+        .into_iter()
+        .map(|name| {
+            name?;
+            resolved.next().unwrap_or_else(|| Err("缺少RPC响应".to_string()))
+        })
+        .collect()
+}
+
+fn lock_name(chain: &str, window_id: Option<&str>, name: String, address: Address) {
+    if let Some(window_id) = window_id {
+        LOCKED_NAMES.lock().unwrap().entry(window_id.to_string()).or_default().insert((chain.to_string(), name), address);
+    }
+}
+
+fn locked_address(chain: &str, window_id: Option<&str>, name: &str) -> Option<Address> {
+    LOCKED_NAMES.lock().unwrap().get(window_id?)?.get(&(chain.to_string(), name.to_string())).copied()
+}
+
+/// 解析一批ENS名称并锁定到窗口对应的批次，已锁定的同名结果会被刷新
+pub async fn resolve_and_lock(chain: &str, window_id: Option<&str>, names: &[String]) -> Vec<Result<Address, String>> {
+    let results = resolve_names(chain, window_id, names).await;
+    for (name, result) in names.iter().zip(&results) {
+        if let (Ok(name), Ok(address)) = (normalize_name(name), result) {
+            println!("[ENS] {name} 解析为 {address:?}");
+            lock_name(chain, window_id, name, *address);
+        }
+    }
+    results
+}
+
+/// 释放窗口对应批次锁定的解析结果
+pub fn release_locked_names(window_id: &str) {
+    LOCKED_NAMES.lock().unwrap().remove(window_id);
+}
+
+/// 解析接收地址：十六进制地址直接使用，ENS名称优先使用本批次已锁定的解析结果
+pub async fn resolve_recipient(chain: &str, window_id: Option<&str>, to_addr: &str) -> Result<Address, String> {
+    let to_addr = to_addr.trim();
+    if !is_ens_name(to_addr) {
+        return to_addr.parse().map_err(|e| format!("目标地址格式错误: {e}，请检查地址格式是否正确"));
+    }
+    let name = normalize_name(to_addr)?;
+    if let Some(address) = locked_address(chain, window_id, &name) {
+        return Ok(address);
+    }
+    resolve_and_lock(chain, window_id, &[name])
+        .await
+        .pop()
+        .unwrap_or_else(|| Err("缺少RPC响应".to_string()))
+}
+
+/// 批量反向解析地址的ENS主名称，查询失败或未设置时为 None
+pub async fn lookup_names(chain: &str, window_id: Option<&str>, addresses: &[Address]) -> Vec<Option<String>> {
+    if addresses.is_empty() {
+        return Vec::new();
+    }
+    match EnsClient::new(chain, window_id).await {
+        Ok(client) => client.reverse(addresses).await,
+        Err(e) => {
+            println!("[WARN] ENS反向解析失败: {e}");
+            vec![None; addresses.len()]
+        }
+    }
+}
+
+// Tauri命令：批量开始前解析并锁定接收地址中的ENS名称
+#[tauri::command]
+pub async fn resolve_recipient_names(
+    chain: String,
+    names: Vec<String>,
+    window_id: Option<String>,
+) -> Result<Vec<NameResolution>, String> {
+    let names: Vec<String> = names.into_iter().filter(|name| is_ens_name(name)).collect();
+    let results = resolve_and_lock(&chain, window_id.as_deref(), &names).await;
+    Ok(names
+        .into_iter()
+        .zip(results)
+        .map(|(name, result)| NameResolution {
+            name,
+            address: result.as_ref().ok().map(|address| format!("{address:?}")),
+            error_msg: result.err(),
+        })
+        .collect())
+}
+
+// Tauri命令：批量结束后释放本窗口锁定的ENS解析结果
+#[tauri::command]
+pub async fn release_recipient_names(window_id: String) -> Result<(), String> {
+    release_locked_names(&window_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            format!("{:?}", namehash("eth")),
+            "0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            format!("{:?}", namehash("foo.eth")),
+            "0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+        assert!(is_ens_name("vitalik.eth"));
+        assert_eq!(normalize_name(" Vitalik.ETH. ").unwrap(), "vitalik.eth");
+        assert!(normalize_name("vitalik..eth").is_err());
+        assert!(!is_ens_name("0x52908400098527886E0F7030069857D2E4169EE7"));
+    }
+
+    #[test]
+    fn test_lock_and_release_names() {
+        let (first, second) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        lock_name("eth", Some("lock-test"), "a.eth".to_string(), first);
+        assert_eq!(locked_address("eth", Some("lock-test"), "a.eth"), Some(first));
+        assert_eq!(locked_address("bsc", Some("lock-test"), "a.eth"), None);
+
+        // 释放后重新解析得到新的地址
+        release_locked_names("lock-test");
+        assert_eq!(locked_address("eth", Some("lock-test"), "a.eth"), None);
+        lock_name("eth", Some("lock-test"), "a.eth".to_string(), second);
+        assert_eq!(locked_address("eth", Some("lock-test"), "a.eth"), Some(second));
+        release_locked_names("lock-test");
+
+        // 没有窗口ID时不锁定，不会留下无法释放的结果
+        lock_name("eth", None, "a.eth".to_string(), first);
+        assert_eq!(locked_address("eth", None, "a.eth"), None);
+        assert!(!LOCKED_NAMES.lock().unwrap().contains_key(""));
+    }
+}
@@ -0,0 +1,190 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use futures::future::join_all;
+use crate::database::{get_database_manager, rpc_service::RpcService};
+use super::rpc_batch::{call_single, http_client, parse_quantity};
+
+// 归档节点检测结果的有效期
+const ARCHIVE_CHECK_TTL: Duration = Duration::from_secs(30 * 60);
+
+// RPC地址 -> (是否可查询历史状态, 检测时间)
+static ARCHIVE_SUPPORT: LazyLock<Mutex<HashMap<String, (bool, Instant)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 历史余额查询的区块：指定高度，或UTC时间戳（秒，取该时间点及之前的最后一个区块）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoricalBlock {
+    Number(u64),
+    Timestamp(u64),
+}
+
+/// 解析后的历史查询目标：区块高度及可服务该查询的归档节点
+#[derive(Debug, Clone)]
+pub struct HistoricalTarget {
+    pub block_number: u64,
+    pub rpcs: Vec<(String, usize)>, // (RPC地址, 批量请求上限)
+}
+
+impl HistoricalTarget {
+    /// 用于 JSON-RPC 请求的区块参数
+    pub fn block_tag(&self) -> String {
+        format!("0x{:x}", self.block_number)
+    }
+
+    /// 随机选择一个归档节点
+    pub fn random_rpc(&self) -> (String, usize) {
+        self.rpcs[rand::random::<usize>() % self.rpcs.len()].clone()
+    }
+}
+
+// 区块的 (高度, 时间戳)
+async fn block_header(client: &Client, rpc_url: &str, tag: Value) -> Result<(u64, u64), String> {
+    let block = call_single(client, rpc_url, "eth_getBlockByNumber", json!([tag, false])).await?;
+    if block.is_null() {
+        return Err(format!("区块不存在: {tag}"));
+    }
+    let number = block.get("number").and_then(parse_quantity);
+    let timestamp = block.get("timestamp").and_then(parse_quantity);
+    number.zip(timestamp).ok_or_else(|| "区块数据格式错误".to_string())
+}
+
+// 节点明确表示该区块的状态不可用（已裁剪），只认JSON-RPC错误响应，网络等错误不算
+fn is_missing_state_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.starts_with("rpc error")
+        && ["missing trie node", "not available", "pruned", "header not found", "no state"]
+            .iter()
+            .any(|keyword| error.contains(keyword))
+}
+
+// 节点能否读取早期区块的状态；非归档节点通常返回 missing trie node 等错误
+//
+// 只缓存确定的结果：探测成功，或节点明确返回状态不可用。超时、连接失败等暂时性错误
+// 本次视为不可用，但不写入缓存，下次查询重新检测。
+async fn is_archive_node(client: &Client, rpc_url: &str) -> bool {
+    let cached = ARCHIVE_SUPPORT.lock().unwrap().get(rpc_url).copied();
+    if let Some((archive, checked_at)) = cached
+        && checked_at.elapsed() < ARCHIVE_CHECK_TTL
+    {
+        return archive;
+    }
+
+    let probe = call_single(
+        client,
+        rpc_url,
+        "eth_getBalance",
+        json!(["0x0000000000000000000000000000000000000000", "0x1"]),
+    ).await;
+    let archive = match &probe {
+        Ok(_) => true,
+        Err(e) if is_missing_state_error(e) => {
+            println!("[INFO] RPC节点不支持历史状态查询: {rpc_url}, 错误: {e}");
+            false
+        }
+        Err(e) => {
+            println!("[WARN] 检测归档节点失败，本次跳过: {rpc_url}, 错误: {e}");
+            return false;
+        }
+    };
+    ARCHIVE_SUPPORT.lock().unwrap().insert(rpc_url.to_string(), (archive, Instant::now()));
+    archive
+}
+
+// 二分查找时间戳不晚于 target 的最后一个区块，要求 timestamp(low) <= target < timestamp(high)
+async fn search_block<F, Fut>(mut low: u64, mut high: u64, target: u64, mut timestamp_of: F) -> Result<u64, String>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<u64, String>>,
+{
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if timestamp_of(mid).await? <= target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// 把UTC时间戳换算为区块高度
+pub async fn block_at_timestamp(client: &Client, rpc_url: &str, timestamp: u64) -> Result<u64, String> {
+    let (latest_number, latest_timestamp) = block_header(client, rpc_url, json!("latest")).await?;
+    if timestamp >= latest_timestamp {
+        return Ok(latest_number);
+    }
+    let (_, genesis_timestamp) = block_header(client, rpc_url, json!("0x0")).await?;
+    if timestamp < genesis_timestamp {
+        return Err(format!("时间戳 {timestamp} 早于创世区块"));
+    }
+
+    search_block(0, latest_number, timestamp, |number| async move {
+        block_header(client, rpc_url, json!(format!("0x{number:x}")))
+            .await
+            .map(|(_, block_timestamp)| block_timestamp)
+    }).await
+}
+
+/// 解析历史查询目标：检测链上可用的归档节点，并把时间戳换算为区块高度
+pub async fn resolve_historical_block(
+    chain: &str,
+    block: HistoricalBlock,
+    window_id: Option<&str>,
+) -> Result<HistoricalTarget, String> {
+    let rpcs = RpcService::new(get_database_manager().get_pool())
+        .get_rpcs_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC列表失败: {e}"))?;
+
+    let client = http_client(window_id);
+    let checks = join_all(rpcs.iter().map(|(rpc_url, _)| is_archive_node(&client, rpc_url))).await;
+    let rpcs: Vec<(String, usize)> = rpcs.into_iter().zip(checks).filter_map(|(rpc, archive)| archive.then_some(rpc)).collect();
+    if rpcs.is_empty() {
+        return Err(format!("链 {chain} 没有支持历史状态查询的归档节点，请在RPC管理中添加归档节点"));
+    }
+
+    let target = HistoricalTarget { block_number: 0, rpcs };
+    let (rpc_url, _) = target.random_rpc();
+    let block_number = match block {
+        HistoricalBlock::Number(number) => {
+            let (latest_number, _) = block_header(&client, &rpc_url, json!("latest")).await?;
+            if number > latest_number {
+                return Err(format!("区块高度 {number} 超过最新区块 {latest_number}"));
+            }
+            number
+        }
+        HistoricalBlock::Timestamp(timestamp) => block_at_timestamp(&client, &rpc_url, timestamp).await?,
+    };
+    println!("[INFO] 历史余额查询 - 链: {chain}, 区块: {block_number}, 可用归档节点: {}", target.rpcs.len());
+    Ok(HistoricalTarget { block_number, ..target })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_block() {
+        // 区块 n 的时间戳为 1000 + 12n
+        let timestamp_of = |number: u64| async move { Ok(1000 + 12 * number) };
+        assert_eq!(search_block(0, 100, 1000, timestamp_of).await, Ok(0));
+        assert_eq!(search_block(0, 100, 1011, timestamp_of).await, Ok(0));
+        assert_eq!(search_block(0, 100, 1012, timestamp_of).await, Ok(1));
+        assert_eq!(search_block(0, 100, 1000 + 12 * 57 + 5, timestamp_of).await, Ok(57));
+        assert_eq!(search_block(0, 100, 1000 + 12 * 100 - 1, timestamp_of).await, Ok(99));
+    }
+
+    #[test]
+    fn test_is_missing_state_error() {
+        assert!(is_missing_state_error("RPC Error: -32000 - missing trie node 1a2b (path )"));
+        assert!(is_missing_state_error("RPC Error: -32000 - historical state is not available"));
+        assert!(!is_missing_state_error("RPC请求超时，RPC地址: https://rpc.test"));
+        assert!(!is_missing_state_error("RPC请求失败（HTTP状态码 502 Bad Gateway），已达到最大重试次数，RPC地址: https://rpc.test"));
+    }
+}
@@ -1,4 +1,19 @@
 pub mod transfer;
+pub mod transfer_job;
+pub mod nonce_manager;
+pub mod tx_replacement;
+pub mod tx_tracker;
+pub mod multicall;
+pub mod rpc_batch;
+pub mod historical;
+pub mod activity_profile;
+pub mod token_discovery;
+pub mod nft;
+pub mod balance_snapshot;
+pub mod pricing;
+pub mod recent_transfer;
+pub mod ens;
+pub mod dry_run;
 pub mod token_transfer;
 pub mod provider;
 pub mod chain_config;
@@ -0,0 +1,89 @@
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use alloy_primitives::{Address, Bytes, U256};
+
+// 单次 aggregate3 打包的调用数上限，避免超过节点 eth_call 的gas上限或响应过大
+pub const MULTICALL3_BATCH_SIZE: usize = 300;
+
+sol! {
+    struct Call3 {
+        address target;
+        bool allowFailure;
+        bytes callData;
+    }
+
+    struct Result3 {
+        bool success;
+        bytes returnData;
+    }
+
+    function aggregate3(Call3[] calls) external payable returns (Result3[] returnData);
+    function getEthBalance(address addr) external view returns (uint256 balance);
+    function balanceOf(address account) external view returns (uint256 balance);
+}
+
+/// 通过 Multicall3 自身的 getEthBalance 查询原生币余额
+pub fn native_balance_call(multicall: Address, owner: Address) -> Call3 {
+    Call3 {
+        target: multicall,
+        allowFailure: true,
+        callData: getEthBalanceCall { addr: owner }.abi_encode().into(),
+    }
+}
+
+/// ERC-20 balanceOf 的调用数据
+pub fn balance_of_calldata(owner: Address) -> Bytes {
+    balanceOfCall { account: owner }.abi_encode().into()
+}
+
+/// ERC-20 balanceOf 调用
+pub fn erc20_balance_call(token: Address, owner: Address) -> Call3 {
+    Call3 {
+        target: token,
+        allowFailure: true,
+        callData: balance_of_calldata(owner),
+    }
+}
+
+pub fn encode_aggregate3(calls: Vec<Call3>) -> Bytes {
+    aggregate3Call { calls }.abi_encode().into()
+}
+
+pub fn decode_aggregate3(output: &[u8]) -> Result<Vec<Result3>, String> {
+    aggregate3Call::abi_decode_returns(output).map_err(|e| format!("aggregate3 返回数据解析失败: {e}"))
+}
+
+/// 解析单个调用返回的 uint256；调用失败或返回数据不足32字节（如非合约地址）时返回 None
+pub fn decode_uint(result: &Result3) -> Option<U256> {
+    if !result.success || result.returnData.len() < 32 {
+        return None;
+    }
+    Some(U256::from_be_slice(&result.returnData[..32]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::sol_types::SolValue;
+    use crate::database::chain_service::MULTICALL3_ADDRESS;
+
+    #[test]
+    fn test_aggregate3_roundtrip() {
+        let multicall: Address = MULTICALL3_ADDRESS.parse().unwrap();
+        let owner = Address::repeat_byte(0x11);
+        let data = encode_aggregate3(vec![native_balance_call(multicall, owner)]);
+        // aggregate3((address,bool,bytes)[]) 的方法ID
+        assert_eq!(&data[..4], &[0x82, 0xad, 0x56, 0xcb]);
+
+        let returned = vec![
+            Result3 { success: true, returnData: U256::from(42u64).abi_encode().into() },
+            Result3 { success: false, returnData: Bytes::new() },
+            Result3 { success: true, returnData: Bytes::new() },
+        ];
+        let decoded = decode_aggregate3(&returned.abi_encode()).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decode_uint(&decoded[0]), Some(U256::from(42u64)));
+        assert_eq!(decode_uint(&decoded[1]), None);
+        assert_eq!(decode_uint(&decoded[2]), None);
+    }
+}
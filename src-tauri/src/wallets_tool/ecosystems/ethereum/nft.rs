@@ -0,0 +1,642 @@
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolEvent, SolValue};
+use alloy_primitives::{Address, Bytes, FixedBytes, TxKind, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_signer::Signer;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tauri::Emitter;
+use crate::database::{get_database_manager, rpc_service::RpcService};
+use super::alloy_utils::{DecimalAmount, format_wei_to_gwei, parse_signer};
+use super::nonce_manager::{broadcast_signed, reserve_nonce, report_nonce_gap, sign_transaction};
+use super::ens::resolve_recipient;
+use super::provider::{AlloyProvider, ProviderUtils};
+use super::rpc_batch::{RpcCall, call_single, http_client, parse_quantity, send_batch};
+use super::token_discovery::scan_block_ranges;
+use super::transfer::{
+    TransferConfig, TransferItem, TransferResult, TransferUtils, create_provider, get_rpc_config,
+    get_stop_flag,
+};
+
+sol! {
+    interface IERC165 {
+        function supportsInterface(bytes4 interfaceId) external view returns (bool);
+    }
+
+    interface IERC721 {
+        event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+        function balanceOf(address owner) external view returns (uint256);
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256);
+        function safeTransferFrom(address from, address to, uint256 tokenId) external;
+    }
+
+    interface IERC1155 {
+        event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value);
+        event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values);
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[]);
+        function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes data) external;
+        function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] values, bytes data) external;
+    }
+}
+
+// ERC-165 接口ID
+const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+const ERC721_ENUMERABLE_INTERFACE_ID: [u8; 4] = [0x78, 0x0e, 0x9d, 0x63];
+const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+// estimate_gas 失败时的默认 Gas Limit
+const ERC721_DEFAULT_GAS: u64 = 150_000;
+const ERC1155_DEFAULT_GAS: u64 = 120_000;
+const ERC1155_GAS_PER_EXTRA_ID: u64 = 40_000;
+
+// tokenOfOwnerByIndex 逐个枚举的上限，balanceOf 超过该值时改用日志重建
+const MAX_ENUMERABLE_TOKENS: u64 = 10_000;
+
+/// NFT 标准
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+/// 持有的 NFT 及数量（ERC-721 数量恒为1），token ID 以十进制字符串表示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftTokenAmount {
+    pub token_id: String,
+    #[serde(default)]
+    pub amount: Option<String>, // 查询结果中为持有数量（ERC-721 为1）；转账时仅 ERC-1155 使用，为空时转出全部持有数量
+}
+
+/// NFT 持有查询参数，未指定起始区块时日志重建从创世区块开始扫描
+#[derive(Debug, Clone, Deserialize)]
+pub struct NftHoldingsParams {
+    pub chain: String,
+    pub contract_address: String,
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    #[serde(default)]
+    pub window_id: Option<String>,
+}
+
+/// 单个地址的持有结果
+#[derive(Debug, Clone, Serialize)]
+pub struct NftOwnerHoldings {
+    pub address: String,
+    pub source: String, // "enumerable" 或 "logs"
+    pub tokens: Vec<NftTokenAmount>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NftHoldingsResult {
+    pub standard: NftStandard,
+    pub items: Vec<NftOwnerHoldings>,
+}
+
+/// NFT 批量转账配置，delay/error_retry/error_count_limit 与代币转账含义一致
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NftTransferConfig {
+    pub chain: String,
+    pub contract_address: String,
+    #[serde(default)]
+    pub standard: Option<NftStandard>, // 为空时通过 ERC-165 自动识别
+    pub delay: [u64; 2],
+    pub limit_type: String,
+    pub limit_count: u64,
+    pub limit_count_list: [u64; 2],
+    pub gas_price_type: String,
+    pub gas_price: f64,
+    pub gas_price_rate: f64,
+    pub max_gas_price: f64,
+    #[serde(default)]
+    pub fee_tier: String,
+    pub error_retry: String,
+    pub error_count_limit: u32,
+    #[serde(default)]
+    pub window_id: Option<String>,
+}
+
+impl NftTransferConfig {
+    // 复用原生币转账的 Gas 计算逻辑
+    fn gas_config(&self) -> TransferConfig {
+        TransferConfig {
+            chain: self.chain.clone(),
+            delay: self.delay,
+            transfer_type: "1".to_string(),
+            transfer_amount: DecimalAmount::default(),
+            transfer_amount_list: Default::default(),
+            left_amount_list: Default::default(),
+            amount_precision: 0,
+            limit_type: self.limit_type.clone(),
+            limit_count: self.limit_count,
+            limit_count_list: self.limit_count_list,
+            gas_price_type: self.gas_price_type.clone(),
+            gas_price: self.gas_price,
+            gas_price_rate: self.gas_price_rate,
+            max_gas_price: self.max_gas_price,
+            fee_tier: self.fee_tier.clone(),
+            error_retry: self.error_retry.clone(),
+            error_count_limit: self.error_count_limit,
+            window_id: self.window_id.clone(),
+        }
+    }
+}
+
+// 只读调用的执行环境
+struct NftReader {
+    client: Client,
+    rpc_url: String,
+    max_batch_size: usize,
+    contract: Address,
+}
+
+impl NftReader {
+    async fn new(chain: &str, contract: Address, window_id: Option<&str>) -> Result<Self, String> {
+        let (rpc_url, max_batch_size) = RpcService::new(get_database_manager().get_pool())
+            .get_random_rpc_with_batch_size(chain)
+            .await
+            .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+        Ok(Self { client: http_client(window_id), rpc_url, max_batch_size, contract })
+    }
+
+    // 批量 eth_call 合约，返回原始返回数据
+    async fn call_many(&self, calls: Vec<Vec<u8>>) -> Vec<Result<Vec<u8>, String>> {
+        let calls: Vec<RpcCall> = calls
+            .into_iter()
+            .map(|data| RpcCall::new("eth_call", json!([{
+                "to": format!("{:?}", self.contract),
+                "data": format!("0x{}", alloy_primitives::hex::encode(data)),
+            }, "latest"])))
+            .collect();
+        send_batch(&self.client, &self.rpc_url, &calls, self.max_batch_size)
+            .await
+            .into_iter()
+            .map(|result| {
+                let value = result?;
+                let hex = value.as_str().ok_or_else(|| "eth_call 返回格式错误".to_string())?;
+                alloy_primitives::hex::decode(hex).map_err(|e| format!("eth_call 返回数据解析失败: {e}"))
+            })
+            .collect()
+    }
+
+    async fn latest_block(&self) -> Result<u64, String> {
+        let value = call_single(&self.client, &self.rpc_url, "eth_blockNumber", json!([])).await?;
+        parse_quantity(&value).ok_or_else(|| "区块高度格式错误".to_string())
+    }
+
+    // 通过 ERC-165 识别标准，返回 (标准, 是否支持 Enumerable)
+    async fn detect_standard(&self) -> Result<(NftStandard, bool), String> {
+        let calls = [ERC721_INTERFACE_ID, ERC721_ENUMERABLE_INTERFACE_ID, ERC1155_INTERFACE_ID]
+            .map(|id| IERC165::supportsInterfaceCall { interfaceId: FixedBytes(id) }.abi_encode())
+            .to_vec();
+        let supports: Vec<bool> = self
+            .call_many(calls)
+            .await
+            .into_iter()
+            .map(|result| {
+                result
+                    .ok()
+                    .and_then(|data| IERC165::supportsInterfaceCall::abi_decode_returns(&data).ok())
+                    .unwrap_or(false)
+            })
+            .collect();
+        match (supports[0], supports[1], supports[2]) {
+            (true, enumerable, _) => Ok((NftStandard::Erc721, enumerable)),
+            (false, _, true) => Ok((NftStandard::Erc1155, false)),
+            _ => Err("合约未声明支持 ERC-721 或 ERC-1155 接口".to_string()),
+        }
+    }
+
+    // 扫描合约在区块范围内、接收方为 owner 的日志
+    async fn logs_to(&self, topic0: Value, to_topic_index: usize, owner: Address, from: u64, to: u64) -> Result<Vec<Value>, String> {
+        let mut topics = vec![topic0, Value::Null, Value::Null, Value::Null];
+        topics[to_topic_index] = json!(format!("{:?}", owner.into_word()));
+        topics.truncate(to_topic_index + 1);
+        scan_block_ranges(from, to, |start, end| {
+            let filter = json!([{
+                "address": format!("{:?}", self.contract),
+                "fromBlock": format!("0x{start:x}"),
+                "toBlock": format!("0x{end:x}"),
+                "topics": topics,
+            }]);
+            async move {
+                match call_single(&self.client, &self.rpc_url, "eth_getLogs", filter).await? {
+                    Value::Array(logs) => Ok(logs),
+                    _ => Err("eth_getLogs 返回格式错误".to_string()),
+                }
+            }
+        }).await
+    }
+
+    // ERC-721 Enumerable：按索引读出全部 token ID
+    async fn erc721_enumerate(&self, owner: Address) -> Result<Vec<U256>, String> {
+        let balance_data = self.call_many(vec![IERC721::balanceOfCall { owner }.abi_encode()]).await.remove(0)?;
+        let balance = IERC721::balanceOfCall::abi_decode_returns(&balance_data)
+            .map_err(|e| format!("balanceOf 返回数据解析失败: {e}"))?;
+        let count = u64::try_from(balance)
+            .ok()
+            .filter(|count| *count <= MAX_ENUMERABLE_TOKENS)
+            .ok_or_else(|| format!("balanceOf 返回 {balance}，超过逐个枚举上限 {MAX_ENUMERABLE_TOKENS}"))?;
+        let calls = (0..count)
+            .map(|index| IERC721::tokenOfOwnerByIndexCall { owner, index: U256::from(index) }.abi_encode())
+            .collect();
+        self.call_many(calls)
+            .await
+            .into_iter()
+            .map(|result| {
+                IERC721::tokenOfOwnerByIndexCall::abi_decode_returns(&result?)
+                    .map_err(|e| format!("tokenOfOwnerByIndex 返回数据解析失败: {e}"))
+            })
+            .collect()
+    }
+
+    // ERC-721 日志重建：收集转入过的 token ID，再用 ownerOf 确认当前仍持有
+    async fn erc721_from_logs(&self, owner: Address, from: u64, to: u64) -> Result<Vec<U256>, String> {
+        let topic0 = json!(format!("{:?}", IERC721::Transfer::SIGNATURE_HASH));
+        let candidates: BTreeSet<U256> = self
+            .logs_to(topic0, 2, owner, from, to)
+            .await?
+            .iter()
+            .filter_map(parse_erc721_token_id)
+            .collect();
+        let candidates: Vec<U256> = candidates.into_iter().collect();
+        let calls = candidates.iter().map(|token_id| IERC721::ownerOfCall { tokenId: *token_id }.abi_encode()).collect();
+        let owners = self.call_many(calls).await;
+        Ok(candidates
+            .into_iter()
+            .zip(owners)
+            .filter(|(_, result)| {
+                // 已销毁的 token ownerOf 会 revert，视为不再持有
+                result
+                    .as_ref()
+                    .ok()
+                    .and_then(|data| IERC721::ownerOfCall::abi_decode_returns(data).ok())
+                    == Some(owner)
+            })
+            .map(|(token_id, _)| token_id)
+            .collect())
+    }
+
+    // ERC-1155 日志重建：收集转入过的 id，再用 balanceOfBatch 读取当前余额
+    async fn erc1155_from_logs(&self, owner: Address, from: u64, to: u64) -> Result<Vec<(U256, U256)>, String> {
+        let topic0 = json!([
+            format!("{:?}", IERC1155::TransferSingle::SIGNATURE_HASH),
+            format!("{:?}", IERC1155::TransferBatch::SIGNATURE_HASH),
+        ]);
+        let ids: BTreeSet<U256> = self
+            .logs_to(topic0, 3, owner, from, to)
+            .await?
+            .iter()
+            .flat_map(parse_erc1155_ids)
+            .collect();
+        let ids: Vec<U256> = ids.into_iter().collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut holdings = Vec::new();
+        for chunk in ids.chunks(200) {
+            let call = IERC1155::balanceOfBatchCall { accounts: vec![owner; chunk.len()], ids: chunk.to_vec() };
+            let data = self.call_many(vec![call.abi_encode()]).await.remove(0)?;
+            let balances = IERC1155::balanceOfBatchCall::abi_decode_returns(&data)
+                .map_err(|e| format!("balanceOfBatch 返回数据解析失败: {e}"))?;
+            holdings.extend(chunk.iter().copied().zip(balances).filter(|(_, balance)| !balance.is_zero()));
+        }
+        Ok(holdings)
+    }
+
+    async fn erc1155_balance(&self, owner: Address, id: U256) -> Result<U256, String> {
+        let call = IERC1155::balanceOfBatchCall { accounts: vec![owner], ids: vec![id] };
+        let data = self.call_many(vec![call.abi_encode()]).await.remove(0)?;
+        IERC1155::balanceOfBatchCall::abi_decode_returns(&data)
+            .map_err(|e| format!("balanceOfBatch 返回数据解析失败: {e}"))?
+            .first()
+            .copied()
+            .ok_or_else(|| "balanceOfBatch 返回为空".to_string())
+    }
+
+    async fn erc721_owner(&self, token_id: U256) -> Result<Address, String> {
+        let data = self.call_many(vec![IERC721::ownerOfCall { tokenId: token_id }.abi_encode()]).await.remove(0)?;
+        IERC721::ownerOfCall::abi_decode_returns(&data).map_err(|e| format!("ownerOf 返回数据解析失败: {e}"))
+    }
+}
+
+// ERC-721 的 Transfer 日志 tokenId 在 topics[3]；同签名的 ERC-20 日志只有3个 topics
+fn parse_erc721_token_id(log: &Value) -> Option<U256> {
+    let topics = log.get("topics")?.as_array()?;
+    if topics.len() != 4 {
+        return None;
+    }
+    topics[3].as_str()?.parse().ok()
+}
+
+// TransferSingle / TransferBatch 日志中的 id 列表
+fn parse_erc1155_ids(log: &Value) -> Vec<U256> {
+    let topic0 = log.get("topics").and_then(|t| t.get(0)).and_then(Value::as_str).unwrap_or_default();
+    let data = log
+        .get("data")
+        .and_then(Value::as_str)
+        .and_then(|hex| alloy_primitives::hex::decode(hex).ok())
+        .unwrap_or_default();
+    if topic0 == format!("{:?}", IERC1155::TransferSingle::SIGNATURE_HASH) {
+        <(U256, U256)>::abi_decode_params(&data).map(|(id, _)| vec![id]).unwrap_or_default()
+    } else if topic0 == format!("{:?}", IERC1155::TransferBatch::SIGNATURE_HASH) {
+        <(Vec<U256>, Vec<U256>)>::abi_decode_params(&data).map(|(ids, _)| ids).unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+fn parse_token_id(token_id: &str) -> Result<U256, String> {
+    token_id.trim().parse::<U256>().map_err(|e| format!("token ID 格式错误: {token_id}, {e}"))
+}
+
+// 构造转账调用数据，返回 (调用数据, 默认 Gas Limit)
+async fn build_transfer_call(
+    reader: &NftReader,
+    standard: NftStandard,
+    from: Address,
+    to: Address,
+    tokens: &[NftTokenAmount],
+) -> Result<(Vec<u8>, u64), String> {
+    match standard {
+        NftStandard::Erc721 => {
+            let [token] = tokens else {
+                return Err("ERC-721 每笔交易只能转出一个 token ID".to_string());
+            };
+            let token_id = parse_token_id(&token.token_id)?;
+            let owner = reader.erc721_owner(token_id).await?;
+            if owner != from {
+                return Err(format!("钱包不持有 token ID {}，当前持有者: {owner:?}", token.token_id));
+            }
+            Ok((IERC721::safeTransferFromCall { from, to, tokenId: token_id }.abi_encode(), ERC721_DEFAULT_GAS))
+        }
+        NftStandard::Erc1155 => {
+            let mut ids = Vec::with_capacity(tokens.len());
+            let mut values = Vec::with_capacity(tokens.len());
+            for token in tokens {
+                let id = parse_token_id(&token.token_id)?;
+                let balance = reader.erc1155_balance(from, id).await?;
+                let amount = match token.amount.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+                    Some(amount) => amount.parse::<U256>().map_err(|e| format!("转出数量格式错误: {amount}, {e}"))?,
+                    None => balance,
+                };
+                if amount.is_zero() || amount > balance {
+                    return Err(format!("token ID {} 余额不足，当前持有: {balance}", token.token_id));
+                }
+                ids.push(id);
+                values.push(amount);
+            }
+            let default_gas = ERC1155_DEFAULT_GAS + ERC1155_GAS_PER_EXTRA_ID * (ids.len() as u64 - 1);
+            let data = if ids.len() == 1 {
+                IERC1155::safeTransferFromCall { from, to, id: ids[0], value: values[0], data: Bytes::new() }.abi_encode()
+            } else {
+                IERC1155::safeBatchTransferFromCall { from, to, ids, values, data: Bytes::new() }.abi_encode()
+            };
+            Ok((data, default_gas))
+        }
+    }
+}
+
+// 自动模式按调用数据估算并留20%余量，固定/随机模式沿用原生币转账的 Gas Limit 规则
+async fn nft_gas_limit(
+    config: &TransferConfig,
+    provider: Arc<AlloyProvider>,
+    tx: &TransactionRequest,
+    default_gas: u64,
+) -> Result<u64, String> {
+    if config.limit_type != "1" {
+        let limit = TransferUtils::get_gas_limit(config, provider, Address::ZERO, Address::ZERO, U256::ZERO).await?;
+        return u64::try_from(limit).map_err(|_| format!("Gas Limit 超出范围: {limit}"));
+    }
+    match provider.estimate_gas(tx.clone()).await {
+        Ok(gas) => Ok(gas * 120 / 100),
+        Err(e) => {
+            println!("[WARN] NFT转账 estimate_gas 失败: {e}, 使用默认值 {default_gas}");
+            Ok(default_gas)
+        }
+    }
+}
+
+async fn nft_transfer_internal<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    index: usize,
+    item: TransferItem,
+    tokens: Vec<NftTokenAmount>,
+    config: NftTransferConfig,
+) -> Result<String, String> {
+    let window_id = config.window_id.as_deref().unwrap_or("");
+    if !window_id.is_empty() && get_stop_flag(window_id) {
+        return Err("用户已停止转账任务".to_string());
+    }
+    if tokens.is_empty() {
+        return Err("未指定要转出的 token ID".to_string());
+    }
+
+    let signer = parse_signer(&item.private_key)?;
+
+    let provider = create_provider(&config.chain, config.window_id.as_deref()).await
+        .map_err(|e| format!("获取RPC提供商失败: {e}"))?;
+    let chain_id = match ProviderUtils::get_chain_id(&config.chain).await {
+        Ok(id) => id,
+        Err(_) => match get_rpc_config(&config.chain).await {
+            Some(c) => c.chain_id,
+            None => return Err(format!("无法获取链 '{}' 的配置信息", config.chain)),
+        },
+    };
+    let signer = signer.with_chain_id(Some(chain_id));
+    let wallet_address = signer.address();
+
+    if item.to_addr.trim().is_empty() {
+        return Err("目标地址不能为空".to_string());
+    }
+    let to_address = resolve_recipient(&config.chain, config.window_id.as_deref(), &item.to_addr).await?;
+    let contract_address: Address = config.contract_address.trim().parse()
+        .map_err(|e| format!("NFT合约地址格式错误: {e}"))?;
+
+    let reader = NftReader::new(&config.chain, contract_address, config.window_id.as_deref()).await?;
+    let standard = match config.standard {
+        Some(standard) => standard,
+        None => reader.detect_standard().await?.0,
+    };
+    let (data, default_gas) = build_transfer_call(&reader, standard, wallet_address, to_address, &tokens).await?;
+
+    if !window_id.is_empty() && get_stop_flag(window_id) {
+        return Err("用户已停止转账任务".to_string());
+    }
+
+    let gas_config = config.gas_config();
+    let gas_fee = TransferUtils::get_gas_fee(&gas_config, provider.clone()).await
+        .map_err(|e| format!("获取Gas Price失败: {e}"))?;
+    if gas_fee.gas_price.is_zero() {
+        return Err("获取到的 gas price 为0".to_string());
+    }
+
+    let mut tx = TransactionRequest {
+        from: Some(wallet_address),
+        to: Some(TxKind::Call(contract_address)),
+        input: Bytes::from(data).into(),
+        value: Some(U256::ZERO),
+        ..Default::default()
+    };
+    let gas_limit = nft_gas_limit(&gas_config, provider.clone(), &tx, default_gas).await?;
+    tx.gas = Some(gas_limit);
+    gas_fee.apply_to(&mut tx);
+
+    let token_ids: Vec<&str> = tokens.iter().map(|t| t.token_id.as_str()).collect();
+    println!("序号：{index}, NFT转账 token ID: {token_ids:?}, gas_limit: {gas_limit}, gas_price: {} gwei",
+        format_wei_to_gwei(gas_fee.gas_price));
+
+    let _ = app_handle.emit("transfer_status_update", serde_json::json!({
+        "index": index - 1,
+        "error_msg": "发送交易中...",
+        "exec_status": "1"
+    }));
+
+    // 再次检查停止状态 - 在发送交易之前 (最关键的拦截点)
+    if !window_id.is_empty() && get_stop_flag(window_id) {
+        return Err("用户已停止转账任务".to_string());
+    }
+
+    // 一对多转账时同一钱包连续发送，本地分配nonce避免冲突
+    let nonce_lease = reserve_nonce(&config.chain, wallet_address, provider.as_ref()).await?;
+    report_nonce_gap(&app_handle, &nonce_lease);
+    tx.nonce = Some(nonce_lease.nonce());
+
+    // 本地签名后广播，节点明确拒绝时nonce自动归还，结果不明时按已广播占用
+    let (raw_tx, tx_hash) = sign_transaction(&signer, tx, &nonce_lease).await?;
+    broadcast_signed(provider.as_ref(), &raw_tx, tx_hash, nonce_lease, None).await
+        .map_err(|e| e.to_string())?;
+
+    let tx_hash_str = format!("{tx_hash:?}");
+    println!("序号：{index}, 交易 hash 为：{tx_hash_str}");
+
+    let _ = app_handle.emit("transfer_status_update", serde_json::json!({
+        "index": index - 1,
+        "error_msg": format!("已提交，等待确认: {}", &tx_hash_str[..20]),
+        "exec_status": "1"
+    }));
+
+    Ok(tx_hash_str)
+}
+
+// Tauri命令：查询地址在指定合约下持有的 NFT（优先 Enumerable，否则通过日志重建）
+#[tauri::command]
+pub async fn query_nft_holdings(params: NftHoldingsParams) -> Result<NftHoldingsResult, String> {
+    let contract: Address = params.contract_address.trim().parse()
+        .map_err(|e| format!("NFT合约地址格式错误: {e}"))?;
+    let reader = NftReader::new(&params.chain, contract, params.window_id.as_deref()).await?;
+    let (standard, enumerable) = reader.detect_standard().await?;
+    let from_block = params.from_block.unwrap_or(0);
+    let to_block = reader.latest_block().await?;
+
+    let mut items = Vec::with_capacity(params.addresses.len());
+    for address in &params.addresses {
+        let address = address.trim().to_string();
+        let mut holdings = NftOwnerHoldings { address: address.clone(), source: "logs".to_string(), tokens: Vec::new(), error_msg: None };
+        let owner = match address.parse::<Address>() {
+            Ok(owner) => owner,
+            Err(e) => {
+                holdings.error_msg = Some(format!("地址格式错误: {e}"));
+                items.push(holdings);
+                continue;
+            }
+        };
+
+        let result = match standard {
+            NftStandard::Erc721 => {
+                let enumerated = if enumerable {
+                    reader.erc721_enumerate(owner).await.inspect_err(|e| {
+                        println!("[WARN] tokenOfOwnerByIndex 查询失败，改用日志重建: {e}");
+                    }).ok()
+                } else {
+                    None
+                };
+                match enumerated {
+                    Some(ids) => {
+                        holdings.source = "enumerable".to_string();
+                        Ok(ids.into_iter().map(|id| (id, U256::from(1))).collect())
+                    }
+                    None => reader.erc721_from_logs(owner, from_block, to_block).await
+                        .map(|ids| ids.into_iter().map(|id| (id, U256::from(1))).collect()),
+                }
+            }
+            NftStandard::Erc1155 => reader.erc1155_from_logs(owner, from_block, to_block).await,
+        };
+
+        match result {
+            Ok(tokens) => {
+                holdings.tokens = tokens
+                    .into_iter()
+                    .map(|(id, amount)| NftTokenAmount { token_id: id.to_string(), amount: Some(amount.to_string()) })
+                    .collect();
+            }
+            Err(e) => holdings.error_msg = Some(e),
+        }
+        items.push(holdings);
+    }
+
+    Ok(NftHoldingsResult { standard, items })
+}
+
+// Tauri命令：NFT转账（一次调用发送一笔 safeTransferFrom，间隔与重试由批量转账流程控制）
+#[tauri::command]
+pub async fn nft_transfer<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    index: usize,
+    item: TransferItem,
+    tokens: Vec<NftTokenAmount>,
+    config: NftTransferConfig,
+) -> Result<TransferResult, String> {
+    match nft_transfer_internal(app_handle, index, item, tokens, config).await {
+        Ok(tx_hash) => Ok(TransferResult {
+            success: true,
+            tx_hash: Some(tx_hash),
+            error: None,
+        }),
+        Err(e) => Ok(TransferResult {
+            success: false,
+            tx_hash: None,
+            error: Some(e),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nft_logs() {
+        let erc721_log = json!({
+            "topics": [
+                format!("{:?}", IERC721::Transfer::SIGNATURE_HASH),
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "0x000000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa96045",
+                "0x000000000000000000000000000000000000000000000000000000000000002a",
+            ],
+            "data": "0x",
+        });
+        assert_eq!(parse_erc721_token_id(&erc721_log), Some(U256::from(42)));
+
+        let single_log = json!({
+            "topics": [format!("{:?}", IERC1155::TransferSingle::SIGNATURE_HASH)],
+            "data": format!("0x{}", alloy_primitives::hex::encode((U256::from(7), U256::from(3)).abi_encode_params())),
+        });
+        assert_eq!(parse_erc1155_ids(&single_log), vec![U256::from(7)]);
+
+        let batch_data = (vec![U256::from(1), U256::from(2)], vec![U256::from(5), U256::from(6)]).abi_encode_params();
+        let batch_log = json!({
+            "topics": [format!("{:?}", IERC1155::TransferBatch::SIGNATURE_HASH)],
+            "data": format!("0x{}", alloy_primitives::hex::encode(batch_data)),
+        });
+        assert_eq!(parse_erc1155_ids(&batch_log), vec![U256::from(1), U256::from(2)]);
+    }
+}
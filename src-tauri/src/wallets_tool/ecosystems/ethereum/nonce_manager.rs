@@ -0,0 +1,378 @@
+use alloy::transports::{RpcError, TransportErrorKind};
+use alloy_network::{EthereumWallet, TransactionBuilder, eip2718::Encodable2718};
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_signer_local::PrivateKeySigner;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::fmt;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, LazyLock};
+use tauri::Emitter;
+
+// 全局Nonce管理器：按 (链, 地址) 在本地分配nonce，避免同一钱包并发发送时重复取号
+static NONCE_STATES: LazyLock<Mutex<HashMap<(String, Address), AccountNonceState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Default)]
+struct AccountNonceState {
+    // 下一个从未分配过的nonce
+    next: u64,
+    // 已分配但尚未广播的nonce
+    reserved: BTreeSet<u64>,
+    // 已广播、链上pending计数尚未越过的nonce
+    broadcast: BTreeSet<u64>,
+    // 节点明确拒绝而归还的nonce，优先复用；未复用前会阻塞更大的nonce
+    released: BTreeSet<u64>,
+    // 见过的最大链上pending值，本地不再分配低于它的nonce
+    max_pending: u64,
+}
+
+impl AccountNonceState {
+    // 使用链上 pending nonce 校准本地状态
+    //
+    // pending回退（交易被丢弃、重组或节点落后）时保留见过的最大值，本地不重复分配；
+    // 缺口检测使用本次获取的pending，回退到本地已分配nonce之下的部分会被报告。
+    fn reconcile(&mut self, pending: u64) {
+        self.max_pending = self.max_pending.max(pending);
+        if self.max_pending > self.next {
+            // 钱包在别处发过交易，本地计数落后
+            self.next = self.max_pending;
+        }
+        let passed = self.max_pending;
+        self.broadcast.retain(|n| *n >= passed);
+        self.released.retain(|n| *n >= passed);
+    }
+
+    // 链上pending与本地已分配之间缺失的nonce（既未在途也未广播，包括已归还待复用的）
+    fn gaps(&self, pending: u64) -> Vec<u64> {
+        (pending..self.next)
+            .filter(|n| !self.reserved.contains(n) && !self.broadcast.contains(n))
+            .collect()
+    }
+
+    fn allocate(&mut self) -> u64 {
+        let nonce = match self.released.pop_first() {
+            Some(n) => n,
+            None => {
+                let n = self.next;
+                self.next += 1;
+                n
+            }
+        };
+        self.reserved.insert(nonce);
+        nonce
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NonceGapReport {
+    pub chain: String,
+    pub address: String,
+    pub pending_nonce: u64,
+    pub next_local_nonce: u64,
+    pub missing_nonces: Vec<u64>,
+}
+
+/// 已分配的nonce租约
+///
+/// 广播成功或结果不明时调用 `commit`；未提交就被丢弃（广播前失败、节点明确拒绝等）时自动归还nonce。
+pub struct NonceLease {
+    chain: String,
+    address: Address,
+    nonce: u64,
+    committed: bool,
+    gap: Option<NonceGapReport>,
+}
+
+impl NonceLease {
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// 分配时检测到的nonce缺口
+    pub fn gap(&self) -> Option<&NonceGapReport> {
+        self.gap.as_ref()
+    }
+
+    /// 交易已广播（或可能已广播），nonce正式占用
+    pub fn commit(mut self) {
+        self.committed = true;
+        let mut states = NONCE_STATES.lock().unwrap();
+        if let Some(state) = states.get_mut(&(self.chain.clone(), self.address)) {
+            state.reserved.remove(&self.nonce);
+            state.broadcast.insert(self.nonce);
+        }
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let mut states = NONCE_STATES.lock().unwrap();
+        if let Some(state) = states.get_mut(&(self.chain.clone(), self.address)) {
+            state.reserved.remove(&self.nonce);
+            state.released.insert(self.nonce);
+        }
+    }
+}
+
+/// 为指定钱包分配下一个nonce
+///
+/// 每次分配都会读取 `eth_getTransactionCount(pending)` 进行校准：链上计数领先时跟进，
+/// 负载均衡RPC返回偏旧的值时以本地计数为准，同时报告无法解释的缺口。
+pub async fn reserve_nonce<P: Provider>(
+    chain: &str,
+    address: Address,
+    provider: &P,
+) -> Result<NonceLease, String> {
+    let pending = provider
+        .get_transaction_count(address)
+        .pending()
+        .await
+        .map_err(|e| format!("获取nonce失败: {e}"))?;
+
+    let mut states = NONCE_STATES.lock().unwrap();
+    let state = states.entry((chain.to_string(), address)).or_default();
+    state.reconcile(pending);
+    let nonce = state.allocate();
+
+    // 分配之后再检测，本次复用的已归还nonce不算缺口
+    let missing = state.gaps(pending);
+    let gap = if missing.is_empty() {
+        None
+    } else {
+        println!(
+            "[WARN] nonce缺口 - 链: {chain}, 地址: {address:?}, 链上pending: {pending}, 本地下一个: {}, 缺失: {missing:?}",
+            state.next
+        );
+        Some(NonceGapReport {
+            chain: chain.to_string(),
+            address: format!("{address:?}"),
+            pending_nonce: pending,
+            next_local_nonce: state.next,
+            missing_nonces: missing,
+        })
+    };
+
+    Ok(NonceLease {
+        chain: chain.to_string(),
+        address,
+        nonce,
+        committed: false,
+        gap,
+    })
+}
+
+/// 广播失败：maybe_broadcast 为 true 时交易可能已被节点接收，nonce已按占用处理
+#[derive(Debug)]
+pub struct BroadcastError {
+    pub message: String,
+    pub maybe_broadcast: bool,
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for BroadcastError {}
+
+/// 已签名、尚未广播的交易
+#[derive(Debug, Clone)]
+pub struct PreparedTx {
+    pub tx_hash: String,
+    pub nonce: u64,
+    pub raw_tx: String, // 0x前缀的十六进制原始交易
+}
+
+/// 广播前回调（例如先把交易哈希落库），返回错误时放弃广播
+pub type BeforeBroadcast<'a> = Box<dyn FnOnce(PreparedTx) -> BoxFuture<'a, Result<(), String>> + Send + 'a>;
+
+// 广播失败的分类
+#[derive(Debug, PartialEq)]
+enum SendFailure {
+    // 节点已收录该交易，视为成功
+    AlreadyKnown,
+    // nonce已被占用或结果不明，不能归还
+    MaybeBroadcast,
+    // 请求未发出或节点明确拒绝，可以归还nonce
+    Rejected,
+}
+
+fn classify_send_error(error: &RpcError<TransportErrorKind>) -> SendFailure {
+    match error {
+        RpcError::ErrorResp(payload) => classify_node_error(&payload.message),
+        RpcError::SerError(_) | RpcError::LocalUsageError(_) | RpcError::UnsupportedFeature(_) => SendFailure::Rejected,
+        // 超时、连接中断、响应无法解析：节点可能已接收
+        _ => SendFailure::MaybeBroadcast,
+    }
+}
+
+// 节点返回的错误：除已收录或nonce已被占用外，均表示交易未被接收
+fn classify_node_error(message: &str) -> SendFailure {
+    let message = message.to_lowercase();
+    if message.contains("already known") || message.contains("known transaction") || message.contains("already imported") {
+        SendFailure::AlreadyKnown
+    } else if message.contains("nonce too low") {
+        SendFailure::MaybeBroadcast
+    } else {
+        SendFailure::Rejected
+    }
+}
+
+/// 使用已分配的nonce在本地签名交易，返回可广播的原始交易与交易哈希
+pub async fn sign_transaction(
+    wallet: &PrivateKeySigner,
+    mut tx: TransactionRequest,
+    lease: &NonceLease,
+) -> Result<(Vec<u8>, B256), String> {
+    tx.from = Some(wallet.address());
+    tx.nonce = Some(lease.nonce());
+    tx.chain_id = wallet.chain_id();
+    let envelope = tx
+        .build(&EthereumWallet::from(wallet.clone()))
+        .await
+        .map_err(|e| format!("签名交易失败: {e}"))?;
+    Ok((envelope.encoded_2718(), *envelope.tx_hash()))
+}
+
+/// 广播已签名的原始交易，节点已收录视为成功
+pub async fn send_signed<P: Provider>(provider: &P, raw_tx: &[u8], tx_hash: B256) -> Result<B256, BroadcastError> {
+    let error = match provider.send_raw_transaction(raw_tx).await {
+        Ok(_) => return Ok(tx_hash),
+        Err(e) => e,
+    };
+    match classify_send_error(&error) {
+        SendFailure::AlreadyKnown => Ok(tx_hash),
+        SendFailure::MaybeBroadcast => Err(BroadcastError {
+            message: format!("发送交易结果未知，请勿重复发送（hash: {tx_hash:?}）: {error}"),
+            maybe_broadcast: true,
+        }),
+        SendFailure::Rejected => Err(BroadcastError {
+            message: format!("发送交易失败: {error}"),
+            maybe_broadcast: false,
+        }),
+    }
+}
+
+/// 广播已签名的交易并结算nonce租约
+///
+/// 节点明确拒绝时归还nonce；超时等结果不明的错误按已广播处理，避免下一笔交易复用该nonce。
+pub async fn broadcast_signed<P: Provider>(
+    provider: &P,
+    raw_tx: &[u8],
+    tx_hash: B256,
+    lease: NonceLease,
+    before_broadcast: Option<BeforeBroadcast<'_>>,
+) -> Result<B256, BroadcastError> {
+    if let Some(hook) = before_broadcast {
+        let prepared = PreparedTx {
+            tx_hash: format!("{tx_hash:?}"),
+            nonce: lease.nonce(),
+            raw_tx: format!("0x{}", hex::encode(raw_tx)),
+        };
+        // 回调失败时交易未广播，租约被丢弃后nonce自动归还
+        hook(prepared).await.map_err(|message| BroadcastError { message, maybe_broadcast: false })?;
+    }
+    let result = send_signed(provider, raw_tx, tx_hash).await;
+    if result.as_ref().map_or_else(|e| e.maybe_broadcast, |_| true) {
+        lease.commit();
+    }
+    result
+}
+
+// 通知前端出现nonce缺口
+pub fn report_nonce_gap<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, lease: &NonceLease) {
+    if let Some(gap) = lease.gap() {
+        let _ = app_handle.emit("nonce_gap_detected", gap);
+    }
+}
+
+// Tauri命令：重置本地nonce状态（address为空时重置整条链）
+#[tauri::command]
+pub async fn reset_nonce_manager(chain: String, address: Option<String>) -> Result<(), String> {
+    let address = match address.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        Some(a) => Some(a.parse::<Address>().map_err(|e| format!("地址格式错误: {e}"))?),
+        None => None,
+    };
+    let mut states = NONCE_STATES.lock().unwrap();
+    states.retain(|(c, a), _| c != &chain || address.is_some_and(|addr| addr != *a));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_follows_chain_and_reuses_released() {
+        let mut state = AccountNonceState::default();
+        state.reconcile(5);
+        assert_eq!(state.allocate(), 5);
+        assert_eq!(state.allocate(), 6);
+
+        // 6 发送前失败被归还，下一次优先复用
+        state.reserved.remove(&6);
+        state.released.insert(6);
+        assert_eq!(state.allocate(), 6);
+        assert_eq!(state.allocate(), 7);
+    }
+
+    #[test]
+    fn test_pending_regression_reports_gap() {
+        let mut state = AccountNonceState::default();
+        state.reconcile(10);
+        let n = state.allocate();
+        state.reserved.remove(&n);
+        state.broadcast.insert(n);
+
+        // pending先越过 10，随后回退到 9：回退的部分作为缺口报告，本地不重复分配
+        state.reconcile(11);
+        assert!(state.gaps(11).is_empty());
+        state.reconcile(9);
+        assert_eq!(state.gaps(9), vec![9, 10]);
+        assert_eq!(state.allocate(), 11);
+
+        // pending恢复后缺口消失
+        state.reserved.remove(&11);
+        state.broadcast.insert(11);
+        state.reconcile(11);
+        assert!(state.gaps(11).is_empty());
+    }
+
+    #[test]
+    fn test_send_error_classification() {
+        assert_eq!(classify_node_error("already known"), SendFailure::AlreadyKnown);
+        assert_eq!(classify_node_error("nonce too low: next nonce 8, tx nonce 7"), SendFailure::MaybeBroadcast);
+        assert_eq!(classify_node_error("insufficient funds for gas * price + value"), SendFailure::Rejected);
+        assert_eq!(classify_send_error(&TransportErrorKind::custom_str("request timed out")), SendFailure::MaybeBroadcast);
+        assert_eq!(classify_send_error(&RpcError::NullResp), SendFailure::MaybeBroadcast);
+    }
+
+    #[test]
+    fn test_gap_detection() {
+        let mut state = AccountNonceState::default();
+        state.reconcile(0);
+        for _ in 0..3 {
+            let n = state.allocate();
+            state.reserved.remove(&n);
+            state.broadcast.insert(n);
+        }
+        // nonce 1 从内存池丢失：从本地记录中移除后，链上停在 1
+        state.broadcast.remove(&1);
+        state.reconcile(1);
+        assert_eq!(state.gaps(1), vec![1]);
+
+        // 被节点拒绝归还的nonce同样阻塞后续交易，需要报告
+        let n = state.allocate();
+        assert_eq!(n, 3);
+        state.reserved.remove(&n);
+        state.released.insert(n);
+        assert_eq!(state.gaps(1), vec![1, 3]);
+    }
+}
@@ -0,0 +1,417 @@
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use alloy_primitives::{Address, I256, U256};
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use crate::database::{get_database_manager, chain_service::ChainService, rpc_service::RpcService};
+use crate::database::models::Token;
+use super::alloy_utils::{Rounding, format_units, parse_units, round_units};
+use super::balance_snapshot::BASE_COIN_KEY;
+use super::rpc_batch::{RpcCall, http_client, send_batch};
+
+sol! {
+    interface IChainlinkAggregator {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+
+    interface IUniswapV2Pair {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+    }
+}
+
+/// 支持的链上价格来源：Chainlink聚合器、Uniswap V2交易对储备、按1美元计价的稳定币
+pub const PRICE_SOURCES: [&str; 3] = ["chainlink", "uniswap_v2", "stable"];
+
+/// 价格统一换算为18位小数的美元数值
+const PRICE_DECIMALS: u8 = 18;
+
+// Uniswap V2 报价代币的价格还可以继续来自交易对，最多向下追溯的层数
+const MAX_QUOTE_DEPTH: u8 = 2;
+
+type PriceFuture<'a> = Pin<Box<dyn Future<Output = Result<U256, String>> + Send + 'a>>;
+
+/// 代币的链上美元价格
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenUsdPrice {
+    pub token_key: String,
+    pub symbol: String,
+    pub contract_address: Option<String>,
+    pub price_source: String,
+    pub price_feed: Option<String>,
+    pub usd_price: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+/// 余额查询结果的美元估值：各币种单价（币种key -> 单价）与全部地址的合计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsdValuation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_total: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub usd_prices: HashMap<String, String>,
+    // 配置了价格来源但读取失败的币种：币种key -> 错误信息
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub usd_price_errors: HashMap<String, String>,
+}
+
+fn pow10(exp: u8) -> U256 {
+    U256::from(10u8).pow(U256::from(exp))
+}
+
+// 把 decimals 位小数的数值换算为18位小数
+fn scale_to_price_decimals(value: U256, decimals: u8) -> Result<U256, String> {
+    if decimals <= PRICE_DECIMALS {
+        value.checked_mul(pow10(PRICE_DECIMALS - decimals)).ok_or_else(|| "价格数值溢出".to_string())
+    } else {
+        Ok(value / pow10(decimals - PRICE_DECIMALS))
+    }
+}
+
+// 数据更新时间距今超过 max_age_secs 视为过期，max_age_secs 为0时不检查
+fn check_staleness(updated_at: u64, max_age_secs: u64, now: u64) -> Result<(), String> {
+    if max_age_secs == 0 {
+        return Ok(());
+    }
+    let age = now.saturating_sub(updated_at);
+    if age > max_age_secs {
+        return Err(format!("价格已过期: {age} 秒未更新，允许 {max_age_secs} 秒"));
+    }
+    Ok(())
+}
+
+// Chainlink 报价换算为18位小数，报价不为正数时视为无效
+fn chainlink_price(answer: I256, feed_decimals: u8) -> Result<U256, String> {
+    if !answer.is_positive() {
+        return Err(format!("Chainlink 报价无效: {answer}"));
+    }
+    scale_to_price_decimals(answer.unsigned_abs(), feed_decimals)
+}
+
+// 由交易对储备计算代币价格：price = 报价储备 × 报价价格 × 10^代币精度 / (代币储备 × 10^报价精度)
+fn pair_price(
+    token_reserve: U256,
+    token_decimals: u8,
+    quote_reserve: U256,
+    quote_decimals: u8,
+    quote_price: U256,
+) -> Result<U256, String> {
+    if token_reserve.is_zero() || quote_reserve.is_zero() {
+        return Err("交易对储备为0".to_string());
+    }
+    let numerator = quote_reserve
+        .checked_mul(quote_price)
+        .and_then(|value| value.checked_mul(pow10(token_decimals)))
+        .ok_or_else(|| "价格数值溢出".to_string())?;
+    let denominator = token_reserve
+        .checked_mul(pow10(quote_decimals))
+        .ok_or_else(|| "价格数值溢出".to_string())?;
+    Ok(numerator / denominator)
+}
+
+/// 按美元价格计算余额价值（18位小数），余额为查询结果中的十进制字符串
+pub fn usd_value(balance: &str, price: U256) -> Option<U256> {
+    let balance = parse_units(balance, PRICE_DECIMALS, Rounding::Down).ok()?;
+    balance.checked_mul(price).map(|value| value / pow10(PRICE_DECIMALS))
+}
+
+/// 格式化美元金额，保留2位小数（截断）
+pub fn format_usd(value: U256) -> String {
+    format_units(round_units(value, PRICE_DECIMALS, 2, Rounding::Down), PRICE_DECIMALS)
+}
+
+/// 格式化美元单价
+pub fn format_price(price: U256) -> String {
+    format_units(price, PRICE_DECIMALS)
+}
+
+fn price_key(token: &Token) -> Option<String> {
+    if token.token_type == "base" {
+        Some(BASE_COIN_KEY.to_string())
+    } else {
+        token.contract_address.as_deref().map(|contract| contract.trim().to_lowercase())
+    }
+}
+
+fn parse_feed(token: &Token) -> Result<Address, String> {
+    let feed = token.price_feed.as_deref().map(str::trim).unwrap_or_default();
+    if feed.is_empty() {
+        return Err(format!("{} 未配置价格合约地址", token.symbol));
+    }
+    feed.parse().map_err(|_| format!("价格合约地址无效: {feed}"))
+}
+
+fn decode_result<C: SolCall>(result: Option<Result<Value, String>>) -> Result<C::Return, String> {
+    let value = result.ok_or_else(|| "缺少RPC响应".to_string())??;
+    let data = value
+        .as_str()
+        .and_then(|hex| alloy_primitives::hex::decode(hex).ok())
+        .ok_or_else(|| format!("RPC返回格式错误: {value}"))?;
+    C::abi_decode_returns(&data).map_err(|e| format!("解码{}返回值失败: {e}", C::SIGNATURE))
+}
+
+/// 读取链上价格，同一查询内的价格只读取一次
+pub struct PriceOracle {
+    chain: String,
+    client: Client,
+    rpc_url: String,
+    max_batch_size: usize,
+    max_age_secs: u64,
+    prices: HashMap<String, Result<U256, String>>,
+}
+
+impl PriceOracle {
+    pub async fn new(chain: &str, window_id: Option<&str>) -> Result<Self, String> {
+        let pool = get_database_manager().get_pool();
+        let chain_info = ChainService::new(pool)
+            .get_chain_by_key(chain)
+            .await
+            .map_err(|e| format!("查询链配置失败: {e}"))?
+            .ok_or_else(|| format!("链不存在: {chain}"))?;
+        let (rpc_url, max_batch_size) = RpcService::new(pool)
+            .get_random_rpc_with_batch_size(chain)
+            .await
+            .map_err(|e| format!("获取RPC失败: {e}"))?;
+
+        Ok(Self {
+            chain: chain.to_string(),
+            client: http_client(window_id),
+            rpc_url,
+            max_batch_size,
+            max_age_secs: chain_info.price_max_age_secs.max(0) as u64,
+            prices: HashMap::new(),
+        })
+    }
+
+    /// 按币种key（平台币为 "base"，代币为合约地址）获取价格，未配置价格来源时返回 None
+    pub async fn price_of_key(&mut self, coin_key: &str) -> Option<Result<U256, String>> {
+        let chain_service = ChainService::new(get_database_manager().get_pool());
+        let token = if coin_key == BASE_COIN_KEY {
+            chain_service.get_base_token(&self.chain).await
+        } else {
+            chain_service.get_token_by_contract(&self.chain, coin_key).await
+        };
+        let token = match token {
+            Ok(Some(token)) => token,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(format!("查询代币配置失败: {e}"))),
+        };
+        token.price_source.as_ref()?;
+        Some(self.price_of(&token).await)
+    }
+
+    /// 获取代币价格（18位小数美元）
+    pub async fn price_of(&mut self, token: &Token) -> Result<U256, String> {
+        self.price_with_depth(token.clone(), 0).await
+    }
+
+    fn price_with_depth(&mut self, token: Token, depth: u8) -> PriceFuture<'_> {
+        Box::pin(async move {
+            let key = price_key(&token);
+            if let Some(cached) = key.as_ref().and_then(|key| self.prices.get(key)) {
+                return cached.clone();
+            }
+            let price = match token.price_source.as_deref() {
+                Some("stable") => Ok(pow10(PRICE_DECIMALS)),
+                Some("chainlink") => self.read_chainlink(&token).await,
+                Some("uniswap_v2") => self.read_uniswap_v2(&token, depth).await,
+                Some(source) => Err(format!("不支持的价格来源: {source}")),
+                None => Err(format!("{} 未配置价格来源", token.symbol)),
+            };
+            if let Some(key) = key {
+                self.prices.insert(key, price.clone());
+            }
+            price
+        })
+    }
+
+    async fn call_feed(&self, feed: Address, calls: &[Vec<u8>]) -> Vec<Result<Value, String>> {
+        let calls: Vec<RpcCall> = calls
+            .iter()
+            .map(|data| {
+                RpcCall::new("eth_call", json!([{
+                    "to": format!("{feed:?}"),
+                    "data": format!("0x{}", alloy_primitives::hex::encode(data)),
+                }, "latest"]))
+            })
+            .collect();
+        send_batch(&self.client, &self.rpc_url, &calls, self.max_batch_size).await
+    }
+
+    async fn read_chainlink(&self, token: &Token) -> Result<U256, String> {
+        use IChainlinkAggregator::{decimalsCall, latestRoundDataCall};
+
+        let feed = parse_feed(token)?;
+        let mut results = self
+            .call_feed(feed, &[decimalsCall {}.abi_encode(), latestRoundDataCall {}.abi_encode()])
+            .await
+            .into_iter();
+        let feed_decimals = decode_result::<decimalsCall>(results.next())?;
+        let round = decode_result::<latestRoundDataCall>(results.next())?;
+
+        check_staleness(round.updatedAt.saturating_to(), self.max_age_secs, Utc::now().timestamp() as u64)?;
+        chainlink_price(round.answer, feed_decimals)
+    }
+
+    async fn read_uniswap_v2(&mut self, token: &Token, depth: u8) -> Result<U256, String> {
+        use IUniswapV2Pair::{getReservesCall, token0Call, token1Call};
+
+        let contract: Address = token
+            .contract_address
+            .as_deref()
+            .and_then(|contract| contract.trim().parse().ok())
+            .ok_or_else(|| format!("{} 没有合约地址，不能使用 Uniswap V2 交易对计价", token.symbol))?;
+        let pair = parse_feed(token)?;
+        let mut results = self
+            .call_feed(pair, &[token0Call {}.abi_encode(), token1Call {}.abi_encode(), getReservesCall {}.abi_encode()])
+            .await
+            .into_iter();
+        let token0 = decode_result::<token0Call>(results.next())?;
+        let token1 = decode_result::<token1Call>(results.next())?;
+        let reserves = decode_result::<getReservesCall>(results.next())?;
+
+        let (token_reserve, quote, quote_reserve) = if token0 == contract {
+            (reserves.reserve0, token1, reserves.reserve1)
+        } else if token1 == contract {
+            (reserves.reserve1, token0, reserves.reserve0)
+        } else {
+            return Err(format!("交易对 {pair:?} 不包含代币 {}", token.symbol));
+        };
+        // 不检查 blockTimestampLast：它只在交易对发生 swap/mint/burn 时更新，
+        // 不活跃但储备量仍然有效的交易对会被误判为过期
+
+        if depth >= MAX_QUOTE_DEPTH {
+            return Err(format!("{} 的报价代币层级过深", token.symbol));
+        }
+        let quote_token = ChainService::new(get_database_manager().get_pool())
+            .get_token_by_contract(&self.chain, &format!("{quote:?}"))
+            .await
+            .map_err(|e| format!("查询报价代币失败: {e}"))?
+            .ok_or_else(|| format!("报价代币 {quote:?} 未在代币列表中配置"))?;
+        let quote_price = self
+            .price_with_depth(quote_token.clone(), depth + 1)
+            .await
+            .map_err(|e| format!("报价代币 {} 价格获取失败: {e}", quote_token.symbol))?;
+
+        pair_price(
+            U256::from(token_reserve),
+            token.decimals as u8,
+            U256::from(quote_reserve),
+            quote_token.decimals as u8,
+            quote_price,
+        )
+    }
+}
+
+/// 按链上价格估值余额，rows 为每一行的 (币种key, 余额) 列表，返回估值汇总与每一行的美元价值
+///
+/// 没有任何已定价币种的行价值为 None；所有币种都未配置价格来源时不计算合计。
+pub async fn value_balances(
+    chain: &str,
+    window_id: Option<&str>,
+    rows: &[Vec<(String, String)>],
+) -> (UsdValuation, Vec<Option<String>>) {
+    let mut valuation = UsdValuation::default();
+    let mut oracle = match PriceOracle::new(chain, window_id).await {
+        Ok(oracle) => oracle,
+        Err(e) => {
+            println!("[WARN] 价格查询初始化失败: {e}");
+            return (valuation, vec![None; rows.len()]);
+        }
+    };
+
+    let mut prices: HashMap<String, Option<U256>> = HashMap::new();
+    for (coin_key, _) in rows.iter().flatten() {
+        if prices.contains_key(coin_key) {
+            continue;
+        }
+        let price = match oracle.price_of_key(coin_key).await {
+            Some(Ok(price)) => {
+                valuation.usd_prices.insert(coin_key.clone(), format_price(price));
+                Some(price)
+            }
+            Some(Err(e)) => {
+                valuation.usd_price_errors.insert(coin_key.clone(), e);
+                None
+            }
+            None => None,
+        };
+        prices.insert(coin_key.clone(), price);
+    }
+
+    let mut total: Option<U256> = None;
+    let values = rows
+        .iter()
+        .map(|row| {
+            let value = row
+                .iter()
+                .filter_map(|(coin_key, balance)| usd_value(balance, prices.get(coin_key).copied().flatten()?))
+                .reduce(|sum, value| sum.saturating_add(value))?;
+            total = Some(total.unwrap_or_default().saturating_add(value));
+            Some(format_usd(value))
+        })
+        .collect();
+    valuation.usd_total = total.map(format_usd);
+    (valuation, values)
+}
+
+// Tauri命令：读取链上所有已配置价格来源的代币价格
+#[tauri::command]
+pub async fn query_token_usd_prices(chain: String, window_id: Option<String>) -> Result<Vec<TokenUsdPrice>, String> {
+    let tokens = ChainService::new(get_database_manager().get_pool())
+        .get_priced_tokens(&chain)
+        .await
+        .map_err(|e| format!("查询代币配置失败: {e}"))?;
+    let mut oracle = PriceOracle::new(&chain, window_id.as_deref()).await?;
+
+    let mut prices = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let price = oracle.price_of(&token).await;
+        prices.push(TokenUsdPrice {
+            token_key: token.token_key,
+            symbol: token.symbol,
+            contract_address: token.contract_address,
+            price_source: token.price_source.unwrap_or_default(),
+            price_feed: token.price_feed,
+            usd_price: price.as_ref().ok().map(|price| format_price(*price)),
+            error_msg: price.err(),
+        });
+    }
+    Ok(prices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_math() {
+        // Chainlink 8位小数报价 2000.5 美元
+        let price = chainlink_price(I256::try_from(200_050_000_000i64).unwrap(), 8).unwrap();
+        assert_eq!(format_price(price), "2000.5");
+        assert!(chainlink_price(I256::ZERO, 8).is_err());
+
+        // 1000 个18位小数代币对 2,000,000 个6位小数 USDC（1美元），单价2000
+        let token_reserve = U256::from(1000u64) * pow10(18);
+        let quote_reserve = U256::from(2_000_000u64) * pow10(6);
+        let price = pair_price(token_reserve, 18, quote_reserve, 6, pow10(18)).unwrap();
+        assert_eq!(format_price(price), "2000");
+
+        assert_eq!(format_usd(usd_value("1.234567", price).unwrap()), "2469.13");
+        assert!(check_staleness(100, 3600, 4000).is_err());
+        assert!(check_staleness(100, 0, 4000).is_ok());
+    }
+}
@@ -0,0 +1,303 @@
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+use alloy_primitives::{Address, B256, U256};
+use chrono::{Duration, TimeZone, Utc};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use crate::database::{get_database_manager, chain_service::ChainService, rpc_service::RpcService};
+use crate::database::native_transfer_service::NativeTransferService;
+use crate::database::tx_replacement_service::TxReplacementService;
+use super::alloy_utils::{Rounding, parse_units};
+use super::historical::block_at_timestamp;
+use super::rpc_batch::{RpcCall, call_single, get_transaction_receipts, http_client, parse_quantity, send_batch};
+use super::token_discovery::scan_block_ranges;
+use super::transfer::RecentTransferResult;
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+/// 未指定回看时长时，检查最近24小时内的转账
+pub const DEFAULT_LOOKBACK_SECS: u64 = 24 * 3600;
+
+// 本地发送记录的保留天数，超过后在记录新转账时清理
+const RETENTION_DAYS: i64 = 30;
+
+// 检查起始时间对应的区块：(链, 起始时间) -> 区块高度。同一批次的检查共用同一起始时间，只换算一次
+static START_BLOCKS: LazyLock<Mutex<HashMap<(String, u64), u64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 本地记录的转账在链上的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SendState {
+    Confirmed,
+    Failed,
+    Pending,
+    Dropped, // nonce 已被其他交易占用
+}
+
+/// 检查的起始时间：转账开始时间与回看时长取较晚者
+pub fn window_start(start_timestamp: u64, lookback_secs: Option<u64>, now: u64) -> u64 {
+    let lookback = lookback_secs.unwrap_or(DEFAULT_LOOKBACK_SECS);
+    start_timestamp.max(now.saturating_sub(lookback))
+}
+
+/// 记录一笔已广播的平台币转账，记录失败只打印日志
+pub async fn record_native_transfer(
+    chain: &str,
+    from: Address,
+    to: Address,
+    amount: U256,
+    nonce: Option<u64>,
+    tx_hash: &str,
+) {
+    let service = NativeTransferService::new(get_database_manager().get_pool());
+    let from = format!("{from:?}");
+    let to = format!("{to:?}");
+    if let Err(e) = service.record(chain, &from, &to, &amount.to_string(), nonce, tx_hash).await {
+        println!("[WARN] 记录转账失败: {e}");
+    }
+    if let Err(e) = service.prune_before(Utc::now() - Duration::days(RETENTION_DAYS)).await {
+        println!("[WARN] 清理转账记录失败: {e}");
+    }
+}
+
+// 没有回执的交易按 nonce 判断：nonce 尚未被确认的交易仍在交易池中，否则已被其他交易替换
+fn classify(receipt_status: Option<bool>, nonce: Option<u64>, latest_nonce: u64, pending_nonce: u64) -> SendState {
+    match (receipt_status, nonce) {
+        (Some(true), _) => SendState::Confirmed,
+        (Some(false), _) => SendState::Failed,
+        (None, Some(nonce)) if nonce >= latest_nonce => SendState::Pending,
+        (None, Some(_)) => SendState::Dropped,
+        // 节点分配nonce的记录不知道具体nonce，只要钱包还有未确认交易就视为仍在等待
+        (None, None) if pending_nonce > latest_nonce => SendState::Pending,
+        (None, None) => SendState::Dropped,
+    }
+}
+
+async fn wallet_nonces(client: &Client, rpc_url: &str, max_batch_size: usize, wallet: Address) -> Result<(u64, u64), String> {
+    let address = format!("{wallet:?}");
+    let calls = [
+        RpcCall::new("eth_getTransactionCount", json!([address, "latest"])),
+        RpcCall::new("eth_getTransactionCount", json!([address, "pending"])),
+    ];
+    let mut results = send_batch(client, rpc_url, &calls, max_batch_size).await.into_iter();
+    let mut next = || -> Result<u64, String> {
+        let value = results.next().unwrap_or_else(|| Err("RPC无返回结果".to_string()))?;
+        parse_quantity(&value).ok_or_else(|| format!("nonce格式错误: {value}"))
+    };
+    let latest = next()?;
+    let pending = next()?;
+    Ok((latest, pending.max(latest)))
+}
+
+/// 平台币：对比本地发送记录与钱包当前 nonce，判断窗口内是否已向目标地址转账
+pub(crate) async fn check_native_transfers(
+    chain: &str,
+    window_id: Option<&str>,
+    wallet: Address,
+    target: Address,
+    since: u64,
+    expected_amount: Option<U256>,
+) -> Result<RecentTransferResult, String> {
+    let pool = get_database_manager().get_pool();
+    let since = Utc.timestamp_opt(since as i64, 0).single().ok_or_else(|| format!("时间戳无效: {since}"))?;
+    let records = NativeTransferService::new(pool)
+        .list_since(chain, &format!("{wallet:?}"), since)
+        .await
+        .map_err(|e| format!("查询本地转账记录失败: {e}"))?;
+
+    let target = format!("{target:?}").to_lowercase();
+    let expected_amount = expected_amount.map(|amount| amount.to_string());
+    let candidates: Vec<_> = records
+        .iter()
+        .filter(|record| record.to_address == target)
+        .filter(|record| expected_amount.as_ref().is_none_or(|amount| &record.amount_wei == amount))
+        .collect();
+    let mut result = RecentTransferResult {
+        has_recent_transfer: false,
+        transaction_count: records.len() as u32,
+        latest_transaction_hash: None,
+    };
+    if candidates.is_empty() {
+        return Ok(result);
+    }
+
+    // 加速后的替换交易与原交易是同一笔转账，一并查询回执
+    let replacement_service = TxReplacementService::new(pool);
+    let mut hashes: Vec<(usize, B256)> = Vec::new();
+    for (index, record) in candidates.iter().enumerate() {
+        let replacements = replacement_service
+            .get_replacements(chain, &record.tx_hash)
+            .await
+            .map_err(|e| format!("查询替换交易失败: {e}"))?;
+        let speed_ups = replacements.iter().filter(|r| r.kind == "speed_up").map(|r| r.replacement_hash.as_str());
+        for hash in std::iter::once(record.tx_hash.as_str()).chain(speed_ups) {
+            if let Ok(hash) = hash.parse() {
+                hashes.push((index, hash));
+            }
+        }
+    }
+    let receipt_hashes: Vec<B256> = hashes.iter().map(|(_, hash)| *hash).collect();
+    let receipts = get_transaction_receipts(chain, window_id, &receipt_hashes).await;
+
+    // 每条记录取最终状态：任一哈希成功即为成功
+    let mut statuses: HashMap<usize, (bool, B256)> = HashMap::new();
+    for ((index, hash), receipt) in hashes.into_iter().zip(receipts) {
+        if let Some(receipt) = receipt? {
+            let status = receipt.status();
+            let entry = statuses.entry(index).or_insert((status, hash));
+            if status && !entry.0 {
+                *entry = (status, hash);
+            }
+        }
+    }
+
+    let (rpc_url, max_batch_size) = RpcService::new(pool)
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+    let (latest_nonce, pending_nonce) = wallet_nonces(&http_client(window_id), &rpc_url, max_batch_size, wallet).await?;
+
+    for (index, record) in candidates.iter().enumerate() {
+        let receipt = statuses.get(&index);
+        let state = classify(
+            receipt.map(|(status, _)| *status),
+            record.nonce.map(|nonce| nonce as u64),
+            latest_nonce,
+            pending_nonce,
+        );
+        if matches!(state, SendState::Confirmed | SendState::Pending) {
+            result.has_recent_transfer = true;
+            result.latest_transaction_hash = Some(match receipt {
+                Some((_, hash)) => format!("{hash:?}"),
+                None => record.tx_hash.clone(),
+            });
+        }
+    }
+    Ok(result)
+}
+
+// 从 Transfer 日志中取出 (交易哈希, 转账数量)
+fn parse_transfer_log(log: &Value) -> Option<(String, U256)> {
+    if log.get("topics")?.as_array()?.len() != 3 {
+        return None;
+    }
+    let data = alloy_primitives::hex::decode(log.get("data")?.as_str()?).ok()?;
+    let value = U256::try_from_be_slice(data.get(..32)?)?;
+    Some((log.get("transactionHash")?.as_str()?.to_string(), value))
+}
+
+/// 把检查起始时间换算为区块高度，同一链上相同起始时间的结果会被复用
+pub(crate) async fn start_block(chain: &str, window_id: Option<&str>, since: u64) -> Result<u64, String> {
+    let key = (chain.to_string(), since);
+    if let Some(block) = START_BLOCKS.lock().unwrap().get(&key).copied() {
+        return Ok(block);
+    }
+    let (rpc_url, _) = RpcService::new(get_database_manager().get_pool())
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+    let block = block_at_timestamp(&http_client(window_id), &rpc_url, since).await?;
+    // 起始时间晚于最新区块时结果是当时的最新区块，之后还会有同一时间的区块出块，不缓存
+    if (since as i64) < Utc::now().timestamp() - 60 {
+        START_BLOCKS.lock().unwrap().insert(key, block);
+    }
+    Ok(block)
+}
+
+/// 代币：按 from/to 过滤合约的 Transfer 日志，判断窗口内（from_block 至最新区块）是否已向目标地址转账
+pub(crate) async fn check_token_transfers(
+    chain: &str,
+    window_id: Option<&str>,
+    wallet: Address,
+    target: Address,
+    contract: Address,
+    from_block: u64,
+    amount: Option<&str>,
+) -> Result<RecentTransferResult, String> {
+    let pool = get_database_manager().get_pool();
+    let (rpc_url, _) = RpcService::new(pool)
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+    let client = http_client(window_id);
+
+    // 代币精度取自代币配置，未配置的代币不校验金额
+    let decimals = ChainService::new(pool)
+        .get_token_by_contract(chain, &format!("{contract:?}"))
+        .await
+        .ok()
+        .flatten()
+        .map(|token| token.decimals as u8);
+    let expected_amount = match (amount.filter(|amount| !amount.trim().is_empty()), decimals) {
+        (Some(amount), Some(decimals)) => parse_units(amount.trim(), decimals, Rounding::Down).ok(),
+        _ => None,
+    };
+
+    let latest_block = call_single(&client, &rpc_url, "eth_blockNumber", json!([]))
+        .await
+        .and_then(|value| parse_quantity(&value).ok_or_else(|| format!("区块号格式错误: {value}")))?;
+
+    let topics = json!([
+        format!("{:?}", Transfer::SIGNATURE_HASH),
+        format!("{:?}", wallet.into_word()),
+        format!("{:?}", target.into_word()),
+    ]);
+    let contract = format!("{contract:?}");
+    let transfers = scan_block_ranges(from_block, latest_block, |start, end| {
+        let filter = json!([{
+            "address": contract,
+            "fromBlock": format!("0x{start:x}"),
+            "toBlock": format!("0x{end:x}"),
+            "topics": topics,
+        }]);
+        let (client, rpc_url) = (&client, &rpc_url);
+        async move {
+            let logs = call_single(client, rpc_url, "eth_getLogs", filter).await?;
+            let logs = logs.as_array().ok_or_else(|| "eth_getLogs 返回格式错误".to_string())?;
+            Ok(logs.iter().filter_map(parse_transfer_log).collect::<Vec<_>>())
+        }
+    }).await?;
+
+    let latest_transaction_hash = transfers
+        .iter()
+        .filter(|(_, value)| expected_amount.is_none_or(|amount| *value == amount))
+        .map(|(hash, _)| hash.clone())
+        .next_back();
+    Ok(RecentTransferResult {
+        has_recent_transfer: latest_transaction_hash.is_some(),
+        transaction_count: transfers.len() as u32,
+        latest_transaction_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_window() {
+        assert_eq!(classify(Some(true), Some(3), 10, 10), SendState::Confirmed);
+        assert_eq!(classify(Some(false), None, 10, 10), SendState::Failed);
+        assert_eq!(classify(None, Some(10), 10, 11), SendState::Pending);
+        assert_eq!(classify(None, Some(9), 10, 11), SendState::Dropped);
+        assert_eq!(classify(None, None, 10, 11), SendState::Pending);
+        assert_eq!(classify(None, None, 10, 10), SendState::Dropped);
+
+        assert_eq!(window_start(1_000, Some(600), 10_000), 9_400);
+        assert_eq!(window_start(9_500, Some(600), 10_000), 9_500);
+        assert_eq!(window_start(0, None, 100_000), 100_000 - DEFAULT_LOOKBACK_SECS);
+    }
+
+    #[test]
+    fn test_parse_transfer_log() {
+        let log = json!({
+            "topics": [format!("{:?}", Transfer::SIGNATURE_HASH), "0x01", "0x02"],
+            "data": format!("0x{:064x}", 1_500_000u64),
+            "transactionHash": "0xabc",
+        });
+        assert_eq!(parse_transfer_log(&log), Some(("0xabc".to_string(), U256::from(1_500_000u64))));
+    }
+}
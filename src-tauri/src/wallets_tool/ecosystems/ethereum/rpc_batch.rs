@@ -0,0 +1,295 @@
+use alloy_primitives::B256;
+use alloy_rpc_types_eth::TransactionReceipt;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{LazyLock, Mutex};
+use tokio::time::{sleep, Duration};
+use crate::database::{get_database_manager, rpc_service::RpcService};
+use super::proxy_manager::PROXY_MANAGER;
+
+// 批量请求的超时时间（响应体比单个请求大，比单个请求的10秒宽松）
+const BATCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+// 429速率限制与5xx服务端错误的最大重试次数
+const MAX_RETRIES: u32 = 3;
+
+// 未配置代理时使用的默认客户端
+static DEFAULT_CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+});
+
+// 节点拒绝批量请求后学习到的上限：RPC地址 -> 最大调用数
+static LEARNED_BATCH_SIZES: LazyLock<Mutex<HashMap<String, usize>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 批量请求中的单个调用
+#[derive(Debug, Clone)]
+pub struct RpcCall {
+    pub method: &'static str,
+    pub params: Value,
+}
+
+impl RpcCall {
+    pub fn new(method: &'static str, params: Value) -> Self {
+        Self { method, params }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: usize,
+    method: &'a str,
+    params: &'a Value,
+}
+
+type BatchFuture<'a> = Pin<Box<dyn Future<Output = Vec<Result<Value, String>>> + Send + 'a>>;
+
+// 请求失败的原因：节点明确表示批量请求过大时拆分重试，网络等其他错误直接失败
+enum BatchError {
+    Rejected(String),
+    Failed(String),
+}
+
+/// 按窗口的代理配置选择HTTP客户端，没有可用代理时直连
+pub fn http_client(window_id: Option<&str>) -> Client {
+    let proxy_client = match window_id {
+        Some(wid) => PROXY_MANAGER.get_random_proxy_client_for_window(wid),
+        None => PROXY_MANAGER.get_random_proxy_client(),
+    };
+    proxy_client.unwrap_or_else(|| DEFAULT_CLIENT.clone())
+}
+
+/// 发送单个JSON-RPC调用（复用批量请求的错误处理与429重试）
+pub async fn call_single(client: &Client, rpc_url: &str, method: &'static str, params: Value) -> Result<Value, String> {
+    send_batch(client, rpc_url, &[RpcCall::new(method, params)], 1)
+        .await
+        .pop()
+        .unwrap_or_else(|| Err("RPC无返回结果".to_string()))
+}
+
+/// 解析JSON-RPC返回的十六进制数量（如区块号、nonce）
+pub fn parse_quantity(value: &Value) -> Option<u64> {
+    u64::from_str_radix(value.as_str()?.trim_start_matches("0x"), 16).ok()
+}
+
+/// 批量查询交易回执，结果与 hashes 一一对应（未上链为 None）
+pub async fn get_transaction_receipts(
+    chain: &str,
+    window_id: Option<&str>,
+    hashes: &[B256],
+) -> Vec<Result<Option<TransactionReceipt>, String>> {
+    if hashes.is_empty() {
+        return Vec::new();
+    }
+    let rpc = RpcService::new(get_database_manager().get_pool())
+        .get_random_rpc_with_batch_size(chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"));
+    let (rpc_url, max_batch_size) = match rpc {
+        Ok(rpc) => rpc,
+        Err(e) => return vec![Err(e); hashes.len()],
+    };
+
+    let calls: Vec<RpcCall> = hashes
+        .iter()
+        .map(|hash| RpcCall::new("eth_getTransactionReceipt", json!([format!("{hash:?}")])))
+        .collect();
+    send_batch(&http_client(window_id), &rpc_url, &calls, max_batch_size)
+        .await
+        .into_iter()
+        .map(|result| {
+            result.and_then(|value| serde_json::from_value(value).map_err(|e| format!("交易回执解析失败: {e}")))
+        })
+        .collect()
+}
+
+// 配置上限与学习到的上限取较小值
+fn effective_batch_size(rpc_url: &str, max_batch_size: usize) -> usize {
+    let learned = LEARNED_BATCH_SIZES.lock().unwrap().get(rpc_url).copied();
+    learned.map_or(max_batch_size, |size| size.min(max_batch_size)).max(1)
+}
+
+fn remember_batch_size(rpc_url: &str, size: usize) {
+    let mut learned = LEARNED_BATCH_SIZES.lock().unwrap();
+    let entry = learned.entry(rpc_url.to_string()).or_insert(size);
+    *entry = (*entry).min(size).max(1);
+}
+
+/// 以JSON-RPC批量请求发送一组调用，返回结果与 calls 一一对应
+///
+/// 按节点上限分批，响应按id匹配（节点可能乱序返回）。节点返回413或明确表示批量过大时
+/// 对半拆分重试并记住更小的上限，拆到单个调用时退回普通请求。
+pub async fn send_batch(
+    client: &Client,
+    rpc_url: &str,
+    calls: &[RpcCall],
+    max_batch_size: usize,
+) -> Vec<Result<Value, String>> {
+    let mut results = Vec::with_capacity(calls.len());
+    let mut offset = 0;
+    while offset < calls.len() {
+        // 每批开始前重新读取上限，前一批拆分时学到的上限立即生效
+        let size = effective_batch_size(rpc_url, max_batch_size);
+        let end = (offset + size).min(calls.len());
+        results.extend(send_chunk(client, rpc_url, &calls[offset..end]).await);
+        offset = end;
+    }
+    results
+}
+
+fn send_chunk<'a>(
+    client: &'a Client,
+    rpc_url: &'a str,
+    calls: &'a [RpcCall],
+) -> BatchFuture<'a> {
+    Box::pin(async move {
+        if calls.len() == 1 {
+            return vec![send_single(client, rpc_url, &calls[0]).await];
+        }
+
+        let body: Vec<JsonRpcRequest> = calls
+            .iter()
+            .enumerate()
+            .map(|(id, call)| JsonRpcRequest { jsonrpc: "2.0", id, method: call.method, params: &call.params })
+            .collect();
+
+        let rejected = match post_json(client, rpc_url, &body).await {
+            Ok(Value::Array(responses)) => return match_responses(calls.len(), responses),
+            // 不支持批量的节点通常返回单个错误对象
+            Ok(other) => match other.get("error") {
+                Some(error) if is_batch_size_error(error) => format!("节点返回错误: {error}"),
+                Some(error) => return vec![Err(format!("节点返回错误: {error}")); calls.len()],
+                None => return vec![Err("批量响应不是数组".to_string()); calls.len()],
+            },
+            Err(BatchError::Rejected(e)) => e,
+            Err(BatchError::Failed(e)) => return vec![Err(e); calls.len()],
+        };
+
+        let half = calls.len().div_ceil(2);
+        println!("[WARN] 节点拒绝批量请求（{} 个调用），拆分为每批 {half} 个后重试: {rejected}, RPC: {rpc_url}", calls.len());
+        remember_batch_size(rpc_url, half);
+        let mut results = send_chunk(client, rpc_url, &calls[..half]).await;
+        results.extend(send_chunk(client, rpc_url, &calls[half..]).await);
+        results
+    })
+}
+
+async fn send_single(client: &Client, rpc_url: &str, call: &RpcCall) -> Result<Value, String> {
+    let body = JsonRpcRequest { jsonrpc: "2.0", id: 0, method: call.method, params: &call.params };
+    match post_json(client, rpc_url, &body).await {
+        Ok(response) => parse_response(&response),
+        Err(BatchError::Rejected(e) | BatchError::Failed(e)) => Err(e),
+    }
+}
+
+// 节点返回的错误是否表示批量请求过大或不支持批量请求
+fn is_batch_size_error(error: &Value) -> bool {
+    let message = error.get("message").and_then(Value::as_str).unwrap_or_default().to_lowercase();
+    message.contains("batch")
+        && ["too large", "too many", "limit", "exceed", "more than", "size", "not supported", "unsupported"]
+            .iter()
+            .any(|keyword| message.contains(keyword))
+}
+
+// 发送请求体，429与5xx时指数退避重试；413表示请求体过大，交由调用方拆分
+async fn post_json<T: Serialize + ?Sized>(client: &Client, rpc_url: &str, body: &T) -> Result<Value, BatchError> {
+    let mut retry_count = 0;
+    loop {
+        let response = tokio::time::timeout(BATCH_TIMEOUT, client.post(rpc_url).json(body).send())
+            .await
+            .map_err(|_| BatchError::Failed(format!("RPC请求超时，RPC地址: {rpc_url}")))?
+            .map_err(|e| BatchError::Failed(format!("RPC请求失败: {e}")))?;
+
+        let status = response.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            retry_count += 1;
+            if retry_count > MAX_RETRIES {
+                return Err(BatchError::Failed(format!("RPC请求失败（HTTP状态码 {status}），已达到最大重试次数，RPC地址: {rpc_url}")));
+            }
+            let wait_time = Duration::from_secs(2_u64.pow(retry_count));
+            println!("[WARN] RPC返回HTTP状态码 {status}，等待 {wait_time:?} 后重试（第 {retry_count} 次重试），RPC: {rpc_url}");
+            sleep(wait_time).await;
+            continue;
+        }
+        if status.as_u16() == 413 {
+            return Err(BatchError::Rejected(format!("HTTP状态码 {status}")));
+        }
+        if !status.is_success() {
+            return Err(BatchError::Failed(format!("RPC请求失败（HTTP状态码 {status}），RPC地址: {rpc_url}")));
+        }
+
+        return tokio::time::timeout(BATCH_TIMEOUT, response.json::<Value>())
+            .await
+            .map_err(|_| BatchError::Failed(format!("RPC响应解析超时，RPC地址: {rpc_url}")))?
+            .map_err(|e| BatchError::Failed(format!("响应解析失败: {e}")));
+    }
+}
+
+// 单个响应对象：error 优先，result 为 null 时原样返回（如尚未上链的交易回执）
+fn parse_response(response: &Value) -> Result<Value, String> {
+    if let Some(error) = response.get("error") {
+        let code = error.get("code").and_then(Value::as_i64).unwrap_or_default();
+        let message = error.get("message").and_then(Value::as_str).unwrap_or_default();
+        return Err(format!("RPC Error: {code} - {message}"));
+    }
+    response.get("result").cloned().ok_or_else(|| "No result in RPC response".to_string())
+}
+
+// 按id把批量响应还原为请求顺序
+fn match_responses(count: usize, responses: Vec<Value>) -> Vec<Result<Value, String>> {
+    let mut by_id: HashMap<u64, Value> = responses
+        .into_iter()
+        .filter_map(|response| Some((response.get("id")?.as_u64()?, response)))
+        .collect();
+    (0..count)
+        .map(|id| match by_id.remove(&(id as u64)) {
+            Some(response) => parse_response(&response),
+            None => Err("批量响应中缺少该请求的结果".to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_responses_by_id() {
+        let responses = vec![
+            json!({"jsonrpc": "2.0", "id": 2, "result": null}),
+            json!({"jsonrpc": "2.0", "id": 0, "result": "0x1"}),
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "execution reverted"}}),
+        ];
+        let results = match_responses(4, responses);
+        assert_eq!(results[0], Ok(json!("0x1")));
+        assert_eq!(results[1], Err("RPC Error: -32000 - execution reverted".to_string()));
+        assert_eq!(results[2], Ok(Value::Null));
+        assert!(results[3].is_err());
+    }
+
+    #[test]
+    fn test_is_batch_size_error() {
+        assert!(is_batch_size_error(&json!({"code": -32600, "message": "Batch size is too large"})));
+        assert!(is_batch_size_error(&json!({"code": -32005, "message": "batch limit exceeded"})));
+        assert!(!is_batch_size_error(&json!({"code": -32005, "message": "daily request count exceeded, request rate limited"})));
+        assert!(!is_batch_size_error(&json!({"code": -32601, "message": "method not found"})));
+    }
+
+    #[test]
+    fn test_learned_batch_size() {
+        let url = "https://batch-limit.test";
+        assert_eq!(effective_batch_size(url, 20), 20);
+        remember_batch_size(url, 10);
+        remember_batch_size(url, 15);
+        assert_eq!(effective_batch_size(url, 20), 10);
+        assert_eq!(effective_batch_size(url, 5), 5);
+    }
+}
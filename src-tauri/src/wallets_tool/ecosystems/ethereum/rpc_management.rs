@@ -15,6 +15,7 @@ pub struct RpcProviderInfo {
     pub last_success_at: Option<String>,
     pub failure_count: i32,
     pub avg_response_time_ms: Option<i32>,
+    pub max_batch_size: i32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,6 +23,8 @@ pub struct UpdateRpcProviderRequest {
     pub rpc_url: String,
     pub is_active: bool,
     pub priority: i32,
+    #[serde(default)]
+    pub max_batch_size: Option<i32>, // 不传则保持原值
 }
 
 #[derive(Debug, Serialize)]
@@ -48,6 +51,7 @@ pub async fn get_rpc_providers(
         last_success_at: p.last_success_at.map(|dt| dt.to_rfc3339()),
         failure_count: p.failure_count,
         avg_response_time_ms: p.avg_response_time_ms,
+        max_batch_size: p.max_batch_size,
     }).collect();
     
     Ok(provider_infos)
@@ -79,6 +83,7 @@ pub async fn add_rpc_provider(
         last_success_at: provider.last_success_at.map(|dt| dt.to_rfc3339()),
         failure_count: provider.failure_count,
         avg_response_time_ms: provider.avg_response_time_ms,
+        max_batch_size: provider.max_batch_size,
     })
 }
 
@@ -89,7 +94,7 @@ pub async fn update_rpc_provider(
     request: UpdateRpcProviderRequest,
     chain_service: State<'_, ChainService<'_>>,
 ) -> Result<RpcProviderInfo, String> {
-    let provider = chain_service.update_rpc_provider(id, &request.rpc_url, request.is_active, request.priority).await
+    let provider = chain_service.update_rpc_provider(id, &request.rpc_url, request.is_active, request.priority, request.max_batch_size).await
         .map_err(|e| format!("更新 RPC 提供商失败: {e}"))?;
     
     Ok(RpcProviderInfo {
@@ -101,6 +106,7 @@ pub async fn update_rpc_provider(
         last_success_at: provider.last_success_at.map(|dt| dt.to_rfc3339()),
         failure_count: provider.failure_count,
         avg_response_time_ms: provider.avg_response_time_ms,
+        max_batch_size: provider.max_batch_size,
     })
 }
 
@@ -11,7 +11,19 @@ use tokio::time::{sleep, Duration};
 use tauri::Emitter;
 use rand;
 use alloy::signers::local::PrivateKeySigner;
+use alloy_primitives::{Address, U256};
 use crate::database::{get_database_manager, rpc_service::RpcService, chain_service::ChainService};
+use super::alloy_utils::format_units;
+use super::multicall::{
+    MULTICALL3_BATCH_SIZE, balance_of_calldata, decode_aggregate3, decode_uint, encode_aggregate3, erc20_balance_call,
+    native_balance_call,
+};
+use super::rpc_batch::{RpcCall, http_client, parse_quantity, send_batch};
+use super::historical::{HistoricalBlock, HistoricalTarget, resolve_historical_block};
+use super::balance_snapshot::{BASE_COIN_KEY, save_balance_snapshot};
+use super::pricing::{UsdValuation, value_balances};
+use super::ens::lookup_names;
+use crate::wallets_tool::address_book::resolve_group_addresses;
 
 // 基于窗口ID的停止标志映射
 static STOP_FLAGS: LazyLock<Mutex<HashMap<String, AtomicBool>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
@@ -41,8 +53,106 @@ fn reset_stop_flag(window_id: &str) {
     set_stop_flag(window_id, false);
 }
 
+// 已检查过的Multicall3部署情况：(链, 合约地址) -> 是否已部署
+static MULTICALL3_DEPLOYED: LazyLock<Mutex<HashMap<(String, Address), bool>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 批量查询方式
+#[derive(Debug, Clone, Copy)]
+enum BatchMode {
+    Multicall3(Address),
+    RpcBatch(usize), // 节点单次批量请求的最大调用数
+}
+
+// 余额查询目标
+#[derive(Debug, Clone, Copy)]
+enum BalanceTarget {
+    Native,
+    Erc20(Address),
+}
+
+// 解析十六进制数值结果；"0x"（如非合约地址的 eth_call）返回 None
+fn parse_hex_quantity(value: &serde_json::Value) -> Option<U256> {
+    let hex = value.as_str()?.trim_start_matches("0x");
+    if hex.is_empty() {
+        return None;
+    }
+    U256::from_str_radix(hex, 16).ok()
+}
+
+// 多币种查询中平台币列的key
+const BASE_COLUMN_KEY: &str = "base";
+
+// 根据各列的查询结果设置项目状态；地址无效或已停止的项目保持原状态
+fn finish_multi_token_item(item: &mut QueryItem, columns: &[TokenColumn], last_error: Option<&str>) {
+    if item.exec_status == "3" {
+        return;
+    }
+    if item.address.trim().parse::<Address>().is_err() {
+        item.exec_status = "3".to_string();
+        item.error_msg = Some(format!("地址格式错误: {}", item.address));
+        return;
+    }
+
+    item.plat_balance = item.token_balances.get(BASE_COLUMN_KEY).cloned();
+    let missing: Vec<&str> = columns
+        .iter()
+        .filter(|column| !item.token_balances.contains_key(&column.key))
+        .map(|column| column.symbol.as_str())
+        .collect();
+    if missing.is_empty() {
+        item.exec_status = "2".to_string();
+        item.error_msg = None;
+    } else {
+        item.exec_status = "3".to_string();
+        item.retry_flag = true;
+        item.error_msg = Some(match last_error {
+            Some(e) => format!("{} 查询失败: {e}", missing.join(", ")),
+            None => format!("{} 查询失败", missing.join(", ")),
+        });
+    }
+}
+
+// 按6位小数显示余额（截断，不经过浮点数）
+fn format_balance(value: U256, decimals: u8) -> String {
+    let formatted = format_units(value, decimals);
+    let (int_part, frac_part) = formatted.split_once('.').unwrap_or((&formatted, ""));
+    let frac: String = frac_part.chars().chain(std::iter::repeat('0')).take(6).collect();
+    format!("{int_part}.{frac}")
+}
+
+// 解析查询项的地址：有私钥时优先从私钥生成地址
+fn prepare_item(item: &mut QueryItem) -> Result<(), String> {
+    if let Some(private_key_str) = &item.private_key {
+        if !private_key_str.trim().is_empty() {
+            // 处理私钥格式，兼容带0x和不带0x的格式
+            let private_key = if private_key_str.starts_with("0x") || private_key_str.starts_with("0X") {
+                private_key_str[2..].to_string()
+            } else {
+                private_key_str.clone()
+            };
+
+            // 从私钥生成地址
+            match private_key.parse::<PrivateKeySigner>() {
+                Ok(signer) => {
+                    item.address = format!("{:?}", signer.address());
+                    println!("[INFO] 从私钥生成地址: {}", item.address);
+                }
+                Err(_) => return Err("私钥格式错误，无法生成地址".to_string()),
+            }
+        }
+    }
+
+    // 检查地址是否为空
+    if item.address.trim().is_empty() {
+        println!("[ERROR] 地址为空，无法查询余额 - key: {}", item.key);
+        return Err("地址为空，无法查询余额".to_string());
+    }
+    Ok(())
+}
+
 // 查询项目结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct QueryItem {
     pub key: String,
     pub address: String,
@@ -53,6 +163,18 @@ pub struct QueryItem {
     pub retry_flag: bool,
     pub exec_status: String, // "0"=未执行, "1"=执行中, "2"=成功, "3"=失败
     pub error_msg: Option<String>,
+    // 多币种查询的余额：结果列key -> 余额
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub token_balances: HashMap<String, String>,
+    // 按链上价格计算的余额美元价值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<String>,
+    // 地址反向解析的ENS主名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ens_name: Option<String>,
+    // 余额的最小单位整数与精度：币种key（平台币为 "base"，代币为小写合约地址）-> (余额, 精度)，用于保存快照
+    #[serde(skip)]
+    pub raw_balances: HashMap<String, (U256, u8)>,
 }
 
 // 币种配置
@@ -75,6 +197,15 @@ pub struct QueryParams {
     pub window_id: Option<String>,
     #[serde(default)]
     pub query_id: Option<String>,
+    // 历史余额查询的区块，不传则查询最新区块
+    #[serde(default)]
+    pub block: Option<HistoricalBlock>,
+    // 地址簿分组名，后端展开为查询地址并追加在 items 之后
+    #[serde(default)]
+    pub address_group: Option<String>,
+    // 是否反向解析地址的ENS名称
+    #[serde(default)]
+    pub resolve_names: bool,
 }
 
 // 查询结果
@@ -83,6 +214,53 @@ pub struct QueryResult {
     pub success: bool,
     pub items: Vec<QueryItem>,
     pub error_msg: Option<String>,
+    // 本次查询保存的余额快照
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<i64>,
+    #[serde(flatten)]
+    pub usd: UsdValuation,
+}
+
+// 多币种查询参数：一次查询平台币和多个代币
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiTokenQueryParams {
+    pub chain: String,
+    pub coins: Vec<CoinConfig>,
+    pub items: Vec<QueryItem>,
+    pub thread_count: usize,
+    #[serde(default)]
+    pub window_id: Option<String>,
+    #[serde(default)]
+    pub query_id: Option<String>,
+    #[serde(default)]
+    pub block: Option<HistoricalBlock>,
+    #[serde(default)]
+    pub address_group: Option<String>,
+    #[serde(default)]
+    pub resolve_names: bool,
+}
+
+// 多币种查询的结果列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenColumn {
+    pub key: String, // 平台币为 "base"，代币为小写合约地址
+    pub symbol: String,
+    pub contract_address: Option<String>,
+    pub decimals: u8,
+}
+
+// 多币种查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiTokenQueryResult {
+    pub success: bool,
+    pub columns: Vec<TokenColumn>,
+    pub items: Vec<QueryItem>,
+    pub error_msg: Option<String>,
+    // 本次查询保存的余额快照，与 columns 一一对应
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snapshot_ids: Vec<i64>,
+    #[serde(flatten)]
+    pub usd: UsdValuation,
 }
 
 // RPC 请求结构
@@ -119,8 +297,11 @@ pub struct ProviderConfig {
 }
 
 // 余额查询器
+#[derive(Clone)]
 pub struct SimpleBalanceQueryService {
     client: Client,
+    // 历史余额查询的目标区块，None 表示查询最新区块
+    historical: Option<HistoricalTarget>,
 }
 
 impl SimpleBalanceQueryService {
@@ -131,17 +312,59 @@ impl SimpleBalanceQueryService {
             .timeout(Duration::from_secs(30))
             .build()
             .unwrap_or_else(|_| Client::new());
-        Self { client }
+        Self { client, historical: None }
+    }
+
+    // 查询指定区块的余额：解析目标区块，之后的请求只发往归档节点
+    pub async fn at_block(chain: &str, block: Option<HistoricalBlock>, window_id: Option<&str>) -> Result<Self, String> {
+        let mut service = Self::new();
+        if let Some(block) = block {
+            service.historical = Some(resolve_historical_block(chain, block, window_id).await?);
+        }
+        Ok(service)
+    }
+
+    // 余额请求使用的区块参数
+    fn block_tag(&self) -> String {
+        self.historical.as_ref().map_or_else(|| "latest".to_string(), HistoricalTarget::block_tag)
+    }
+
+    // 历史查询的区块高度，查询最新区块时为 None
+    fn block_number(&self) -> Option<u64> {
+        self.historical.as_ref().map(|historical| historical.block_number)
     }
 
-    // 从数据库获取RPC URL
+    // 从数据库获取RPC URL（历史查询时从归档节点中选择）
     async fn get_rpc_url(&self, chain: &str) -> Result<String> {
+        if let Some(historical) = &self.historical {
+            return Ok(historical.random_rpc().0);
+        }
         let db_manager = get_database_manager();
         let rpc_service = RpcService::new(db_manager.get_pool());
         
         rpc_service.get_random_rpc_url(chain).await
     }
 
+    // 获取RPC URL及其批量请求上限（历史查询时从归档节点中选择）
+    async fn get_rpc_with_batch_size(&self, chain: &str) -> Result<(String, usize)> {
+        if let Some(historical) = &self.historical {
+            return Ok(historical.random_rpc());
+        }
+        RpcService::new(get_database_manager().get_pool())
+            .get_random_rpc_with_batch_size(chain)
+            .await
+    }
+
+    // 查询地址在查询区块的nonce用于保存快照，获取RPC失败时nonce保持为空
+    async fn attach_nonces(&self, chain: &str, window_id: Option<&str>, items: &mut [QueryItem]) {
+        match self.get_rpc_with_batch_size(chain).await {
+            Ok((rpc_url, max_batch_size)) => {
+                fetch_nonces(&http_client(window_id), &rpc_url, max_batch_size, &self.block_tag(), items).await
+            }
+            Err(e) => println!("[WARN] 查询nonce失败: {e}"),
+        }
+    }
+
     // 发送 JSON-RPC 请求（带超时、代理支持和429重试）
     async fn send_rpc_request(&self, rpc_url: &str, method: &str, params: serde_json::Value, window_id: Option<&str>) -> Result<serde_json::Value> {
         use crate::wallets_tool::ecosystems::ethereum::proxy_manager::PROXY_MANAGER;
@@ -260,82 +483,78 @@ impl SimpleBalanceQueryService {
         let balance_result = self.send_rpc_request(
             &rpc_url,
             "eth_getBalance",
-            serde_json::json!([item.address, "latest"]),
+            serde_json::json!([item.address, self.block_tag()]),
             window_id
         ).await?;
 
-        if let Some(balance_hex) = balance_result.as_str() {
-            let hex_without_prefix = &balance_hex[2..];
-            match u128::from_str_radix(hex_without_prefix, 16) {
-                Ok(balance_wei) => {
-                    let balance_eth = balance_wei as f64 / 1e18;
-                    item.plat_balance = Some(format!("{balance_eth:.6}"));
-                }
-                Err(e) => {
-                    return Err(anyhow!("余额数值转换失败: {e} (原始值: {balance_hex})"));
-                }
-            }
+        if balance_result.is_string() {
+            let balance = parse_hex_quantity(&balance_result)
+                .ok_or_else(|| anyhow!("余额数值转换失败 (原始值: {balance_result})"))?;
+            item.plat_balance = Some(format_balance(balance, 18));
+            item.raw_balances.insert(BASE_COIN_KEY.to_string(), (balance, 18));
         }
 
         Ok(())
     }
 
-    // 查询代币余额
-    async fn query_token_balance(&self, item: &mut QueryItem, chain: &str, contract_address: &str, window_id: Option<&str>) -> Result<()> {
-        let rpc_url = self.get_rpc_url(chain).await?;
-        
-        println!("[DEBUG] 开始查询代币余额 - 链: {}, 地址: {}, 合约: {}", chain, item.address, contract_address);
-        
-        // 首先尝试从数据库获取代币的 decimals 配置
-        let decimals = {
-            let pool = get_database_manager().get_pool();
-            let chain_service = ChainService::new(pool);
-            match chain_service.get_token_decimals_by_contract(chain, contract_address).await {
-                Ok(Some(db_decimals)) => {
-                    println!("[DEBUG] 从数据库获取到代币decimals - 链: {chain}, 合约: {contract_address}, decimals: {db_decimals}");
-                    db_decimals as u8
-                }
-                Ok(None) | Err(_) => {
-                    println!("[DEBUG] 数据库中未找到代币配置，回退到合约查询 - 链: {chain}, 合约: {contract_address}");
-                    // 回退到从合约查询 decimals
-                    let decimals_method = "313ce567"; // decimals() 函数的方法ID
-                    let decimals_data = format!("0x{decimals_method}");
-                    
-                    match self.send_rpc_request(
-                        &rpc_url,
-                        "eth_call",
-                        serde_json::json!([{
-                            "to": contract_address,
-                            "data": decimals_data
-                        }, "latest"]),
-                        window_id
-                    ).await {
-                        Ok(decimals_result) => {
-                            if let Some(decimals_hex) = decimals_result.as_str() {
-                                let hex_without_prefix = &decimals_hex[2..];
-                                match u8::from_str_radix(hex_without_prefix, 16) {
-                                    Ok(d) => {
-                                        println!("[DEBUG] 从合约查询到代币decimals - 链: {chain}, 合约: {contract_address}, decimals: {d}");
-                                        d
-                                    }
-                                    Err(e) => {
-                                        println!("[WARNING] decimals查询失败，使用默认值18 - 链: {chain}, 合约: {contract_address}, 错误: {e}");
-                                        18 // 默认使用18位小数
-                                    }
+    // 获取代币精度：优先使用数据库配置，否则查询合约，均失败时默认18
+    async fn get_token_decimals(&self, rpc_url: &str, chain: &str, contract_address: &str, window_id: Option<&str>) -> u8 {
+        let pool = get_database_manager().get_pool();
+        let chain_service = ChainService::new(pool);
+        match chain_service.get_token_decimals_by_contract(chain, contract_address).await {
+            Ok(Some(db_decimals)) => {
+                println!("[DEBUG] 从数据库获取到代币decimals - 链: {chain}, 合约: {contract_address}, decimals: {db_decimals}");
+                db_decimals as u8
+            }
+            Ok(None) | Err(_) => {
+                println!("[DEBUG] 数据库中未找到代币配置，回退到合约查询 - 链: {chain}, 合约: {contract_address}");
+                // 回退到从合约查询 decimals
+                let decimals_method = "313ce567"; // decimals() 函数的方法ID
+                let decimals_data = format!("0x{decimals_method}");
+                
+                match self.send_rpc_request(
+                    rpc_url,
+                    "eth_call",
+                    serde_json::json!([{
+                        "to": contract_address,
+                        "data": decimals_data
+                    }, "latest"]),
+                    window_id
+                ).await {
+                    Ok(decimals_result) => {
+                        if let Some(decimals_hex) = decimals_result.as_str() {
+                            let hex_without_prefix = &decimals_hex[2..];
+                            match u8::from_str_radix(hex_without_prefix, 16) {
+                                Ok(d) => {
+                                    println!("[DEBUG] 从合约查询到代币decimals - 链: {chain}, 合约: {contract_address}, decimals: {d}");
+                                    d
+                                }
+                                Err(e) => {
+                                    println!("[WARNING] decimals查询失败，使用默认值18 - 链: {chain}, 合约: {contract_address}, 错误: {e}");
+                                    18 // 默认使用18位小数
                                 }
-                            } else {
-                                println!("[WARNING] decimals查询返回空值，使用默认值18 - 链: {chain}, 合约: {contract_address}");
-                                18 // 默认使用18位小数
                             }
-                        }
-                        Err(e) => {
-                            println!("[WARNING] decimals查询失败，使用默认值18 - 链: {chain}, 合约: {contract_address}, 错误: {e}");
+                        } else {
+                            println!("[WARNING] decimals查询返回空值，使用默认值18 - 链: {chain}, 合约: {contract_address}");
                             18 // 默认使用18位小数
                         }
                     }
+                    Err(e) => {
+                        println!("[WARNING] decimals查询失败，使用默认值18 - 链: {chain}, 合约: {contract_address}, 错误: {e}");
+                        18 // 默认使用18位小数
+                    }
                 }
             }
-        };
+        }
+    }
+
+    // 查询代币余额
+    async fn query_token_balance(&self, item: &mut QueryItem, chain: &str, contract_address: &str, window_id: Option<&str>) -> Result<()> {
+        let rpc_url = self.get_rpc_url(chain).await?;
+        
+        println!("[DEBUG] 开始查询代币余额 - 链: {}, 地址: {}, 合约: {}", chain, item.address, contract_address);
+        
+        let decimals = self.get_token_decimals(&rpc_url, chain, contract_address, window_id).await;
         
         // ERC20 balanceOf 函数的方法ID
         let balance_of_method = "70a08231";
@@ -350,42 +569,27 @@ impl SimpleBalanceQueryService {
             serde_json::json!([{
                 "to": contract_address,
                 "data": data
-            }, "latest"]),
+            }, self.block_tag()]),
             window_id
         ).await?;
 
         if let Some(balance_hex) = balance_result.as_str() {
             
-            // 检查十六进制字符串长度
-            let hex_without_prefix = &balance_hex[2..];
-            
             println!("[DEBUG] 原始余额查询结果 - 链: {}, 地址: {}, 合约: {}, 十六进制: {}", 
                     chain, item.address, contract_address, balance_hex);
             
-            // 将十六进制转换为十进制，使用u128避免溢出
-            match u128::from_str_radix(hex_without_prefix, 16) {
-                Ok(balance_wei) => {
-                    println!("[DEBUG] 原始余额转换 - 链: {}, 地址: {}, 合约: {}, 原始余额(十进制): {}, 是否为零: {}", 
-                            chain, item.address, contract_address, balance_wei, balance_wei == 0);
-                    
-                    // 使用实际的 decimals 值计算余额
-                    let divisor = 10_u128.pow(decimals as u32);
-                    let balance_tokens = balance_wei as f64 / divisor as f64;
-                    
-                    println!("[DEBUG] 余额计算过程 - 链: {}, 地址: {}, 合约: {}, 原始余额: {}, decimals: {}, 除数: {}, 最终余额: {}", 
-                            chain, item.address, contract_address, balance_wei, decimals, divisor, balance_tokens);
-                    
-                    item.coin_balance = Some(format!("{balance_tokens:.6}")); // 显示6位小数
-                    
-                    println!("[DEBUG] 代币余额查询完成 - 链: {}, 地址: {}, 合约: {}, 格式化余额: {}", 
-                            chain, item.address, contract_address, item.coin_balance.as_ref().unwrap());
-                }
-                Err(e) => {
-                    println!("[ERROR] 代币余额十六进制转换失败 - 链: {}, 地址: {}, 合约: {}, 十六进制: {}, 错误: {}", 
-                            chain, item.address, contract_address, balance_hex, e);
-                    return Err(anyhow!("代币余额数值转换失败: {e} (原始值: {balance_hex})"));
-                }
-            }
+            let balance = parse_hex_quantity(&balance_result).ok_or_else(|| {
+                println!("[ERROR] 代币余额十六进制转换失败 - 链: {}, 地址: {}, 合约: {}, 十六进制: {}",
+                        chain, item.address, contract_address, balance_hex);
+                anyhow!("代币余额数值转换失败 (原始值: {balance_hex})")
+            })?;
+
+            // 按实际的 decimals 精确换算，不经过浮点数
+            item.coin_balance = Some(format_balance(balance, decimals)); // 显示6位小数
+            item.raw_balances.insert(contract_address.trim().to_lowercase(), (balance, decimals));
+
+            println!("[DEBUG] 代币余额查询完成 - 链: {}, 地址: {}, 合约: {}, 原始余额: {}, decimals: {}, 格式化余额: {}",
+                    chain, item.address, contract_address, balance, decimals, item.coin_balance.as_ref().unwrap());
         } else {
             println!("[WARNING] 代币余额查询返回空值 - 链: {}, 地址: {}, 合约: {}", 
                     chain, item.address, contract_address);
@@ -406,34 +610,9 @@ impl SimpleBalanceQueryService {
         item.exec_status = "1".to_string(); // 执行中
         item.error_msg = None;
 
-        // 如果有私钥，优先从私钥生成地址
-        if let Some(private_key_str) = &item.private_key {
-            if !private_key_str.trim().is_empty() {
-                // 处理私钥格式，兼容带0x和不带0x的格式
-                let private_key = if private_key_str.starts_with("0x") || private_key_str.starts_with("0X") {
-                    private_key_str[2..].to_string()
-                } else {
-                    private_key_str.clone()
-                };
-                
-// 从私钥生成地址
-                if let Ok(signer) = private_key.parse::<PrivateKeySigner>() {
-                    let address = format!("{:?}", signer.address());
-                    item.address = address;
-                    println!("[INFO] 从私钥生成地址: {}", item.address);
-            } else {
-                    item.exec_status = "3".to_string();
-                    item.error_msg = Some("私钥格式错误，无法生成地址".to_string());
-                    return item;
-                }
-            }
-        }
-
-        // 检查地址是否为空
-        if item.address.trim().is_empty() {
+        if let Err(e) = prepare_item(&mut item) {
             item.exec_status = "3".to_string();
-            item.error_msg = Some("地址为空，无法查询余额".to_string());
-            println!("[ERROR] 地址为空，无法查询余额 - key: {}", item.key);
+            item.error_msg = Some(e);
             return item;
         }
 
@@ -555,8 +734,457 @@ impl SimpleBalanceQueryService {
         item
     }
 
+    // 获取可用的Multicall3地址：链未配置或合约未部署时返回 None
+    async fn get_multicall3_address(&self, chain: &str, window_id: Option<&str>) -> Option<Address> {
+        let chain_service = ChainService::new(get_database_manager().get_pool());
+        let address: Address = chain_service.get_multicall3_address(chain).await.ok()??.trim().parse().ok()?;
+
+        let cached = MULTICALL3_DEPLOYED.lock().unwrap().get(&(chain.to_string(), address)).copied();
+        if let Some(deployed) = cached {
+            return deployed.then_some(address);
+        }
+
+        let rpc_url = self.get_rpc_url(chain).await.ok()?;
+        let code = self.send_rpc_request(
+            &rpc_url,
+            "eth_getCode",
+            serde_json::json!([format!("{address:?}"), "latest"]),
+            window_id
+        ).await.ok()?;
+        let deployed = code.as_str().is_some_and(|c| c.len() > 2);
+        if !deployed {
+            println!("[INFO] 链 {chain} 未部署Multicall3 ({address:?})，不使用Multicall3查询");
+        }
+        MULTICALL3_DEPLOYED.lock().unwrap().insert((chain.to_string(), address), deployed);
+        deployed.then_some(address)
+    }
+
+    // 按批量方式一次查询一组 (地址, 目标) 的余额，结果与输入一一对应，单个调用失败为 None
+    async fn fetch_balances(
+        &self,
+        chain: &str,
+        mode: BatchMode,
+        requests: &[(Address, BalanceTarget)],
+        window_id: &str,
+    ) -> Result<Vec<Option<U256>>> {
+        match mode {
+            BatchMode::Multicall3(multicall) => {
+                let calls = requests.iter().map(|(owner, target)| match target {
+                    BalanceTarget::Native => native_balance_call(multicall, *owner),
+                    BalanceTarget::Erc20(token) => erc20_balance_call(*token, *owner),
+                }).collect();
+                let rpc_url = self.get_rpc_url(chain).await?;
+                let output = self.send_rpc_request(
+                    &rpc_url,
+                    "eth_call",
+                    serde_json::json!([{
+                        "to": format!("{multicall:?}"),
+                        "data": encode_aggregate3(calls).to_string()
+                    }, self.block_tag()]),
+                    Some(window_id)
+                ).await?;
+                let output = output.as_str().ok_or_else(|| anyhow!("aggregate3 返回值为空"))?;
+                let output = hex::decode(output.trim_start_matches("0x")).map_err(|e| anyhow!("aggregate3 返回值格式错误: {e}"))?;
+                let results = decode_aggregate3(&output).map_err(|e| anyhow!(e))?;
+                if results.len() != requests.len() {
+                    return Err(anyhow!("aggregate3 返回结果数量不匹配"));
+                }
+                Ok(results.iter().map(decode_uint).collect())
+            }
+            BatchMode::RpcBatch(_) => {
+                let block_tag = self.block_tag();
+                let calls: Vec<RpcCall> = requests.iter().map(|(owner, target)| match target {
+                    BalanceTarget::Native => {
+                        RpcCall::new("eth_getBalance", serde_json::json!([format!("{owner:?}"), block_tag]))
+                    }
+                    BalanceTarget::Erc20(token) => RpcCall::new("eth_call", serde_json::json!([{
+                        "to": format!("{token:?}"),
+                        "data": balance_of_calldata(*owner).to_string()
+                    }, block_tag])),
+                }).collect();
+                let (rpc_url, max_batch_size) = self.get_rpc_with_batch_size(chain).await?;
+                let results = send_batch(&http_client(Some(window_id)), &rpc_url, &calls, max_batch_size).await;
+                Ok(results.iter().map(|r| r.as_ref().ok().and_then(parse_hex_quantity)).collect())
+            }
+        }
+    }
+
+    // 批量查询一组地址的余额，返回需要回退到逐地址查询的项目下标
+    async fn query_chunk_batched(
+        &self,
+        items: &mut [QueryItem],
+        params: &QueryParams,
+        mode: BatchMode,
+        token: Option<(Address, u8)>,
+        window_id: &str,
+    ) -> Result<Vec<usize>> {
+        let with_native = token.is_none() || !params.only_coin_config;
+
+        // 地址无法解析的项目直接走逐地址查询，由其给出错误信息
+        let mut fallback = Vec::new();
+        let mut queried = Vec::new();
+        let mut requests = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let Ok(owner) = item.address.trim().parse::<Address>() else {
+                fallback.push(i);
+                continue;
+            };
+            if with_native {
+                requests.push((owner, BalanceTarget::Native));
+            }
+            if let Some((token_address, _)) = token {
+                requests.push((owner, BalanceTarget::Erc20(token_address)));
+            }
+            queried.push(i);
+        }
+        if requests.is_empty() {
+            return Ok(fallback);
+        }
+
+        let results = self.fetch_balances(&params.chain, mode, &requests, window_id).await?;
+
+        // 单个调用失败只影响对应地址
+        let calls_per_item = usize::from(with_native) + usize::from(token.is_some());
+        for (i, chunk) in queried.into_iter().zip(results.chunks(calls_per_item)) {
+            let mut values = chunk.iter().copied();
+            let native = if with_native { Some(values.next().flatten()) } else { None };
+            let token_balance = token.map(|_| values.next().flatten());
+            if native.is_some_and(|v| v.is_none()) || token_balance.is_some_and(|v| v.is_none()) {
+                fallback.push(i);
+                continue;
+            }
+
+            let item = &mut items[i];
+            if let Some(Some(balance)) = native {
+                item.plat_balance = Some(format_balance(balance, 18));
+                item.raw_balances.insert(BASE_COIN_KEY.to_string(), (balance, 18));
+            }
+            if let (Some(Some(balance)), Some((token_address, decimals))) = (token_balance, token) {
+                item.coin_balance = Some(format_balance(balance, decimals));
+                item.raw_balances.insert(format!("{token_address:?}"), (balance, decimals));
+            }
+            item.exec_status = "2".to_string();
+            item.error_msg = None;
+        }
+        Ok(fallback)
+    }
+
+    // 选择批量查询方式：优先Multicall3，其次节点支持的JSON-RPC批量请求；均不可用时返回 None
+    // 历史查询不使用Multicall3（目标区块时合约可能尚未部署）
+    async fn select_batch_mode(&self, chain: &str, window_id: Option<&str>) -> Option<BatchMode> {
+        if self.historical.is_none()
+            && let Some(multicall) = self.get_multicall3_address(chain, window_id).await
+        {
+            return Some(BatchMode::Multicall3(multicall));
+        }
+        let (_, max_batch_size) = self.get_rpc_with_batch_size(chain).await.ok()?;
+        (max_batch_size > 1).then_some(BatchMode::RpcBatch(max_batch_size))
+    }
+
+    // 分块批量查询全部项目，每个项目完成时调用 on_update
+    async fn query_balances_batched<F: Fn(usize, &QueryItem) + Sync>(
+        &self,
+        params: &QueryParams,
+        mode: BatchMode,
+        window_id: &str,
+        on_update: F,
+    ) -> Vec<QueryItem> {
+        let token = match (params.coin_config.coin_type.as_str(), &params.coin_config.contract_address) {
+            ("token", Some(contract)) => match (contract.trim().parse::<Address>(), self.get_rpc_url(&params.chain).await) {
+                (Ok(address), Ok(rpc_url)) => {
+                    let decimals = self.get_token_decimals(&rpc_url, &params.chain, contract, Some(window_id)).await;
+                    Some((address, decimals))
+                }
+                // 合约地址或RPC异常时交给逐地址查询报告错误
+                _ => None,
+            },
+            _ => None,
+        };
+        let is_token = params.coin_config.coin_type == "token";
+        let calls_per_item = if is_token { 1 + usize::from(!params.only_coin_config) } else { 1 };
+        let batch_size = match mode {
+            BatchMode::Multicall3(_) => MULTICALL3_BATCH_SIZE,
+            BatchMode::RpcBatch(max_batch_size) => max_batch_size,
+        };
+        let chunk_size = (batch_size / calls_per_item).max(1);
+        let thread_count = params.thread_count.clamp(1, 99);
+        let semaphore = Semaphore::new(thread_count);
+
+        println!("开始批量查询余额（{:?}），每批 {} 个地址，总任务数: {}", mode, chunk_size, params.items.len());
+
+        let indexed: Vec<(usize, QueryItem)> = params.items.iter().cloned().enumerate().collect();
+        let tasks = indexed.chunks(chunk_size).map(|chunk| {
+            let semaphore = &semaphore;
+            let on_update = &on_update;
+            let mut chunk = chunk.to_vec();
+            async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                let mut items = Vec::with_capacity(chunk.len());
+                let mut indexes = Vec::with_capacity(chunk.len());
+                for (index, item) in chunk.iter_mut() {
+                    if get_stop_flag(window_id) {
+                        item.exec_status = "3".to_string();
+                        item.error_msg = Some("查询已被用户停止".to_string());
+                        on_update(*index, item);
+                        continue;
+                    }
+                    item.exec_status = "1".to_string();
+                    item.error_msg = None;
+                    on_update(*index, item);
+                    if let Err(e) = prepare_item(item) {
+                        item.exec_status = "3".to_string();
+                        item.error_msg = Some(e);
+                        on_update(*index, item);
+                        continue;
+                    }
+                    indexes.push(*index);
+                    items.push(item.clone());
+                }
+
+                // 代币合约地址无效等情况下不打包，全部走逐地址查询
+                let fallback = if is_token && token.is_none() {
+                    (0..items.len()).collect()
+                } else {
+                    match self.query_chunk_batched(&mut items, params, mode, token, window_id).await {
+                        Ok(fallback) => fallback,
+                        Err(e) => {
+                            println!("[WARN] 批量查询失败，该批次回退到逐地址查询: {e}");
+                            (0..items.len()).collect()
+                        }
+                    }
+                };
+                for i in fallback {
+                    let item = std::mem::take(&mut items[i]);
+                    items[i] = self.query_single_item(item, params, window_id).await;
+                }
+
+                let mut done: HashMap<usize, QueryItem> = indexes.into_iter().zip(items).collect();
+                for (index, item) in chunk.iter_mut() {
+                    if let Some(result) = done.remove(index) {
+                        on_update(*index, &result);
+                        *item = result;
+                    }
+                }
+                chunk
+            }
+        });
+
+        let mut results: Vec<(usize, QueryItem)> = join_all(tasks).await.into_iter().flatten().collect();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, item)| item).collect()
+    }
+
+    // 解析多币种查询的结果列：精度和符号来自 tokens 表，未配置的代币回退到合约查询
+    async fn resolve_token_columns(
+        &self,
+        chain: &str,
+        coins: &[CoinConfig],
+        window_id: &str,
+    ) -> Result<Vec<(TokenColumn, BalanceTarget)>> {
+        let chain_service = ChainService::new(get_database_manager().get_pool());
+        let mut columns: Vec<(TokenColumn, BalanceTarget)> = Vec::new();
+        for coin in coins {
+            let column = if coin.coin_type == "base" {
+                let (symbol, decimals) = match chain_service.get_base_token(chain).await? {
+                    Some(token) => (token.symbol, token.decimals),
+                    None => {
+                        let chain_info = chain_service.get_chain_by_key(chain).await?
+                            .ok_or_else(|| anyhow!("链不存在: {chain}"))?;
+                        (chain_info.native_currency_symbol, chain_info.native_currency_decimals)
+                    }
+                };
+                let column = TokenColumn {
+                    key: BASE_COLUMN_KEY.to_string(),
+                    symbol,
+                    contract_address: None,
+                    decimals: decimals as u8,
+                };
+                (column, BalanceTarget::Native)
+            } else {
+                let contract = coin.contract_address.as_deref().map(str::trim).filter(|c| !c.is_empty())
+                    .ok_or_else(|| anyhow!("代币缺少合约地址"))?;
+                let address: Address = contract.parse().map_err(|e| anyhow!("合约地址格式错误: {contract} ({e})"))?;
+                let (symbol, decimals) = match chain_service.get_token_by_contract(chain, contract).await? {
+                    Some(token) => (token.symbol, token.decimals as u8),
+                    None => {
+                        let rpc_url = self.get_rpc_url(chain).await?;
+                        (contract.to_string(), self.get_token_decimals(&rpc_url, chain, contract, Some(window_id)).await)
+                    }
+                };
+                let column = TokenColumn {
+                    key: contract.to_lowercase(),
+                    symbol,
+                    contract_address: Some(contract.to_string()),
+                    decimals,
+                };
+                (column, BalanceTarget::Erc20(address))
+            };
+            if !columns.iter().any(|(existing, _)| existing.key == column.0.key) {
+                columns.push(column);
+            }
+        }
+        if columns.is_empty() {
+            return Err(anyhow!("未选择任何币种"));
+        }
+        Ok(columns)
+    }
+
+    // 查询一组地址的全部币种余额，失败的调用重新选择RPC节点重试
+    async fn query_chunk_multi_token(
+        &self,
+        chain: &str,
+        mode: BatchMode,
+        columns: &[(TokenColumn, BalanceTarget)],
+        items: &mut [QueryItem],
+        window_id: &str,
+    ) {
+        let max_attempts = 3;
+        // (项目下标, 列下标, 地址, 查询目标)
+        let mut pending = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            if let Ok(owner) = item.address.trim().parse::<Address>() {
+                pending.extend(columns.iter().enumerate().map(|(c, (_, target))| (i, c, owner, *target)));
+            }
+        }
+
+        let mut last_error = None;
+        for attempt in 0..max_attempts {
+            if pending.is_empty() || get_stop_flag(window_id) {
+                break;
+            }
+            if attempt > 0 {
+                println!("[RETRY] 多币种余额查询重试 {attempt}/{} - 剩余 {} 个调用", max_attempts - 1, pending.len());
+                sleep(Duration::from_millis(500)).await;
+            }
+
+            let requests: Vec<_> = pending.iter().map(|(_, _, owner, target)| (*owner, *target)).collect();
+            let results = match self.fetch_balances(chain, mode, &requests, window_id).await {
+                Ok(results) => results,
+                Err(e) => {
+                    last_error = Some(e.to_string());
+                    vec![None; requests.len()]
+                }
+            };
+
+            let mut failed = Vec::new();
+            for (request, balance) in pending.into_iter().zip(results) {
+                let (i, c, _, _) = request;
+                match balance {
+                    Some(balance) => {
+                        let column = &columns[c].0;
+                        items[i].token_balances.insert(column.key.clone(), format_balance(balance, column.decimals));
+                        items[i].raw_balances.insert(column.key.clone(), (balance, column.decimals));
+                    }
+                    None => failed.push(request),
+                }
+            }
+            pending = failed;
+        }
+
+        let columns: Vec<TokenColumn> = columns.iter().map(|(column, _)| column.clone()).collect();
+        for item in items.iter_mut() {
+            finish_multi_token_item(item, &columns, last_error.as_deref());
+        }
+    }
+
+    // 多币种批量查询，每个项目开始和完成时调用 on_update
+    async fn query_multi_token_balances<F: Fn(usize, &QueryItem) + Sync>(
+        &self,
+        params: &MultiTokenQueryParams,
+        window_id: &str,
+        on_update: F,
+    ) -> MultiTokenQueryResult {
+        let columns = match self.resolve_token_columns(&params.chain, &params.coins, window_id).await {
+            Ok(columns) => columns,
+            Err(e) => {
+                return MultiTokenQueryResult {
+                    success: false,
+                    columns: Vec::new(),
+                    items: params.items.clone(),
+                    error_msg: Some(format!("解析币种配置失败: {e}")),
+                    snapshot_ids: Vec::new(),
+                    usd: UsdValuation::default(),
+                };
+            }
+        };
+
+        // 无批量方式可用时逐个调用发送
+        let mode = self.select_batch_mode(&params.chain, Some(window_id)).await.unwrap_or(BatchMode::RpcBatch(1));
+        let batch_size = match mode {
+            BatchMode::Multicall3(_) => MULTICALL3_BATCH_SIZE,
+            BatchMode::RpcBatch(max_batch_size) => max_batch_size,
+        };
+        let chunk_size = (batch_size / columns.len()).max(1);
+        let semaphore = Semaphore::new(params.thread_count.clamp(1, 99));
+
+        println!("开始多币种批量查询余额（{:?}），币种数: {}，每批 {} 个地址，总任务数: {}",
+                mode, columns.len(), chunk_size, params.items.len());
+
+        let indexed: Vec<(usize, QueryItem)> = params.items.iter().cloned().enumerate().collect();
+        let tasks = indexed.chunks(chunk_size).map(|chunk| {
+            let semaphore = &semaphore;
+            let on_update = &on_update;
+            let columns = &columns;
+            let mut chunk = chunk.to_vec();
+            async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                let mut items = Vec::with_capacity(chunk.len());
+                let mut indexes = Vec::with_capacity(chunk.len());
+                for (index, item) in chunk.iter_mut() {
+                    item.token_balances.clear();
+                    if get_stop_flag(window_id) {
+                        item.exec_status = "3".to_string();
+                        item.error_msg = Some("查询已被用户停止".to_string());
+                        on_update(*index, item);
+                        continue;
+                    }
+                    item.exec_status = "1".to_string();
+                    item.error_msg = None;
+                    on_update(*index, item);
+                    if let Err(e) = prepare_item(item) {
+                        item.exec_status = "3".to_string();
+                        item.error_msg = Some(e);
+                        on_update(*index, item);
+                        continue;
+                    }
+                    indexes.push(*index);
+                    items.push(item.clone());
+                }
+
+                self.query_chunk_multi_token(&params.chain, mode, columns, &mut items, window_id).await;
+
+                let mut done: HashMap<usize, QueryItem> = indexes.into_iter().zip(items).collect();
+                for (index, item) in chunk.iter_mut() {
+                    if let Some(result) = done.remove(index) {
+                        on_update(*index, &result);
+                        *item = result;
+                    }
+                }
+                chunk
+            }
+        });
+
+        let mut results: Vec<(usize, QueryItem)> = join_all(tasks).await.into_iter().flatten().collect();
+        results.sort_by_key(|(index, _)| *index);
+        let result = Self::build_query_result(results.into_iter().map(|(_, item)| item).collect());
+
+        MultiTokenQueryResult {
+            success: result.success,
+            columns: columns.into_iter().map(|(column, _)| column).collect(),
+            items: result.items,
+            error_msg: result.error_msg,
+            snapshot_ids: Vec::new(),
+            usd: UsdValuation::default(),
+        }
+    }
+
     // 批量查询余额（多线程）
     pub async fn query_balances(&self, params: QueryParams) -> QueryResult {
+        if let Some(mode) = self.select_batch_mode(&params.chain, None).await {
+            let results = self.query_balances_batched(&params, mode, "default", |_, _| {}).await;
+            return Self::build_query_result(results);
+        }
+
         let thread_count = params.thread_count.clamp(1, 99); // 限制线程数在1-99之间
         let semaphore = Arc::new(Semaphore::new(thread_count));
 
@@ -595,6 +1223,30 @@ impl SimpleBalanceQueryService {
             success,
             items: results,
             error_msg,
+            snapshot_id: None,
+            usd: UsdValuation::default(),
+        }
+    }
+
+    fn build_query_result(items: Vec<QueryItem>) -> QueryResult {
+        let success = items.iter().all(|item| item.exec_status == "2");
+        let error_msg = if success {
+            None
+        } else {
+            let first_error = items.iter()
+                .find(|item| item.error_msg.as_ref().is_some_and(|e| !e.is_empty()))
+                .and_then(|item| item.error_msg.clone());
+            Some(first_error.unwrap_or_else(|| "查询失败".to_string()))
+        };
+
+        println!("查询完成，成功: {success}");
+
+        QueryResult {
+            success,
+            items,
+            error_msg,
+            snapshot_id: None,
+            usd: UsdValuation::default(),
         }
     }
 
@@ -608,13 +1260,28 @@ impl SimpleBalanceQueryService {
         // 重置停止标志
         reset_stop_flag(&window_id);
 
+        if let Some(mode) = self.select_batch_mode(&params.chain, Some(&window_id)).await {
+            let query_id = params.query_id.clone().unwrap_or_default();
+            let results = self.query_balances_batched(&params, mode, &window_id, |index, item| {
+                if let Err(e) = app_handle.emit("balance_item_update", serde_json::json!({
+                    "index": index,
+                    "item": item,
+                    "window_id": window_id,
+                    "query_id": query_id
+                })) {
+                    println!("发送查询进度事件失败: {e}");
+                }
+            }).await;
+            return Self::build_query_result(results);
+        }
+
         let thread_count = params.thread_count.clamp(1, 99); // 限制线程数在1-99之间
         let semaphore = Arc::new(Semaphore::new(thread_count));
 
         println!("开始批量查询余额（实时更新），线程数: {}, 总任务数: {}", thread_count, params.items.len());
 
         let total_items = params.items.len();
-        let service = Arc::new(SimpleBalanceQueryService::new());
+        let service = Arc::new(self.clone());
 
         // 创建结果数组，使用索引映射确保按原始顺序返回
         // results[original_index] = Some(result) 或 None（如果任务失败）
@@ -717,6 +1384,10 @@ impl SimpleBalanceQueryService {
                     retry_flag: true,
                     exec_status: "3".to_string(),
                     error_msg: Some(format!("任务执行失败{}", if join_errors > 0 { format!("（{join_errors}个任务异常）") } else { String::new() })),
+                    token_balances: HashMap::new(),
+                    usd_value: None,
+                    ens_name: None,
+                    raw_balances: HashMap::new(),
                 };
                 ordered_results.push(error_item);
             }
@@ -738,29 +1409,233 @@ impl SimpleBalanceQueryService {
             success,
             items: ordered_results,
             error_msg,
+            snapshot_id: None,
+            usd: UsdValuation::default(),
+        }
+    }
+}
+
+// 把单币种查询结果保存为余额快照，保存失败只记录日志，不影响查询结果
+async fn snapshot_query_result(chain: &str, coin_config: &CoinConfig, block_number: Option<u64>, result: &mut QueryResult) {
+    let coin_key = match (coin_config.coin_type.as_str(), &coin_config.contract_address) {
+        ("base", _) => BASE_COIN_KEY.to_string(),
+        (_, Some(contract)) => contract.trim().to_lowercase(),
+        _ => return,
+    };
+    let raw = |item: &QueryItem| item.raw_balances.get(&coin_key).copied().filter(|_| item.exec_status == "2");
+    // 全部查询失败时没有精度信息，余额均为空，精度不影响比较
+    let decimals = result.items.iter().find_map(raw).map_or(18, |(_, decimals)| decimals);
+    let items = result
+        .items
+        .iter()
+        .map(|item| (item.address.clone(), raw(item).map(|(balance, _)| balance), item.nonce))
+        .collect();
+    match save_balance_snapshot(chain, &coin_key, decimals, block_number, items).await {
+        Ok(snapshot_id) => result.snapshot_id = Some(snapshot_id),
+        Err(e) => println!("[WARN] {e}"),
+    }
+}
+
+// 多币种查询的每一列分别保存为一个余额快照
+async fn snapshot_multi_token_result(chain: &str, block_number: Option<u64>, result: &mut MultiTokenQueryResult) {
+    for column in &result.columns {
+        let coin_key = if column.key == BASE_COLUMN_KEY { BASE_COIN_KEY } else { column.key.as_str() };
+        let items = result
+            .items
+            .iter()
+            .map(|item| (item.address.clone(), item.raw_balances.get(&column.key).map(|(balance, _)| *balance), item.nonce))
+            .collect();
+        match save_balance_snapshot(chain, coin_key, column.decimals, block_number, items).await {
+            Ok(snapshot_id) => result.snapshot_ids.push(snapshot_id),
+            Err(e) => println!("[WARN] {e}"),
         }
     }
 }
 
+// 按链上价格计算单币种查询结果的美元价值，历史查询不计算（价格只能读取最新值）
+async fn value_query_result(chain: &str, coin_config: &CoinConfig, window_id: Option<&str>, result: &mut QueryResult) {
+    let token_key = match (coin_config.coin_type.as_str(), &coin_config.contract_address) {
+        ("base", _) => None,
+        (_, Some(contract)) => Some(contract.trim().to_lowercase()),
+        _ => return,
+    };
+    let rows: Vec<Vec<(String, String)>> = result
+        .items
+        .iter()
+        .map(|item| {
+            if item.exec_status != "2" {
+                return Vec::new();
+            }
+            let mut row = Vec::new();
+            if let Some(balance) = &item.plat_balance {
+                row.push((BASE_COIN_KEY.to_string(), balance.clone()));
+            }
+            if let (Some(token_key), Some(balance)) = (&token_key, &item.coin_balance) {
+                row.push((token_key.clone(), balance.clone()));
+            }
+            row
+        })
+        .collect();
+
+    let (valuation, values) = value_balances(chain, window_id, &rows).await;
+    for (item, value) in result.items.iter_mut().zip(values) {
+        item.usd_value = value;
+    }
+    result.usd = valuation;
+}
+
+// 多币种查询按每行所有币种的美元价值求和
+async fn value_multi_token_result(chain: &str, window_id: Option<&str>, result: &mut MultiTokenQueryResult) {
+    let rows: Vec<Vec<(String, String)>> = result
+        .items
+        .iter()
+        .map(|item| {
+            if item.exec_status != "2" {
+                return Vec::new();
+            }
+            result
+                .columns
+                .iter()
+                .filter_map(|column| {
+                    let balance = item.token_balances.get(&column.key)?;
+                    let coin_key = if column.key == BASE_COLUMN_KEY { BASE_COIN_KEY } else { column.key.as_str() };
+                    Some((coin_key.to_string(), balance.clone()))
+                })
+                .collect()
+        })
+        .collect();
+
+    let (valuation, values) = value_balances(chain, window_id, &rows).await;
+    for (item, value) in result.items.iter_mut().zip(values) {
+        item.usd_value = value;
+    }
+    result.usd = valuation;
+}
+
+// 把地址簿分组展开为查询项，追加在传入的地址之后
+async fn expand_address_group(chain: &str, group: Option<&str>, items: &mut Vec<QueryItem>) -> Result<(), String> {
+    let Some(group) = group.map(str::trim).filter(|group| !group.is_empty()) else {
+        return Ok(());
+    };
+    let addresses = resolve_group_addresses(chain, group).await?;
+    items.extend(addresses.into_iter().map(|address| QueryItem {
+        key: address.clone(),
+        address,
+        exec_status: "0".to_string(),
+        ..Default::default()
+    }));
+    Ok(())
+}
+
+// 以一次批量请求查询全部地址的nonce，查询失败的地址nonce为空
+async fn fetch_nonces(client: &Client, rpc_url: &str, max_batch_size: usize, block_tag: &str, items: &mut [QueryItem]) {
+    let targets: Vec<(usize, Address)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| Some((index, item.address.trim().parse().ok()?)))
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+    let calls: Vec<RpcCall> = targets
+        .iter()
+        .map(|(_, address)| RpcCall::new("eth_getTransactionCount", serde_json::json!([format!("{address:?}"), block_tag])))
+        .collect();
+    let results = send_batch(client, rpc_url, &calls, max_batch_size).await;
+    for ((index, _), result) in targets.into_iter().zip(results) {
+        items[index].nonce = result.ok().as_ref().and_then(parse_quantity);
+    }
+}
+
+// 为查询成功的地址反向解析ENS名称
+async fn attach_ens_names(chain: &str, window_id: Option<&str>, items: &mut [QueryItem]) {
+    let targets: Vec<(usize, Address)> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.exec_status == "2")
+        .filter_map(|(index, item)| Some((index, item.address.trim().parse().ok()?)))
+        .collect();
+    let addresses: Vec<Address> = targets.iter().map(|(_, address)| *address).collect();
+    let names = lookup_names(chain, window_id, &addresses).await;
+    for ((index, _), name) in targets.into_iter().zip(names) {
+        items[index].ens_name = name;
+    }
+}
+
 // Tauri 命令：查询余额
 #[tauri::command]
-pub async fn query_balances_simple(params: QueryParams) -> Result<QueryResult, String> {
-    let service = SimpleBalanceQueryService::new();
-    
-    let result = service.query_balances(params).await;
+pub async fn query_balances_simple(mut params: QueryParams) -> Result<QueryResult, String> {
+    expand_address_group(&params.chain, params.address_group.as_deref(), &mut params.items).await?;
+    let service = SimpleBalanceQueryService::at_block(&params.chain, params.block, None).await?;
+    let (chain, coin_config) = (params.chain.clone(), params.coin_config.clone());
+
+    let resolve_names = params.resolve_names;
+    let mut result = service.query_balances(params).await;
+    if resolve_names {
+        attach_ens_names(&chain, None, &mut result.items).await;
+    }
+    service.attach_nonces(&chain, None, &mut result.items).await;
+    snapshot_query_result(&chain, &coin_config, service.block_number(), &mut result).await;
+    if service.block_number().is_none() {
+        value_query_result(&chain, &coin_config, None, &mut result).await;
+    }
     Ok(result)
 }
 
 // 带有实时更新的查询余额命令
 #[tauri::command]
 pub async fn query_balances_with_updates<R: tauri::Runtime>(
-    params: QueryParams,
+    mut params: QueryParams,
     app_handle: tauri::AppHandle<R>,
 ) -> Result<QueryResult, String> {
+    expand_address_group(&params.chain, params.address_group.as_deref(), &mut params.items).await?;
+    let window_id = params.window_id.clone().unwrap_or_default();
+    let service = SimpleBalanceQueryService::at_block(&params.chain, params.block, Some(&window_id)).await?;
+    let (chain, coin_config) = (params.chain.clone(), params.coin_config.clone());
+
+    let resolve_names = params.resolve_names;
+    let mut result = service.query_balances_with_updates(params, app_handle, window_id.clone()).await;
+    if resolve_names {
+        attach_ens_names(&chain, Some(&window_id), &mut result.items).await;
+    }
+    service.attach_nonces(&chain, Some(&window_id), &mut result.items).await;
+    snapshot_query_result(&chain, &coin_config, service.block_number(), &mut result).await;
+    if service.block_number().is_none() {
+        value_query_result(&chain, &coin_config, Some(&window_id), &mut result).await;
+    }
+    Ok(result)
+}
+
+// 多币种查询余额命令（带实时更新）
+#[tauri::command]
+pub async fn query_multi_token_balances<R: tauri::Runtime>(
+    mut params: MultiTokenQueryParams,
+    app_handle: tauri::AppHandle<R>,
+) -> Result<MultiTokenQueryResult, String> {
+    expand_address_group(&params.chain, params.address_group.as_deref(), &mut params.items).await?;
     let window_id = params.window_id.clone().unwrap_or_default();
-    let service = SimpleBalanceQueryService::new();
-    
-    let result = service.query_balances_with_updates(params, app_handle, window_id).await;
+    let query_id = params.query_id.clone().unwrap_or_default();
+    reset_stop_flag(&window_id);
+
+    let service = SimpleBalanceQueryService::at_block(&params.chain, params.block, Some(&window_id)).await?;
+    let mut result = service.query_multi_token_balances(&params, &window_id, |index, item| {
+        if let Err(e) = app_handle.emit("balance_item_update", serde_json::json!({
+            "index": index,
+            "item": item,
+            "window_id": window_id,
+            "query_id": query_id
+        })) {
+            println!("发送查询进度事件失败: {e}");
+        }
+    }).await;
+    if params.resolve_names {
+        attach_ens_names(&params.chain, Some(&window_id), &mut result.items).await;
+    }
+    service.attach_nonces(&params.chain, Some(&window_id), &mut result.items).await;
+    snapshot_multi_token_result(&params.chain, service.block_number(), &mut result).await;
+    if service.block_number().is_none() {
+        value_multi_token_result(&params.chain, Some(&window_id), &mut result).await;
+    }
     Ok(result)
 }
 
@@ -779,3 +1654,109 @@ pub async fn reset_balance_query_stop(window_id: String) -> Result<(), String> {
     println!("重置余额查询停止标志，窗口ID: {window_id}");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::balance_snapshot_service::BalanceSnapshotService;
+    use crate::database::models::{CreateBalanceSnapshotItemRequest, CreateBalanceSnapshotRequest};
+    use std::io::{Read, Write};
+
+    // 只响应一次请求的本地节点：批量请求中每个调用都返回 nonce 7
+    fn mock_nonce_rpc() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                let Some(header_end) = text.find("\r\n\r\n") else { continue };
+                let length = text[..header_end]
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + length {
+                    break request[header_end + 4..header_end + 4 + length].to_vec();
+                }
+            };
+            let calls: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            let responses: Vec<serde_json::Value> = calls
+                .iter()
+                .map(|call| serde_json::json!({ "jsonrpc": "2.0", "id": call["id"], "result": "0x7" }))
+                .collect();
+            let body = serde_json::to_string(&responses).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_saves_nonce() {
+        let mut items: Vec<QueryItem> = [0x11u8, 0x22]
+            .iter()
+            .map(|byte| QueryItem { address: format!("{:?}", Address::repeat_byte(*byte)), exec_status: "2".to_string(), ..Default::default() })
+            .collect();
+        fetch_nonces(&Client::builder().no_proxy().build().unwrap(), &mock_nonce_rpc(), 10, "latest", &mut items).await;
+        assert!(items.iter().all(|item| item.nonce == Some(7)));
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query(include_str!("../../../../data/init.sql")).execute(&pool).await.unwrap();
+        let service = BalanceSnapshotService::new(&pool);
+        let snapshot_id = service
+            .create_snapshot(CreateBalanceSnapshotRequest {
+                chain_key: "eth".to_string(),
+                coin_key: BASE_COIN_KEY.to_string(),
+                coin_symbol: "ETH".to_string(),
+                block_number: None,
+                label: None,
+                decimals: 18,
+                items: items
+                    .iter()
+                    .map(|item| CreateBalanceSnapshotItemRequest {
+                        address: item.address.clone(),
+                        balance: Some("0".to_string()),
+                        nonce: item.nonce.map(|nonce| nonce as i64),
+                    })
+                    .collect(),
+            })
+            .await
+            .unwrap();
+        let saved = service.get_snapshot_items(snapshot_id).await.unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.iter().all(|item| item.nonce == Some(7)));
+    }
+
+    #[test]
+    fn test_finish_multi_token_item() {
+        let columns = vec![
+            TokenColumn { key: BASE_COLUMN_KEY.to_string(), symbol: "ETH".to_string(), contract_address: None, decimals: 18 },
+            TokenColumn { key: "0xusdc".to_string(), symbol: "USDC".to_string(), contract_address: Some("0xUSDC".to_string()), decimals: 6 },
+        ];
+        let mut item = QueryItem {
+            address: format!("{:?}", Address::repeat_byte(0x11)),
+            exec_status: "1".to_string(),
+            ..Default::default()
+        };
+        item.token_balances.insert(BASE_COLUMN_KEY.to_string(), "1.500000".to_string());
+
+        finish_multi_token_item(&mut item, &columns, None);
+        assert_eq!(item.exec_status, "3");
+        assert_eq!(item.plat_balance.as_deref(), Some("1.500000"));
+        assert_eq!(item.error_msg.as_deref(), Some("USDC 查询失败"));
+
+        item.exec_status = "1".to_string();
+        item.token_balances.insert("0xusdc".to_string(), "20.000000".to_string());
+        finish_multi_token_item(&mut item, &columns, None);
+        assert_eq!(item.exec_status, "2");
+        assert_eq!(item.error_msg, None);
+    }
+}
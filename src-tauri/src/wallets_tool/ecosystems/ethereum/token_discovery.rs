@@ -0,0 +1,407 @@
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolEvent, SolValue};
+use alloy_primitives::{Address, B256};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::future::Future;
+use tauri::Emitter;
+use tokio::sync::Semaphore;
+use crate::database::{get_database_manager, chain_service::ChainService, rpc_service::RpcService};
+use crate::database::models::CreateTokenRequest;
+use super::rpc_batch::{RpcCall, call_single, http_client, parse_quantity, send_batch};
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    function symbol() external view returns (string);
+    function decimals() external view returns (uint8);
+}
+
+// 单次 eth_getLogs 的初始区块跨度，成功后逐步放大，节点报结果过多时减半
+const INITIAL_BLOCK_SPAN: u64 = 10_000;
+const MAX_BLOCK_SPAN: u64 = 500_000;
+
+// 一次 eth_getLogs 的 topics 中最多合并的接收地址数
+const ADDRESS_TOPIC_BATCH: usize = 50;
+
+// 节点因结果过多或区块范围过大拒绝 eth_getLogs 时的错误片段，只匹配各服务商的具体提示，
+// 避免把其它错误（如包含数字或 "range" 的参数错误）误判为需要缩小跨度
+const RANGE_ERROR_HINTS: [&str; 6] = [
+    "block range",               // geth / Ankr / BSC: "exceed maximum block range", "block range is too wide"
+    "query returned more than",  // Infura: "query returned more than 10000 results"
+    "limit exceeded",            // "Log response size limit exceeded" 等
+    "response size exceeded",    // Alchemy: "Log response size exceeded"
+    "query timeout",             // 节点查询超时
+    "rpc请求超时",               // rpc_batch 的请求超时
+];
+
+/// 代币发现参数，未指定区块范围时扫描整条链
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDiscoveryParams {
+    pub chain: String,
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    #[serde(default = "default_thread_count")]
+    pub thread_count: usize,
+    #[serde(default)]
+    pub window_id: Option<String>,
+    #[serde(default)]
+    pub query_id: Option<String>,
+}
+
+fn default_thread_count() -> usize {
+    3
+}
+
+/// 发现的代币合约
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredToken {
+    pub contract_address: String,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub holders: Vec<String>,  // 收到过该代币的地址
+    pub already_added: bool,   // 是否已在代币配置中
+}
+
+/// 代币发现结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDiscoveryResult {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub tokens: Vec<DiscoveredToken>,
+    pub address_tokens: BTreeMap<String, Vec<String>>, // 地址 -> 收到过的代币合约
+}
+
+/// 批量添加的代币
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredTokenInput {
+    pub contract_address: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+fn is_range_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    RANGE_ERROR_HINTS.iter().any(|hint| error.contains(hint))
+}
+
+/// 分段扫描 [from, to] 区间：成功后放大跨度，范围类错误时减半跨度重试该段
+pub async fn scan_block_ranges<T, F, Fut>(from: u64, to: u64, mut fetch: F) -> Result<Vec<T>, String>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, String>>,
+{
+    let mut items = Vec::new();
+    let mut span = INITIAL_BLOCK_SPAN;
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(span - 1).min(to);
+        match fetch(start, end).await {
+            Ok(batch) => {
+                items.extend(batch);
+                if end == to {
+                    break;
+                }
+                start = end + 1;
+                span = (span * 2).min(MAX_BLOCK_SPAN);
+            }
+            Err(e) if is_range_error(&e) && span > 1 => {
+                span = (end - start + 1).div_ceil(2);
+                println!("[WARN] eth_getLogs 范围过大，缩小为每段 {span} 个区块后重试: {e}");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(items)
+}
+
+async fn latest_block(client: &Client, rpc_url: &str) -> Result<u64, String> {
+    let value = call_single(client, rpc_url, "eth_blockNumber", json!([])).await?;
+    parse_quantity(&value).ok_or_else(|| "区块高度格式错误".to_string())
+}
+
+fn address_topic(address: &Address) -> String {
+    format!("{:?}", address.into_word())
+}
+
+// 从日志中取出 (接收地址, 代币合约)；ERC-721 的 Transfer 多一个 indexed tokenId，按 topics 数量排除
+fn parse_transfer_log(log: &Value) -> Option<(Address, Address)> {
+    let topics = log.get("topics")?.as_array()?;
+    if topics.len() != 3 {
+        return None;
+    }
+    let to: B256 = topics[2].as_str()?.parse().ok()?;
+    let contract: Address = log.get("address")?.as_str()?.parse().ok()?;
+    Some((Address::from_word(to), contract))
+}
+
+// 扫描一组接收地址的 ERC-20 转入日志
+async fn scan_transfers_to(
+    client: &Client,
+    rpc_url: &str,
+    recipients: &[Address],
+    from: u64,
+    to: u64,
+) -> Result<Vec<(Address, Address)>, String> {
+    let topics = json!([
+        format!("{:?}", Transfer::SIGNATURE_HASH),
+        Value::Null,
+        recipients.iter().map(address_topic).collect::<Vec<_>>(),
+    ]);
+    scan_block_ranges(from, to, |start, end| {
+        let filter = json!([{
+            "fromBlock": format!("0x{start:x}"),
+            "toBlock": format!("0x{end:x}"),
+            "topics": topics,
+        }]);
+        async move {
+            let logs = call_single(client, rpc_url, "eth_getLogs", filter).await?;
+            let logs = logs.as_array().ok_or_else(|| "eth_getLogs 返回格式错误".to_string())?;
+            Ok(logs.iter().filter_map(parse_transfer_log).collect())
+        }
+    }).await
+}
+
+// string 类型的 symbol()；部分早期代币（如MKR）返回 bytes32
+fn decode_symbol(data: &[u8]) -> Option<String> {
+    let symbol = String::abi_decode(data)
+        .ok()
+        .or_else(|| {
+            let word = B256::abi_decode(data).ok()?;
+            Some(String::from_utf8_lossy(word.as_slice()).trim_end_matches('\0').to_string())
+        })?;
+    let symbol = symbol.trim().to_string();
+    (!symbol.is_empty()).then_some(symbol)
+}
+
+fn decode_hex_result(result: Result<Value, String>) -> Option<Vec<u8>> {
+    let value = result.ok()?;
+    alloy_primitives::hex::decode(value.as_str()?).ok()
+}
+
+// 批量读取代币的 symbol() 与 decimals()
+async fn read_token_metadata(
+    client: &Client,
+    rpc_url: &str,
+    max_batch_size: usize,
+    contracts: &[Address],
+) -> Vec<(Option<String>, Option<u8>)> {
+    let calls: Vec<RpcCall> = contracts
+        .iter()
+        .flat_map(|contract| {
+            [symbolCall {}.abi_encode(), decimalsCall {}.abi_encode()].map(|data| {
+                RpcCall::new("eth_call", json!([{
+                    "to": format!("{contract:?}"),
+                    "data": format!("0x{}", alloy_primitives::hex::encode(data)),
+                }, "latest"]))
+            })
+        })
+        .collect();
+    let mut results = send_batch(client, rpc_url, &calls, max_batch_size).await.into_iter();
+
+    contracts
+        .iter()
+        .map(|_| {
+            let symbol = results.next().and_then(decode_hex_result).and_then(|data| decode_symbol(&data));
+            let decimals = results
+                .next()
+                .and_then(decode_hex_result)
+                .and_then(|data| decimalsCall::abi_decode_returns(&data).ok());
+            (symbol, decimals)
+        })
+        .collect()
+}
+
+// Tauri命令：扫描地址列表收到过的 ERC-20 代币
+#[tauri::command]
+pub async fn discover_tokens<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    params: TokenDiscoveryParams,
+) -> Result<TokenDiscoveryResult, String> {
+    let window_id = params.window_id.clone().unwrap_or_default();
+    let query_id = params.query_id.clone().unwrap_or_default();
+
+    let mut recipients = Vec::new();
+    for address in &params.addresses {
+        let address = address.trim();
+        let parsed = address.parse::<Address>().map_err(|e| format!("地址格式错误: {address}, {e}"))?;
+        if !recipients.contains(&parsed) {
+            recipients.push(parsed);
+        }
+    }
+    if recipients.is_empty() {
+        return Err("地址列表为空".to_string());
+    }
+
+    let (rpc_url, max_batch_size) = RpcService::new(get_database_manager().get_pool())
+        .get_random_rpc_with_batch_size(&params.chain)
+        .await
+        .map_err(|e| format!("获取RPC地址失败: {e}"))?;
+    let client = http_client(Some(&window_id));
+
+    let to_block = match params.to_block {
+        Some(block) => block,
+        None => latest_block(&client, &rpc_url).await?,
+    };
+    let from_block = params.from_block.unwrap_or(0);
+    if from_block > to_block {
+        return Err(format!("起始区块 {from_block} 大于结束区块 {to_block}"));
+    }
+
+    // 接收地址分组扫描，每组完成后推送进度
+    let groups: Vec<&[Address]> = recipients.chunks(ADDRESS_TOPIC_BATCH).collect();
+    let total_groups = groups.len();
+    let semaphore = Semaphore::new(params.thread_count.clamp(1, 10));
+    let tasks = groups.into_iter().enumerate().map(|(index, group)| {
+        let semaphore = &semaphore;
+        let client = &client;
+        let rpc_url = rpc_url.as_str();
+        let app_handle = &app_handle;
+        let window_id = &window_id;
+        let query_id = &query_id;
+        async move {
+            let _permit = semaphore.acquire().await.unwrap();
+            let result = scan_transfers_to(client, rpc_url, group, from_block, to_block).await;
+            if let Err(e) = app_handle.emit("token_discovery_progress", json!({
+                "index": index,
+                "total": total_groups,
+                "success": result.is_ok(),
+                "window_id": window_id,
+                "query_id": query_id
+            })) {
+                println!("发送代币发现进度事件失败: {e}");
+            }
+            result
+        }
+    });
+
+    let mut holders_by_contract: BTreeMap<Address, BTreeSet<Address>> = BTreeMap::new();
+    for result in join_all(tasks).await {
+        for (recipient, contract) in result? {
+            holders_by_contract.entry(contract).or_default().insert(recipient);
+        }
+    }
+
+    let contracts: Vec<Address> = holders_by_contract.keys().copied().collect();
+    let metadata = read_token_metadata(&client, &rpc_url, max_batch_size, &contracts).await;
+
+    let chain_service = ChainService::new(get_database_manager().get_pool());
+    let known: HashSet<String> = chain_service
+        .get_token_contracts(&params.chain)
+        .await
+        .map_err(|e| format!("获取代币配置失败: {e}"))?
+        .into_iter()
+        .map(|contract| contract.to_lowercase())
+        .collect();
+
+    let mut address_tokens: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let tokens = holders_by_contract
+        .into_iter()
+        .zip(metadata)
+        .map(|((contract, holders), (symbol, decimals))| {
+            let contract_address = contract.to_checksum(None);
+            for holder in &holders {
+                address_tokens.entry(holder.to_checksum(None)).or_default().push(contract_address.clone());
+            }
+            DiscoveredToken {
+                already_added: known.contains(&contract_address.to_lowercase()),
+                contract_address,
+                symbol,
+                decimals,
+                holders: holders.iter().map(|holder| holder.to_checksum(None)).collect(),
+            }
+        })
+        .collect();
+
+    Ok(TokenDiscoveryResult { from_block, to_block, tokens, address_tokens })
+}
+
+// Tauri命令：批量添加发现的代币，已存在的合约会被跳过，返回实际添加数量
+#[tauri::command]
+pub async fn add_discovered_tokens(chain: String, tokens: Vec<DiscoveredTokenInput>) -> Result<usize, String> {
+    let requests = tokens
+        .into_iter()
+        .map(|token| CreateTokenRequest {
+            chain_key: chain.clone(),
+            token_key: token.symbol.to_lowercase(),
+            token_name: token.symbol.clone(),
+            symbol: token.symbol,
+            contract_address: Some(token.contract_address),
+            decimals: token.decimals as i32,
+            token_type: "token".to_string(),
+            contract_type: Some("ERC20".to_string()),
+            abi: None,
+        })
+        .collect();
+
+    ChainService::new(get_database_manager().get_pool())
+        .add_tokens(&chain, requests)
+        .await
+        .map_err(|e| format!("批量添加代币失败: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scan_block_ranges_shrinks_span() {
+        // 节点单次最多返回 1000 个区块的日志，每个区块一条
+        let fetch = |start: u64, end: u64| async move {
+            if end - start + 1 > 1000 {
+                Err("RPC Error: -32005 - query returned more than 10000 results".to_string())
+            } else {
+                Ok((start..=end).collect::<Vec<u64>>())
+            }
+        };
+        let blocks = scan_block_ranges(5, 25_004, fetch).await.unwrap();
+        assert_eq!(blocks, (5..=25_004).collect::<Vec<u64>>());
+
+        let failing = |_: u64, _: u64| async { Err::<Vec<u64>, _>("RPC Error: -32601 - method not found".to_string()) };
+        assert!(scan_block_ranges(0, 10, failing).await.is_err());
+    }
+
+    #[test]
+    fn test_is_range_error() {
+        assert!(is_range_error("RPC Error: -32000 - exceed maximum block range: 50000"));
+        assert!(is_range_error("Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range"));
+        assert!(is_range_error("RPC请求超时，RPC地址: https://example.org"));
+        // 与日志范围无关的错误不应缩小跨度重试
+        assert!(!is_range_error("RPC Error: -32602 - invalid argument 0: hex number > 64 bits"));
+        assert!(!is_range_error("RPC Error: -32000 - insufficient funds, balance 10000"));
+        assert!(!is_range_error("gas required exceeds allowance"));
+    }
+
+    #[test]
+    fn test_decode_symbol() {
+        assert_eq!(decode_symbol(&"USDC".to_string().abi_encode()), Some("USDC".to_string()));
+        let mut word = [0u8; 32];
+        word[..3].copy_from_slice(b"MKR");
+        assert_eq!(decode_symbol(&word), Some("MKR".to_string()));
+        assert_eq!(decode_symbol(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_transfer_log() {
+        let log = json!({
+            "address": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "topics": [
+                format!("{:?}", Transfer::SIGNATURE_HASH),
+                "0x000000000000000000000000000000000000000000000000000000000000dead",
+                "0x000000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa96045",
+            ],
+        });
+        let (to, contract) = parse_transfer_log(&log).unwrap();
+        assert_eq!(to, "0xd8da6bf26964af9d7eed9e03e53415d37aa96045".parse::<Address>().unwrap());
+        assert_eq!(contract, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".parse::<Address>().unwrap());
+
+        let mut nft_log = log.clone();
+        nft_log["topics"].as_array_mut().unwrap().push(json!("0x01"));
+        assert!(parse_transfer_log(&nft_log).is_none());
+    }
+}
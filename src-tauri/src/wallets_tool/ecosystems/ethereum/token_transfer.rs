@@ -5,12 +5,17 @@ use alloy_rpc_types_eth::TransactionRequest;
 use alloy_signer_local::PrivateKeySigner;
 use alloy_signer::Signer;
 use std::sync::Arc;
-use rand::Rng;
 use tauri::Emitter;
-use super::transfer::{TransferConfig, TransferItem, TransferResult, TransferUtils, create_provider, create_signer_provider, get_rpc_config, FastTransferResult, get_stop_flag};
+use super::transfer::{
+    TransferConfig, TransferItem, TransferResult, TransferUtils, create_provider, create_signer_provider, get_rpc_config,
+    FastTransferResult, get_stop_flag, fixed_transfer_units, random_transfer_units, remaining_transfer_units,
+};
 use crate::wallets_tool::ecosystems::ethereum::provider::{ProviderUtils, AlloyProvider};
 use hex;
-use super::alloy_utils::{parse_ether_to_wei_f64, format_wei_to_ether, format_wei_to_gwei, u256_to_f64};
+use super::alloy_utils::{DecimalAmount, format_wei_to_ether, format_wei_to_gwei, format_units};
+use super::nonce_manager::{BeforeBroadcast, broadcast_signed, reserve_nonce, report_nonce_gap, sign_transaction};
+use super::ens::resolve_recipient;
+use crate::wallets_tool::address_book::group_recipient;
 
 
 
@@ -21,9 +26,9 @@ pub struct TokenTransferConfig {
     pub contract_address: String,
     pub delay: [u64; 2],
     pub transfer_type: String,
-    pub transfer_amount: f64,
-    pub transfer_amount_list: [f64; 2],
-    pub left_amount_list: [f64; 2],
+    pub transfer_amount: DecimalAmount,
+    pub transfer_amount_list: [DecimalAmount; 2],
+    pub left_amount_list: [DecimalAmount; 2],
     pub amount_precision: u8,
     pub limit_type: String,
     pub limit_count: u64,
@@ -32,12 +37,40 @@ pub struct TokenTransferConfig {
     pub gas_price: f64,
     pub gas_price_rate: f64,
     pub max_gas_price: f64,
+    #[serde(default)]
+    pub fee_tier: String,
     pub error_retry: String,
     pub error_count_limit: u32,
     #[serde(default)]
     pub window_id: Option<String>,
 }
 
+// Gas相关计算复用原生币转账的配置
+impl From<&TokenTransferConfig> for TransferConfig {
+    fn from(config: &TokenTransferConfig) -> Self {
+        TransferConfig {
+            chain: config.chain.clone(),
+            delay: config.delay,
+            transfer_type: config.transfer_type.clone(),
+            transfer_amount: config.transfer_amount.clone(),
+            transfer_amount_list: config.transfer_amount_list.clone(),
+            left_amount_list: config.left_amount_list.clone(),
+            amount_precision: config.amount_precision,
+            limit_type: config.limit_type.clone(),
+            limit_count: config.limit_count,
+            limit_count_list: config.limit_count_list,
+            gas_price_type: config.gas_price_type.clone(),
+            gas_price: config.gas_price,
+            gas_price_rate: config.gas_price_rate,
+            max_gas_price: config.max_gas_price,
+            fee_tier: config.fee_tier.clone(),
+            error_retry: config.error_retry.clone(),
+            error_count_limit: config.error_count_limit,
+            window_id: config.window_id.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenInfo {
     pub symbol: String,
@@ -56,25 +89,7 @@ impl TokenTransferUtils {
         to_address: Address,
         transfer_amount: U256,
     ) -> Result<U256, Box<dyn std::error::Error>> {
-        let transfer_config = TransferConfig {
-            chain: config.chain.clone(),
-            delay: config.delay,
-            transfer_type: config.transfer_type.clone(),
-            transfer_amount: config.transfer_amount,
-            transfer_amount_list: config.transfer_amount_list,
-            left_amount_list: config.left_amount_list,
-            amount_precision: config.amount_precision,
-            limit_type: config.limit_type.clone(),
-            limit_count: config.limit_count,
-            limit_count_list: config.limit_count_list,
-            gas_price_type: config.gas_price_type.clone(),
-            gas_price: config.gas_price,
-            gas_price_rate: config.gas_price_rate,
-            max_gas_price: config.max_gas_price,
-            error_retry: config.error_retry.clone(),
-            error_count_limit: config.error_count_limit,
-            window_id: config.window_id.clone(),
-        };
+        let transfer_config = TransferConfig::from(config);
         
         TransferUtils::get_gas_limit(&transfer_config, provider, wallet_address, to_address, transfer_amount).await
             .map_err(|e| format!("获取代币合约Gas Limit失败: {e}").into())
@@ -206,8 +221,7 @@ async fn token_transfer_internal<R: tauri::Runtime>(
     if item.to_addr.trim().is_empty() {
         return Err("目标地址不能为空".into());
     }
-    let to_address: Address = item.to_addr.parse()
-        .map_err(|e| format!("目标地址格式错误: {e}"))?;
+    let to_address = resolve_recipient(&config.chain, config.window_id.as_deref(), &item.to_addr).await?;
     
     let contract_address: Address = config.contract_address.parse()
         .map_err(|e| format!("合约地址格式错误: {e}"))?;
@@ -227,39 +241,18 @@ async fn token_transfer_internal<R: tauri::Runtime>(
     let balance = TokenTransferUtils::get_token_balance(&provider, contract_address, wallet_address).await
         .map_err(|e| format!("获取代币余额失败 (RPC: {rpc_url}): {e}"))?;
     
-    let decimals = if let Ok((_, d)) = TokenTransferUtils::get_token_info(&provider, contract_address).await {
-        d
-    } else {
-        18
-    };
-    
-    let balance_wei = u256_to_f64(balance);
-    let balance_tokens = balance_wei / 10f64.powi(decimals as i32);
-    println!("序号：{index}, 当前代币余额为: {balance_tokens} ({balance} wei)");
+    // 金额换算依赖真实精度，查询失败时不能假定为18位
+    let (_symbol, decimals) = TokenTransferUtils::get_token_info(&provider, contract_address).await
+        .map_err(|e| format!("获取代币信息失败 (RPC: {rpc_url}): {e}"))?;
+
+    println!("序号：{}, 当前代币余额为: {} ({} wei)", index, format_units(balance, decimals), balance);
     
-    let gas_price = TransferUtils::get_gas_price(
-        &TransferConfig {
-            chain: config.chain.clone(),
-            delay: config.delay,
-            transfer_type: config.transfer_type.clone(),
-            transfer_amount: config.transfer_amount,
-            transfer_amount_list: config.transfer_amount_list,
-            left_amount_list: config.left_amount_list,
-            amount_precision: config.amount_precision,
-            limit_type: config.limit_type.clone(),
-            limit_count: config.limit_count,
-            limit_count_list: config.limit_count_list,
-            gas_price_type: config.gas_price_type.clone(),
-            gas_price: config.gas_price,
-            gas_price_rate: config.gas_price_rate,
-            max_gas_price: config.max_gas_price,
-            error_retry: config.error_retry.clone(),
-            error_count_limit: config.error_count_limit,
-            window_id: config.window_id.clone(),
-        },
+    let gas_fee = TransferUtils::get_gas_fee(
+        &TransferConfig::from(&config),
         provider.clone()
     ).await
         .map_err(|e| format!("获取Gas Price失败 (RPC: {rpc_url}): {e}"))?;
+    let gas_price = gas_fee.gas_price;
     
     if gas_price.is_zero() {
         return Err("获取到的 gas price 为0".into());
@@ -268,46 +261,22 @@ async fn token_transfer_internal<R: tauri::Runtime>(
     let transfer_amount = match config.transfer_type.as_str() {
         "1" => balance,
         "2" => {
-            let amount = parse_ether_to_wei_f64(config.transfer_amount)?
-                * U256::from(10u64.pow(decimals as u32 - 18));
+            let amount = fixed_transfer_units(&config.transfer_amount, decimals)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
             amount
         }
         "3" => {
-            let mut rng = rand::thread_rng();
-            let random_amount = rng.gen_range(config.transfer_amount_list[0]..=config.transfer_amount_list[1]);
-            let formatted_amount = format!("{:.precision$}", random_amount, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            let amount = parse_ether_to_wei_f64(precise_amount)?
-                * U256::from(10u64.pow(decimals as u32 - 18));
+            let amount = random_transfer_units(&config.transfer_amount_list, decimals, config.amount_precision)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
             amount
         }
         "4" => {
-            let balance_ether = balance_wei / 10f64.powi(decimals as i32);
-            let gas_fee_wei = u256_to_f64(gas_price * U256::from(65000));
-            let gas_fee_ether = gas_fee_wei / 1e18;
-            let available_balance = balance_ether - gas_fee_ether;
-            
-            if available_balance <= config.left_amount_list[1] {
-                return Err(format!("当前可用余额为：{}，无法满足最大剩余数量 {} 要求", available_balance, config.left_amount_list[1]).into());
-            }
-            
-            let mut rng = rand::thread_rng();
-            let left_amount = rng.gen_range(config.left_amount_list[0]..=config.left_amount_list[1]);
-            let transfer_amount_f64 = available_balance - left_amount;
-            
-            if transfer_amount_f64 <= 0.0 {
-                return Err("计算转账金额为负数或零，不做转账操作！".into());
-            }
-            
-            let formatted_amount = format!("{:.precision$}", transfer_amount_f64, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            parse_ether_to_wei_f64(precise_amount)? * U256::from(10u64.pow(decimals as u32 - 18))
+            // Gas 以原生币支付，代币余额全部可用
+            remaining_transfer_units(balance, &config.left_amount_list, decimals, config.amount_precision)?.0
         }
         _ => return Err("无效的转账类型".into()),
     };
@@ -316,7 +285,7 @@ async fn token_transfer_internal<R: tauri::Runtime>(
     let actual_gas_fee = gas_price * gas_limit;
     
     println!("序号：{}, 转账数量为: {}, gas_limit: {}, gas_price: {} gwei, gas_fee: {} ETH", 
-        index, transfer_amount, gas_limit, format_wei_to_gwei(gas_price), format_wei_to_ether(actual_gas_fee));
+        index, format_units(transfer_amount, decimals), gas_limit, format_wei_to_gwei(gas_price), format_wei_to_ether(actual_gas_fee));
     
     let _ = app_handle.emit("transfer_status_update", serde_json::json!({
         "index": index - 1,
@@ -329,14 +298,14 @@ async fn token_transfer_internal<R: tauri::Runtime>(
     let amount_param = format!("{:0>64}", format!("{:x}", transfer_amount));
     let data = format!("0x{method_id}{to_param}{amount_param}");
     
-    let tx = TransactionRequest {
+    let mut tx = TransactionRequest {
         to: Some(TxKind::Call(contract_address)),
         input: data.parse::<alloy_primitives::Bytes>().unwrap().into(),
         value: Some(U256::from(0)),
         gas: Some(gas_limit.to::<u64>()),
-        gas_price: Some(gas_price.to::<u128>()),
         ..Default::default()
     };
+    gas_fee.apply_to(&mut tx);
     
     // 再次检查停止状态 - 在发送交易之前 (最关键的拦截点)
     if !window_id.is_empty() && get_stop_flag(window_id) {
@@ -384,9 +353,7 @@ pub async fn query_token_balance(
         18
     };
     
-    let balance_wei = u256_to_f64(balance);
-    let balance_tokens = balance_wei / 10f64.powi(decimals as i32);
-    Ok(format!("{balance_tokens:.6}"))
+    Ok(format_units(balance, decimals))
 }
 
 #[tauri::command]
@@ -409,13 +376,10 @@ pub async fn get_token_info(
     let balance = TokenTransferUtils::get_token_balance(&provider, contract_addr, wallet_address).await
         .map_err(|e| e.to_string())?;
     
-    let balance_wei = u256_to_f64(balance);
-    let balance_tokens = balance_wei / 10f64.powi(decimals as i32);
-    
     Ok(TokenInfo {
         symbol,
         decimals,
-        balance: format!("{balance_tokens:.6}"),
+        balance: format_units(balance, decimals),
     })
 }
 
@@ -423,9 +387,15 @@ pub async fn get_token_info(
 pub async fn token_transfer<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
-    item: TransferItem,
+    mut item: TransferItem,
     config: TokenTransferConfig,
+    to_group: Option<String>,
 ) -> Result<TransferResult, String> {
+    match group_recipient(&config.chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
     TokenTransferUtils::token_transfer(app_handle, index, item, config).await
 }
 
@@ -433,10 +403,16 @@ pub async fn token_transfer<R: tauri::Runtime>(
 pub async fn token_transfer_fast<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
-    item: TransferItem,
+    mut item: TransferItem,
     config: TokenTransferConfig,
+    to_group: Option<String>,
 ) -> Result<FastTransferResult, String> {
-    match token_transfer_fast_internal(app_handle, index, item, config).await {
+    match group_recipient(&config.chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(FastTransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
+    match token_transfer_fast_internal(app_handle, index, item, config, None).await {
         Ok(tx_hash) => Ok(FastTransferResult {
             success: true,
             tx_hash: Some(tx_hash),
@@ -450,11 +426,13 @@ pub async fn token_transfer_fast<R: tauri::Runtime>(
     }
 }
 
-async fn token_transfer_fast_internal<R: tauri::Runtime>(
+// before_broadcast 在交易签名后、广播前调用，可用于先记录交易哈希
+pub(crate) async fn token_transfer_fast_internal<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
     mut item: TransferItem,
     config: TokenTransferConfig,
+    before_broadcast: Option<BeforeBroadcast<'_>>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     item.retry_flag = false;
     let window_id = config.window_id.as_deref().unwrap_or("");
@@ -504,8 +482,7 @@ async fn token_transfer_fast_internal<R: tauri::Runtime>(
     if item.to_addr.trim().is_empty() {
         return Err("目标地址不能为空".into());
     }
-    let to_address: Address = item.to_addr.parse()
-        .map_err(|e| format!("目标地址格式错误: {e}"))?;
+    let to_address = resolve_recipient(&config.chain, config.window_id.as_deref(), &item.to_addr).await?;
     
     let contract_address: Address = config.contract_address.parse()
         .map_err(|e| format!("代币合约地址格式错误: {e}"))?;
@@ -521,33 +498,14 @@ async fn token_transfer_fast_internal<R: tauri::Runtime>(
     let balance = TokenTransferUtils::get_token_balance(&provider, contract_address, wallet_address).await
         .map_err(|e| format!("获取钱包代币余额失败: {e}"))?;
     
-    let balance_wei = u256_to_f64(balance);
-
     // 检查停止状态
     if !window_id.is_empty() && get_stop_flag(window_id) {
         return Err("用户已停止转账任务".into());
     }
 
-    let gas_price = TransferUtils::get_gas_price(&TransferConfig {
-        chain: config.chain.clone(),
-        delay: config.delay,
-        transfer_type: config.transfer_type.clone(),
-        transfer_amount: config.transfer_amount,
-        transfer_amount_list: config.transfer_amount_list,
-        left_amount_list: config.left_amount_list,
-        amount_precision: config.amount_precision,
-        limit_type: config.limit_type.clone(),
-        limit_count: config.limit_count,
-        limit_count_list: config.limit_count_list,
-        gas_price_type: config.gas_price_type.clone(),
-        gas_price: config.gas_price,
-        gas_price_rate: config.gas_price_rate,
-        max_gas_price: config.max_gas_price,
-        error_retry: config.error_retry.clone(),
-        error_count_limit: config.error_count_limit,
-        window_id: config.window_id.clone(),
-    }, provider.clone()).await
+    let gas_fee = TransferUtils::get_gas_fee(&TransferConfig::from(&config), provider.clone()).await
         .map_err(|e| format!("获取Gas Price失败: {e}"))?;
+    let gas_price = gas_fee.gas_price;
         
     if gas_price.is_zero() {
         return Err("获取到的 gas price 为0".into());
@@ -557,46 +515,22 @@ async fn token_transfer_fast_internal<R: tauri::Runtime>(
     let transfer_amount = match config.transfer_type.as_str() {
         "1" => balance,
         "2" => {
-            let amount = parse_ether_to_wei_f64(config.transfer_amount)?
-                * U256::from(10u64.pow(decimals as u32 - 18));
+            let amount = fixed_transfer_units(&config.transfer_amount, decimals)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
             amount
         }
         "3" => {
-            let mut rng = rand::thread_rng();
-            let random_amount = rng.gen_range(config.transfer_amount_list[0]..=config.transfer_amount_list[1]);
-            let formatted_amount = format!("{:.precision$}", random_amount, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            let amount = parse_ether_to_wei_f64(precise_amount)?
-                * U256::from(10u64.pow(decimals as u32 - 18));
+            let amount = random_transfer_units(&config.transfer_amount_list, decimals, config.amount_precision)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
             amount
         }
         "4" => {
-            let balance_ether = balance_wei / 10f64.powi(decimals as i32);
-            let gas_fee_wei = u256_to_f64(gas_price * U256::from(65000));
-            let gas_fee_ether = gas_fee_wei / 1e18;
-            let available_balance = balance_ether - gas_fee_ether;
-            
-            if available_balance <= config.left_amount_list[1] {
-                return Err(format!("当前可用余额为：{}，无法满足最大剩余数量 {} 要求", available_balance, config.left_amount_list[1]).into());
-            }
-            
-            let mut rng = rand::thread_rng();
-            let left_amount = rng.gen_range(config.left_amount_list[0]..=config.left_amount_list[1]);
-            let transfer_amount_f64 = available_balance - left_amount;
-            
-            if transfer_amount_f64 <= 0.0 {
-                return Err("计算转账金额为负数或零，不做转账操作！".into());
-            }
-            
-            let formatted_amount = format!("{:.precision$}", transfer_amount_f64, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            parse_ether_to_wei_f64(precise_amount)? * U256::from(10u64.pow(decimals as u32 - 18))
+            // Gas 以原生币支付，代币余额全部可用
+            remaining_transfer_units(balance, &config.left_amount_list, decimals, config.amount_precision)?.0
         }
         _ => return Err("无效的转账类型".into()),
     };
@@ -619,25 +553,29 @@ async fn token_transfer_fast_internal<R: tauri::Runtime>(
     let amount_param = format!("{:0>64}", format!("{:x}", transfer_amount));
     let data = format!("0x{method_id}{to_param}{amount_param}");
     
-    let tx = TransactionRequest {
+    let mut tx = TransactionRequest {
         to: Some(TxKind::Call(contract_address)),
         input: data.parse::<alloy_primitives::Bytes>().unwrap().into(),
         value: Some(U256::from(0)),
         gas: Some(gas_limit.to::<u64>()),
-        gas_price: Some(gas_price.to::<u128>()),
         ..Default::default()
     };
+    gas_fee.apply_to(&mut tx);
     
     // 再次检查停止状态 - 在发送交易之前 (最关键的拦截点)
     if !window_id.is_empty() && get_stop_flag(window_id) {
         return Err("用户已停止转账任务".into());
     }
 
-    let signer_provider = create_signer_provider(&config.chain, config.window_id.as_deref(), &signer).await?;
-    let pending_tx = signer_provider.send_transaction(tx).await
-        .map_err(|e| format!("发送交易失败: {e}"))?;
+    // 本地分配nonce，避免同一钱包并发发送时取到相同的pending nonce
+    let nonce_lease = reserve_nonce(&config.chain, wallet_address, provider.as_ref()).await?;
+    report_nonce_gap(&app_handle, &nonce_lease);
+    tx.nonce = Some(nonce_lease.nonce());
+
+    // 本地签名后广播，节点明确拒绝时nonce自动归还，结果不明时按已广播占用
+    let (raw_tx, tx_hash) = sign_transaction(&signer, tx, &nonce_lease).await?;
+    broadcast_signed(provider.as_ref(), &raw_tx, tx_hash, nonce_lease, before_broadcast).await?;
     
-    let tx_hash = *pending_tx.tx_hash();
     let tx_hash_str = format!("{tx_hash:?}");
     
     println!("[狂暴模式] 序号：{index}, 交易已提交，hash: {tx_hash_str}");
@@ -3,19 +3,29 @@ use tauri::Emitter;
 use alloy_provider::{Provider, RootProvider};
 use alloy_transport_http::{Http, Client as AlloyClient};
 use alloy::rpc::client::RpcClient;
-use alloy::consensus::Transaction as _;
 use alloy_primitives::{Address, U256};
-use alloy_rpc_types_eth::{TransactionRequest, BlockNumberOrTag};
+use alloy_rpc_types_eth::{TransactionRequest, BlockNumberOrTag, FeeHistory};
 use alloy_signer_local::{PrivateKeySigner};
 use alloy_signer::Signer;
 use url::Url;
 use std::sync::Arc;
 use rand::Rng;
 use crate::database::get_database_manager;
+use crate::wallets_tool::address_book::group_recipient;
 use crate::wallets_tool::ecosystems::ethereum::provider::{ProviderUtils, create_provider_with_client, create_http_client_with_proxy, AlloyProvider};
 use crate::wallets_tool::security::SecureMemory;
 use sqlx::Row;
-use super::alloy_utils::{parse_ether_to_wei_f64, parse_gwei_to_wei, format_wei_to_ether, format_wei_to_gwei};
+use super::alloy_utils::{
+    DecimalAmount, Rounding, parse_ether_to_wei_f64, parse_gwei_to_wei, format_wei_to_ether, format_wei_to_gwei,
+    format_units, parse_units, random_units_in_range, round_units,
+};
+use super::nonce_manager::{BeforeBroadcast, broadcast_signed, reserve_nonce, report_nonce_gap, sign_transaction};
+use super::tx_replacement::check_replaced_status;
+use super::tx_tracker::{ConfirmationTracker, emit_reorg_events};
+use super::rpc_batch::get_transaction_receipts;
+use super::historical::{HistoricalBlock, resolve_historical_block};
+use super::ens::resolve_recipient;
+use super::recent_transfer::{check_native_transfers, check_token_transfers, record_native_transfer, start_block, window_start};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::collections::HashMap;
 use std::sync::{Mutex, LazyLock};
@@ -69,17 +79,19 @@ pub struct TransferConfig {
     pub chain: String,
     pub delay: [u64; 2],
     pub transfer_type: String, // "1", "2", "3", "4"
-    pub transfer_amount: f64,
-    pub transfer_amount_list: [f64; 2],
-    pub left_amount_list: [f64; 2],
+    pub transfer_amount: DecimalAmount,
+    pub transfer_amount_list: [DecimalAmount; 2],
+    pub left_amount_list: [DecimalAmount; 2],
     pub amount_precision: u8,
     pub limit_type: String, // "1", "2", "3"
     pub limit_count: u64,
     pub limit_count_list: [u64; 2],
-    pub gas_price_type: String, // "1", "2", "3"
+    pub gas_price_type: String, // "1", "2", "3", "4"(EIP-1559)
     pub gas_price: f64,
     pub gas_price_rate: f64,
     pub max_gas_price: f64,
+    #[serde(default)]
+    pub fee_tier: String, // "slow", "normal", "fast"，仅 gas_price_type 为 "4" 时生效
     pub error_retry: String,
     pub error_count_limit: u32,
     #[serde(default)]
@@ -465,6 +477,132 @@ fn validate_estimated_gas(estimated: U256, chain_id: u64, default_min: u64) -> U
     estimated
 }
 
+// eth_feeHistory 采样的区块数与奖励百分位（分别对应 slow / normal / fast）
+const FEE_HISTORY_BLOCKS: u64 = 10;
+const FEE_HISTORY_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+// 原生币精度（wei）
+pub(crate) const NATIVE_DECIMALS: u8 = 18;
+
+// 固定数量模式：按代币精度精确转换，超出精度的小数位舍去
+pub(crate) fn fixed_transfer_units(amount: &DecimalAmount, decimals: u8) -> Result<U256, String> {
+    amount.to_units(decimals, Rounding::Down)
+}
+
+// 随机数量模式：在区间内取恰好 precision 位小数的金额
+pub(crate) fn random_transfer_units(range: &[DecimalAmount; 2], decimals: u8, precision: u8) -> Result<U256, String> {
+    let min = range[0].to_units(decimals, Rounding::Up)?;
+    let max = range[1].to_units(decimals, Rounding::Down)?;
+    random_units_in_range(min, max, decimals, precision)
+}
+
+// 剩余随机数量模式：从可用余额中随机保留一部分，转账金额按精度向下取整，
+// 因此实际剩余不会少于随机出的保留量。返回 (转账金额, 实际剩余)
+pub(crate) fn remaining_transfer_units(
+    available: U256,
+    left_range: &[DecimalAmount; 2],
+    decimals: u8,
+    precision: u8,
+) -> Result<(U256, U256), String> {
+    let min_left = left_range[0].to_units(decimals, Rounding::Up)?;
+    let max_left = left_range[1].to_units(decimals, Rounding::Up)?;
+    if available <= max_left {
+        return Err(format!(
+            "当前可用余额为：{}，无法满足最大剩余数量 {} 要求，不做转账操作！",
+            format_units(available, decimals),
+            left_range[1]
+        ));
+    }
+    let left = random_units_in_range(min_left, max_left, decimals, decimals)?;
+    let amount = round_units(available - left, decimals, precision, Rounding::Down);
+    if amount.is_zero() {
+        return Err(format!(
+            "计算转账金额为零：可用余额 {} - 剩余数量 {} 按 {} 位小数取整后为0，不做转账操作！",
+            format_units(available, decimals),
+            format_units(left, decimals),
+            precision
+        ));
+    }
+    Ok((amount, available - amount))
+}
+
+/// EIP-1559 费用参数（单位：wei）
+#[derive(Debug, Clone, Copy)]
+pub struct Eip1559Fees {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+/// 交易费用：传统 gas price 或 EIP-1559 参数
+#[derive(Debug, Clone, Copy)]
+pub struct GasFee {
+    // 每单位gas可能支付的最高价格，用于余额与手续费计算
+    pub gas_price: U256,
+    pub eip1559: Option<Eip1559Fees>,
+}
+
+impl GasFee {
+    /// 将费用写入交易：EIP-1559 模式发送 type-2 交易，否则使用传统 gasPrice
+    pub fn apply_to(&self, tx: &mut TransactionRequest) {
+        match self.eip1559 {
+            Some(fees) => {
+                tx.gas_price = None;
+                tx.max_fee_per_gas = Some(fees.max_fee_per_gas);
+                tx.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+            }
+            None => {
+                tx.gas_price = Some(self.gas_price.to::<u128>());
+            }
+        }
+    }
+}
+
+// 根据档位返回 (奖励百分位下标, baseFee上浮百分比)
+// baseFee 每个满块最多上涨12.5%，上浮比例决定交易能承受几个区块的涨幅
+fn fee_tier_params(tier: &str) -> (usize, u128) {
+    match tier {
+        "slow" => (0, 110),
+        "fast" => (2, 200),
+        _ => (1, 125),
+    }
+}
+
+// 由 feeHistory 计算 EIP-1559 费用，cap 为 maxFeePerGas 上限（wei）
+fn compute_eip1559_fees(history: &FeeHistory, tier: &str, cap: Option<u128>) -> Result<Eip1559Fees, String> {
+    let next_base_fee = history.next_block_base_fee().unwrap_or(0);
+    if next_base_fee == 0 {
+        return Err("当前链未返回baseFee，无法使用EIP-1559模式，请选择其他Gas Price方式".to_string());
+    }
+
+    let (percentile_index, base_fee_multiplier) = fee_tier_params(tier);
+
+    // 取采样区块中对应百分位奖励的中位数，避免单个区块异常值
+    let mut rewards: Vec<u128> = history
+        .reward
+        .as_ref()
+        .map(|blocks| blocks.iter().filter_map(|r| r.get(percentile_index).copied()).collect())
+        .unwrap_or_default();
+    rewards.sort_unstable();
+    let priority_fee = rewards.get(rewards.len() / 2).copied().unwrap_or(0);
+
+    let mut max_fee = next_base_fee * base_fee_multiplier / 100 + priority_fee;
+    if let Some(cap) = cap {
+        if next_base_fee > cap {
+            return Err(format!(
+                "当前baseFee ({} gwei) 超出最大值限制 ({} gwei)",
+                format_wei_to_gwei(U256::from(next_base_fee)),
+                format_wei_to_gwei(U256::from(cap))
+            ));
+        }
+        max_fee = max_fee.min(cap);
+    }
+
+    Ok(Eip1559Fees {
+        max_fee_per_gas: max_fee,
+        max_priority_fee_per_gas: priority_fee.min(max_fee),
+    })
+}
+
 // 转账工具函数
 pub struct TransferUtils;
 
@@ -565,7 +703,8 @@ impl TransferUtils {
         }
         
         // 获取Gas Price进行预估
-        let gas_price = Self::get_gas_price(config, provider.clone()).await?;
+        // EIP-1559 模式下为 maxFeePerGas，即每单位gas可能支付的最高价格
+        let gas_price = Self::get_gas_fee(config, provider.clone()).await?.gas_price;
         
         if gas_price.is_zero() {
             return Err("获取到的 gas price 为0，请检查网络连接或RPC配置".into());
@@ -588,7 +727,7 @@ impl TransferUtils {
             }
             "2" => {
                 // 转账固定数量
-                let transfer_amount = parse_ether_to_wei_f64(config.transfer_amount)?;
+                let transfer_amount = fixed_transfer_units(&config.transfer_amount, NATIVE_DECIMALS)?;
                 let estimated_gas_limit = Self::get_gas_limit(config, provider.clone(), wallet_address, to_address, parse_ether_to_wei_f64(0.000003)?).await?;
                 let estimated_gas_fee = gas_price * estimated_gas_limit;
                 let total_needed = transfer_amount + estimated_gas_fee;
@@ -605,7 +744,7 @@ format_wei_to_ether(total_needed),
             }
             "3" => {
                 // 转账随机数量 - 使用最大可能金额进行检查
-                let max_transfer_amount = parse_ether_to_wei_f64(config.transfer_amount_list[1])?;
+                let max_transfer_amount = config.transfer_amount_list[1].to_units(NATIVE_DECIMALS, Rounding::Down)?;
                 let estimated_gas_limit =Self::get_gas_limit(config, provider.clone(), wallet_address, to_address, parse_ether_to_wei_f64(0.000003)?).await?;
                 let estimated_gas_fee = gas_price * estimated_gas_limit;
                 let total_needed = max_transfer_amount + estimated_gas_fee;
@@ -636,14 +775,16 @@ format_wei_to_ether(total_needed),
                     _ => U256::from(21000), // 默认ETH转账gas limit
                 };
                 let estimated_gas_fee = gas_price * estimated_gas_limit;
-                let balance_ether = format_wei_to_ether(balance).parse::<f64>()?;
-                let gas_fee_ether = format_wei_to_ether(estimated_gas_fee).parse::<f64>()?;
-                let available_balance = balance_ether - gas_fee_ether;
-                
-                if available_balance <= config.left_amount_list[1] {
+                let available_balance = balance.saturating_sub(estimated_gas_fee);
+                let max_left_amount = config.left_amount_list[1].to_units(NATIVE_DECIMALS, Rounding::Up)?;
+
+                if available_balance <= max_left_amount {
                     return Err(format!(
                         "余额不足！可用余额: {} ETH (总余额: {} - Gas: {} ETH)，无法满足最大剩余数量 {} ETH 要求",
-                        available_balance, balance_ether, gas_fee_ether, config.left_amount_list[1]
+                        format_units(available_balance, NATIVE_DECIMALS),
+                        format_units(balance, NATIVE_DECIMALS),
+                        format_units(estimated_gas_fee, NATIVE_DECIMALS),
+                        config.left_amount_list[1]
                     ).into());
                 }
             }
@@ -653,7 +794,52 @@ format_wei_to_ether(total_needed),
         Ok(())
     }
 
-    // 获取Gas Price
+    // 获取EIP-1559费用（基于 eth_feeHistory，适用于任何返回baseFee的链）
+    pub async fn get_eip1559_fees(
+        config: &TransferConfig,
+        provider: Arc<AlloyProvider>,
+    ) -> Result<Eip1559Fees, Box<dyn std::error::Error>> {
+        let history = provider
+            .get_fee_history(FEE_HISTORY_BLOCKS, BlockNumberOrTag::Latest, &FEE_HISTORY_PERCENTILES)
+            .await
+            .map_err(|e| format!("获取feeHistory失败: {e}"))?;
+
+        let cap = if config.max_gas_price > 0.0 {
+            Some(parse_gwei_to_wei(config.max_gas_price).to::<u128>())
+        } else {
+            None
+        };
+        let fees = compute_eip1559_fees(&history, &config.fee_tier, cap)?;
+
+        println!("[DEBUG] EIP-1559费用 ({}): maxFeePerGas {} gwei, maxPriorityFeePerGas {} gwei",
+            if config.fee_tier.is_empty() { "normal" } else { config.fee_tier.as_str() },
+            format_wei_to_gwei(U256::from(fees.max_fee_per_gas)),
+            format_wei_to_gwei(U256::from(fees.max_priority_fee_per_gas))
+        );
+
+        Ok(fees)
+    }
+
+    // 获取交易费用（EIP-1559 模式返回 type-2 参数，其余模式返回传统 gas price）
+    pub async fn get_gas_fee(
+        config: &TransferConfig,
+        provider: Arc<AlloyProvider>,
+    ) -> Result<GasFee, Box<dyn std::error::Error>> {
+        if config.gas_price_type == "4" {
+            let fees = Self::get_eip1559_fees(config, provider).await?;
+            return Ok(GasFee {
+                gas_price: U256::from(fees.max_fee_per_gas),
+                eip1559: Some(fees),
+            });
+        }
+
+        Ok(GasFee {
+            gas_price: Self::get_gas_price(config, provider).await?,
+            eip1559: None,
+        })
+    }
+
+    // 获取传统模式的Gas Price（EIP-1559 模式由 get_gas_fee 处理）
     pub async fn get_gas_price(
         config: &TransferConfig,
         provider: Arc<AlloyProvider>,
@@ -1001,9 +1187,16 @@ format_wei_to_ether(total_needed),
 pub async fn base_coin_transfer<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
-    item: TransferItem,
+    mut item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
 ) -> Result<TransferResult, String> {
+    // 指定了地址分组时，第 index 行转给分组中的第 index 个地址
+    match group_recipient(&config.chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
     match base_coin_transfer_internal(app_handle, index, item, config).await {
         Ok(tx_hash) => Ok(TransferResult {
             success: true,
@@ -1072,9 +1265,7 @@ async fn base_coin_transfer_internal<R: tauri::Runtime>(
     if item.to_addr.trim().is_empty() {
         return Err("目标地址不能为空，请先导入接收地址！".into());
     }
-    let to_address: Address = item.to_addr.parse().map_err(|e| {
-        format!("目标地址格式错误: {e}，请检查地址格式是否正确")
-    })?;
+    let to_address = resolve_recipient(&config.chain, config.window_id.as_deref(), &item.to_addr).await?;
     
     // 获取当前使用的RPC URL用于错误信息
     let rpc_url = if let Some(rpc_config) = get_rpc_config(&config.chain).await {
@@ -1115,9 +1306,10 @@ async fn base_coin_transfer_internal<R: tauri::Runtime>(
         format!("获取RPC提供商失败: {e}")
     })?;
     
-    let gas_price = TransferUtils::get_gas_price(&config, provider_for_gas_price.clone()).await.map_err(|e| {
+    let gas_fee = TransferUtils::get_gas_fee(&config, provider_for_gas_price.clone()).await.map_err(|e| {
         format!("获取Gas Price失败 (RPC: {rpc_url}): {e}")
     })?;
+    let gas_price = gas_fee.gas_price;
     
 
     
@@ -1297,20 +1489,15 @@ async fn base_coin_transfer_internal<R: tauri::Runtime>(
         }
         "2" => {
             // 转账固定数量
-            let amount = parse_ether_to_wei_f64(config.transfer_amount)?;
+            let amount = fixed_transfer_units(&config.transfer_amount, NATIVE_DECIMALS)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
             amount
         }
         "3" => {
-            // 转账随机数量
-            let mut rng = rand::thread_rng();
-            let random_amount = rng.gen_range(config.transfer_amount_list[0]..=config.transfer_amount_list[1]);
-            // 根据精度设置格式化随机金额
-            let formatted_amount = format!("{:.precision$}", random_amount, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            let amount = parse_ether_to_wei_f64(precise_amount)?;
+            // 转账随机数量（按精度取值）
+            let amount = random_transfer_units(&config.transfer_amount_list, NATIVE_DECIMALS, config.amount_precision)?;
             if amount >= balance {
                 return Err("当前余额不足，不做转账操作！".into());
             }
@@ -1318,69 +1505,46 @@ async fn base_coin_transfer_internal<R: tauri::Runtime>(
         }
         "4" => {
             // 剩余随机数量
-            // 使用format_wei_to_ether替代format_ether，确保精度不丢失
-            let balance_ether_str = format_wei_to_ether(balance);
-            let balance_f64: f64 = balance_ether_str.parse::<f64>().map_err(|e| {
-                format!("余额转换失败: {e}, 原始余额: {balance}, format_wei_to_ether结果: {balance_ether_str}")
-            })?;
-            
-
-            
             // 使用之前已获取的gas_limit
-            
             let gas_fee = gas_price * gas_limit;
-            let gas_fee_ether_str = format_wei_to_ether(gas_fee);
-            let gas_fee_ether: f64 = gas_fee_ether_str.parse::<f64>().map_err(|e| {
-                format!("gas费用转换失败: {e}, gas_fee: {gas_fee}, format_wei_to_ether结果: {gas_fee_ether_str}")
-            })?;
-            
 
-            
             // 可用于转账的余额 = 总余额 - Gas费用
-            let available_balance = balance_f64 - gas_fee_ether;
-            
-            println!("序号：{index}, 总余额: {balance_f64}, Gas费用: {gas_fee_ether}, 可用余额: {available_balance}");
-            
-            // 检查可用余额是否足够满足最小剩余数量要求
-            if available_balance <= config.left_amount_list[1] {
-                return Err(format!(
-                    "当前可用余额为：{} (总余额: {} - Gas费用: {})，无法满足最大剩余数量 {} 要求，不做转账操作！",
-                    available_balance, balance_f64, gas_fee_ether, config.left_amount_list[1]
-                ).into());
-            }
-            
-            let mut rng = rand::thread_rng();
-            let left_amount = rng.gen_range(config.left_amount_list[0]..=config.left_amount_list[1]);
-            let transfer_amount_f64 = available_balance - left_amount;
-            
-            if transfer_amount_f64 <= 0.0 {
-                return Err(format!(
-                    "计算转账金额为负数或零：可用余额 {available_balance} - 剩余数量 {left_amount} = {transfer_amount_f64}，不做转账操作！"
-                ).into());
-            }
-            
-            // 根据精度设置格式化转账金额
-            let formatted_amount = format!("{:.precision$}", transfer_amount_f64, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            
-            println!("序号：{index}, 剩余数量: {left_amount}, 转账金额: {transfer_amount_f64} (格式化后: {precise_amount})");
-            
-            parse_ether_to_wei_f64(precise_amount)?
+            let available_balance = balance.saturating_sub(gas_fee);
+
+            println!("序号：{}, 总余额: {}, Gas费用: {}, 可用余额: {}", index,
+                format_units(balance, NATIVE_DECIMALS),
+                format_units(gas_fee, NATIVE_DECIMALS),
+                format_units(available_balance, NATIVE_DECIMALS)
+            );
+
+            let (amount, left_amount) = remaining_transfer_units(
+                available_balance,
+                &config.left_amount_list,
+                NATIVE_DECIMALS,
+                config.amount_precision,
+            )?;
+
+            println!("序号：{}, 剩余数量: {}, 转账金额: {}", index,
+                format_units(left_amount, NATIVE_DECIMALS),
+                format_units(amount, NATIVE_DECIMALS)
+            );
+
+            amount
         }
         _ => return Err("无效的转账类型".into()),
     };
     
-    println!("序号：{}, 转账数量为: {}", index, format_wei_to_ether(transfer_amount));
+    println!("序号：{}, 转账数量为: {}", index, format_units(transfer_amount, NATIVE_DECIMALS));
     
     // 构建交易（使用之前已获取的gas_limit）
-    let tx = TransactionRequest {
+    let mut tx = TransactionRequest {
         from: Some(wallet_address),
         to: Some(to_address.into()),
         value: Some(transfer_amount),
-        gas_price: Some(gas_price.to::<u128>()),
         gas: Some(gas_limit.to::<u64>()),
         ..Default::default()
     };
+    gas_fee.apply_to(&mut tx);
     
     // 再次检查停止状态 - 在发送交易之前 (最关键的拦截点)
     if !window_id.is_empty() && get_stop_flag(window_id) {
@@ -1403,6 +1567,7 @@ let signer_provider = create_signer_provider(&config.chain, config.window_id.as_
     
     let tx_hash = *pending_tx.tx_hash();
     println!("序号：{index}, 交易 hash 为：{tx_hash:?}");
+    record_native_transfer(&config.chain, wallet_address, to_address, transfer_amount, None, &format!("{tx_hash:?}")).await;
     
     // 等待交易确认（设置30秒超时）
     item.error_msg = "等待交易结果...".to_string();
@@ -1442,8 +1607,9 @@ let signer_provider = create_signer_provider(&config.chain, config.window_id.as_
 pub async fn query_balance(
     chain: String,
     address: String,
+    block: Option<HistoricalBlock>,
 ) -> Result<String, String> {
-    match query_balance_internal(chain, address).await {
+    match query_balance_internal(chain, address, block).await {
         Ok(balance) => Ok(balance),
         Err(e) => Err(e.to_string()),
     }
@@ -1453,14 +1619,27 @@ pub async fn query_balance(
 async fn query_balance_internal(
     chain: String,
     address: String,
+    block: Option<HistoricalBlock>,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let provider = create_provider(&chain, None).await?;
     if address.trim().is_empty() {
         return Err("查询地址不能为空！".into());
     }
     let address: Address = address.parse().map_err(|e| {
         format!("地址格式错误: {e}，请检查地址格式是否正确")
     })?;
+
+    // 历史余额只向归档节点查询
+    if let Some(block) = block {
+        use crate::wallets_tool::ecosystems::ethereum::proxy_manager::PROXY_MANAGER;
+        let target = resolve_historical_block(&chain, block, None).await?;
+        let (rpc_url, _) = target.random_rpc();
+        let provider = create_provider_with_client(&rpc_url, PROXY_MANAGER.get_random_proxy().as_deref()).await?;
+        let balance = provider.get_balance(address).number(target.block_number).await
+            .map_err(|e| format!("获取区块 {} 的余额失败 (RPC: {rpc_url}): {e}", target.block_number))?;
+        return Ok(format_wei_to_ether(balance));
+    }
+
+    let provider = create_provider(&chain, None).await?;
     let balance = match provider.get_balance(address).await {
         Ok(balance) => balance,
         Err(e) => {
@@ -1493,92 +1672,73 @@ pub struct FastTransferResult {
 
 
 // 交易状态检查结果
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TransactionStatusResult {
     pub confirmed: bool,
     pub success: Option<bool>,  // None表示还在pending，Some(true)表示成功，Some(false)表示失败
     pub error: Option<String>,
+    // 当前确认数与链配置的确认深度，确认数达到深度后 confirmed 才为 true
+    #[serde(default)]
+    pub confirmations: u64,
+    #[serde(default)]
+    pub required_confirmations: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BatchTransactionStatusResult {
     pub hash: String,
     pub status: TransactionStatusResult,
+    // 实际上链的交易哈希（交易被加速/取消时可能与 hash 不同）
+    #[serde(default)]
+    pub mined_hash: Option<String>,
 }
 
 // Tauri命令：批量检查交易状态
 #[tauri::command]
-pub async fn check_transactions_status_batch(
+pub async fn check_transactions_status_batch<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
     chain: String,
     tx_hashes: Vec<String>,
+    window_id: Option<String>,
 ) -> Result<Vec<BatchTransactionStatusResult>, String> {
-    match check_transactions_status_batch_internal(chain, tx_hashes).await {
-        Ok(results) => Ok(results),
-        Err(e) => Err(e.to_string()),
-    }
+    let result = check_transactions_status_batch_internal(chain, tx_hashes, window_id.as_deref()).await;
+    emit_reorg_events(&app_handle);
+    result.map_err(|e| e.to_string())
 }
 
 // 内部批量检查交易状态实现
 async fn check_transactions_status_batch_internal(
     chain: String,
     tx_hashes: Vec<String>,
+    window_id: Option<&str>,
 ) -> Result<Vec<BatchTransactionStatusResult>, Box<dyn std::error::Error>> {
     if tx_hashes.is_empty() {
         return Ok(Vec::new());
     }
 
     // 复用同一个Provider
-    let provider = create_provider(&chain, None).await?;
-    
-    let mut results = Vec::new();
-    
-    // 并发检查还是顺序检查？
-    // 为了避免对RPC造成瞬间过大压力，这里使用顺序检查，因为Provider复用已经减少了很大开销
-    // 如果需要进一步优化，可以使用FuturesUnordered进行并发，但要注意RPC限制
-    
+    let provider = create_provider(&chain, window_id).await?;
+    let mut tracker = ConfirmationTracker::new(provider.as_ref(), &chain).await?;
+
+    let mut results = Vec::with_capacity(tx_hashes.len());
+    // 需要查询回执的交易：(结果下标, 哈希)
+    let mut pending = Vec::new();
+
     for tx_hash in tx_hashes {
-        // 解析交易哈希
-        let hash_res: Result<alloy::primitives::B256, _> = tx_hash.parse();
-        
-        match hash_res {
+        // 被加速/取消过的交易：同时检查替换交易，报告实际上链的那一笔
+        if let Some(result) = check_replaced_status(provider.as_ref(), &mut tracker, &chain, &tx_hash).await {
+            results.push(result);
+            continue;
+        }
+
+        match tx_hash.parse::<alloy::primitives::B256>() {
             Ok(hash) => {
-                // 获取交易回执
-                match provider.get_transaction_receipt(hash).await {
-                    Ok(Some(receipt)) => {
-                        let success = receipt.status();
-                        results.push(BatchTransactionStatusResult {
-                            hash: tx_hash,
-                            status: TransactionStatusResult {
-                                confirmed: true,
-                                success: Some(success),
-                                error: if success { None } else { Some("交易执行失败".to_string()) },
-                            }
-                        });
-                    }
-                    Ok(None) => {
-                        results.push(BatchTransactionStatusResult {
-                            hash: tx_hash,
-                            status: TransactionStatusResult {
-                                confirmed: false,
-                                success: None,
-                                error: None,
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        // 单个失败不影响整体，标记为查询失败（或者视为pending）
-                        // 这里我们返回confirmed=false但带有错误信息，或者直接忽略
-                        // 为了前端好处理，我们返回一个带有错误信息的pending状态
-                         results.push(BatchTransactionStatusResult {
-                            hash: tx_hash,
-                            status: TransactionStatusResult {
-                                confirmed: false,
-                                success: None,
-                                error: Some(format!("查询失败: {e}")),
-                            }
-                        });
-                    }
-                }
+                pending.push((results.len(), hash));
+                results.push(BatchTransactionStatusResult {
+                    hash: tx_hash,
+                    status: TransactionStatusResult::default(),
+                    mined_hash: None,
+                });
             }
             Err(e) => {
                 results.push(BatchTransactionStatusResult {
@@ -1587,12 +1747,34 @@ async fn check_transactions_status_batch_internal(
                         confirmed: false,
                         success: Some(false),
                         error: Some(format!("哈希格式错误: {e}")),
-                    }
+                        ..Default::default()
+                    },
+                    mined_hash: None,
                 });
             }
         }
     }
-    
+
+    // 回执通过JSON-RPC批量请求获取，避免逐个哈希请求
+    let hashes: Vec<_> = pending.iter().map(|(_, hash)| *hash).collect();
+    let receipts = get_transaction_receipts(&chain, window_id, &hashes).await;
+    for ((index, hash), receipt) in pending.into_iter().zip(receipts) {
+        let result = &mut results[index];
+        match receipt {
+            Ok(receipt) => {
+                result.status = tracker.evaluate(provider.as_ref(), hash, receipt.as_ref()).await;
+                result.mined_hash = (result.status.confirmations > 0).then(|| result.hash.clone());
+            }
+            Err(e) => {
+                // 单个失败不影响整体，返回带错误信息的pending状态，便于前端处理
+                result.status = TransactionStatusResult {
+                    error: Some(format!("查询失败: {e}")),
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
     Ok(results)
 }
 
@@ -1601,10 +1783,17 @@ async fn check_transactions_status_batch_internal(
 pub async fn base_coin_transfer_fast<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
-    item: TransferItem,
+    mut item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
 ) -> Result<FastTransferResult, String> {
-    match base_coin_transfer_fast_internal(app_handle, index, item, config).await {
+    // 指定了地址分组时，第 index 行转给分组中的第 index 个地址
+    match group_recipient(&config.chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(FastTransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
+    match base_coin_transfer_fast_internal(app_handle, index, item, config, None).await {
         Ok(tx_hash) => Ok(FastTransferResult {
             success: true,
             tx_hash: Some(tx_hash),
@@ -1619,11 +1808,13 @@ pub async fn base_coin_transfer_fast<R: tauri::Runtime>(
 }
 
 // 内部快速基础币转账实现（只提交交易，不等待确认）
-async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
+// before_broadcast 在交易签名后、广播前调用，可用于先记录交易哈希
+pub(crate) async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     index: usize,
     mut item: TransferItem,
     config: TransferConfig,
+    before_broadcast: Option<BeforeBroadcast<'_>>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     item.retry_flag = false;
     
@@ -1675,9 +1866,7 @@ async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
     if item.to_addr.trim().is_empty() {
         return Err("目标地址不能为空！".into());
     }
-    let to_address: Address = item.to_addr.parse().map_err(|e| {
-        format!("目标地址格式错误: {e}")
-    })?;
+    let to_address = resolve_recipient(&config.chain, config.window_id.as_deref(), &item.to_addr).await?;
     
     // 检查停止状态
     if !window_id.is_empty() && get_stop_flag(window_id) {
@@ -1699,7 +1888,8 @@ async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
     }
 
     // 获取Gas Price (复用Provider)
-    let gas_price = TransferUtils::get_gas_price(&config, provider.clone()).await?;
+    let gas_fee = TransferUtils::get_gas_fee(&config, provider.clone()).await?;
+    let gas_price = gas_fee.gas_price;
     
     if gas_price.is_zero() {
         return Err("获取到的 gas price 为0".into());
@@ -1744,44 +1934,23 @@ async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
             balance - gas_fee - safety_margin
         }
         "2" => {
-            let amount = parse_ether_to_wei_f64(config.transfer_amount)?;
+            let amount = fixed_transfer_units(&config.transfer_amount, NATIVE_DECIMALS)?;
             if amount >= balance {
                 return Err("余额不足".into());
             }
             amount
         }
         "3" => {
-            let mut rng = rand::thread_rng();
-            let random_amount = rng.gen_range(config.transfer_amount_list[0]..=config.transfer_amount_list[1]);
-            let formatted_amount = format!("{:.precision$}", random_amount, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            let amount = parse_ether_to_wei_f64(precise_amount)?;
+            let amount = random_transfer_units(&config.transfer_amount_list, NATIVE_DECIMALS, config.amount_precision)?;
             if amount >= balance {
                 return Err("余额不足".into());
             }
             amount
         }
         "4" => {
-            let balance_f64: f64 = format_wei_to_ether(balance).parse()?;
             let gas_fee = gas_price * gas_limit;
-            let gas_fee_ether: f64 = format_wei_to_ether(gas_fee).parse()?;
-            let available_balance = balance_f64 - gas_fee_ether;
-            
-            if available_balance <= config.left_amount_list[1] {
-                return Err("余额不足".into());
-            }
-            
-            let mut rng = rand::thread_rng();
-            let left_amount = rng.gen_range(config.left_amount_list[0]..=config.left_amount_list[1]);
-            let transfer_amount_f64 = available_balance - left_amount;
-            
-            if transfer_amount_f64 <= 0.0 {
-                return Err("计算转账金额为负数或零".into());
-            }
-            
-            let formatted_amount = format!("{:.precision$}", transfer_amount_f64, precision = config.amount_precision as usize);
-            let precise_amount: f64 = formatted_amount.parse()?;
-            parse_ether_to_wei_f64(precise_amount)?
+            let available_balance = balance.saturating_sub(gas_fee);
+            remaining_transfer_units(available_balance, &config.left_amount_list, NATIVE_DECIMALS, config.amount_precision)?.0
         }
         _ => return Err("无效的转账类型".into()),
     };
@@ -1794,27 +1963,32 @@ async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
     }));
     
     // 构建并发送交易
-    let tx = TransactionRequest {
+    let mut tx = TransactionRequest {
         from: Some(wallet_address),
         to: Some(to_address.into()),
         value: Some(transfer_amount),
-        gas_price: Some(gas_price.to::<u128>()),
         gas: Some(gas_limit.to::<u64>()),
         ..Default::default()
     };
+    gas_fee.apply_to(&mut tx);
     
     // 再次检查停止状态 - 在发送交易之前 (最关键的拦截点)
     if !window_id.is_empty() && get_stop_flag(window_id) {
         return Err("用户已停止转账任务".into());
     }
 
-    let signer_provider = create_signer_provider(&config.chain, config.window_id.as_deref(), &wallet).await?;
-    let pending_tx = signer_provider.send_transaction(tx).await.map_err(|e| {
-        format!("发送交易失败: {e}")
-    })?;
+    // 本地分配nonce，避免同一钱包并发发送时取到相同的pending nonce
+    let nonce_lease = reserve_nonce(&config.chain, wallet_address, provider.as_ref()).await?;
+    report_nonce_gap(&app_handle, &nonce_lease);
+    tx.nonce = Some(nonce_lease.nonce());
+
+    // 本地签名后广播，节点明确拒绝时nonce自动归还，结果不明时按已广播占用
+    let nonce = nonce_lease.nonce();
+    let (raw_tx, tx_hash) = sign_transaction(&wallet, tx, &nonce_lease).await?;
+    broadcast_signed(provider.as_ref(), &raw_tx, tx_hash, nonce_lease, before_broadcast).await?;
     
-    let tx_hash = pending_tx.tx_hash();
     let tx_hash_str = format!("{tx_hash:?}");
+    record_native_transfer(&config.chain, wallet_address, to_address, transfer_amount, Some(nonce), &tx_hash_str).await;
     
     println!("[狂暴模式] 序号：{index}, 交易已提交，hash: {tx_hash_str}");
     
@@ -1831,18 +2005,18 @@ async fn base_coin_transfer_fast_internal<R: tauri::Runtime>(
 
 // Tauri命令：检查交易状态
 #[tauri::command]
-pub async fn check_transaction_status(
+pub async fn check_transaction_status<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
     chain: String,
     tx_hash: String,
 ) -> Result<TransactionStatusResult, String> {
-    match check_transaction_status_internal(chain, tx_hash).await {
-        Ok(result) => Ok(result),
-        Err(e) => Err(e.to_string()),
-    }
+    let result = check_transaction_status_internal(chain, tx_hash).await;
+    emit_reorg_events(&app_handle);
+    result.map_err(|e| e.to_string())
 }
 
 // 内部检查交易状态实现
-async fn check_transaction_status_internal(
+pub(crate) async fn check_transaction_status_internal(
     chain: String,
     tx_hash: String,
 ) -> Result<TransactionStatusResult, Box<dyn std::error::Error>> {
@@ -1853,29 +2027,16 @@ async fn check_transaction_status_internal(
         format!("交易哈希格式错误: {e}")
     })?;
     
-    // 获取交易回执
-    match provider.get_transaction_receipt(hash).await {
-        Ok(Some(receipt)) => {
-            // 交易已确认
-            let success = receipt.status();
-            Ok(TransactionStatusResult {
-                confirmed: true,
-                success: Some(success),
-                error: if success { None } else { Some("交易执行失败".to_string()) },
-            })
-        }
-        Ok(None) => {
-            // 交易还在pending
-            Ok(TransactionStatusResult {
-                confirmed: false,
-                success: None,
-                error: None,
-            })
-        }
-        Err(e) => {
-            Err(format!("查询交易状态失败: {e}").into())
-        }
+    let mut tracker = ConfirmationTracker::new(provider.as_ref(), &chain).await?;
+    // 被加速/取消过的交易按实际上链的那一笔判断
+    if let Some(result) = check_replaced_status(provider.as_ref(), &mut tracker, &chain, &tx_hash).await {
+        return Ok(result.status);
     }
+
+    // 获取交易回执，按确认深度及区块哈希校验判断是否已确认
+    let receipt = provider.get_transaction_receipt(hash).await
+        .map_err(|e| format!("查询交易状态失败: {e}"))?;
+    Ok(tracker.evaluate(provider.as_ref(), hash, receipt.as_ref()).await)
 }
 
 // ========== 原有代码 ==========
@@ -1889,6 +2050,7 @@ pub struct RecentTransferResult {
 }
 
 // Tauri命令：检查钱包最近转账记录
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn check_wallet_recent_transfers(
     chain: String,
@@ -1898,8 +2060,10 @@ pub async fn check_wallet_recent_transfers(
     coin_type: String,
     contract_address: Option<String>,
     amount: Option<String>,
+    lookback_secs: Option<u64>,
+    window_id: Option<String>,
 ) -> Result<RecentTransferResult, String> {
-    match check_wallet_recent_transfers_internal(
+    check_wallet_recent_transfers_internal(
         chain,
         private_key,
         target_address,
@@ -1907,13 +2071,13 @@ pub async fn check_wallet_recent_transfers(
         coin_type,
         contract_address,
         amount,
-    ).await {
-        Ok(result) => Ok(result),
-        Err(e) => Err(e.to_string()),
-    }
+        lookback_secs,
+        window_id.as_deref(),
+    ).await
 }
 
-// 内部检查钱包最近转账记录实现
+// 内部检查钱包最近转账记录实现：代币查询 Transfer 日志，平台币对比本地发送记录与钱包nonce
+#[allow(clippy::too_many_arguments)]
 async fn check_wallet_recent_transfers_internal(
     chain: String,
     private_key: String,
@@ -1922,10 +2086,9 @@ async fn check_wallet_recent_transfers_internal(
     coin_type: String,
     contract_address: Option<String>,
     amount: Option<String>,
-) -> Result<RecentTransferResult, Box<dyn std::error::Error>> {
-    // 创建Provider
-    let provider = create_provider(&chain, None).await?;
-    
+    lookback_secs: Option<u64>,
+    window_id: Option<&str>,
+) -> Result<RecentTransferResult, String> {
     // 处理私钥格式
     let private_key = if private_key.starts_with("0x") || private_key.starts_with("0X") {
         private_key[2..].to_string()
@@ -1943,144 +2106,93 @@ async fn check_wallet_recent_transfers_internal(
     let target_addr: Address = target_address.parse().map_err(|e| {
         format!("目标地址格式错误: {e}")
     })?;
-    
-    // 获取当前区块号
-    let current_block = match provider.get_block_number().await {
-        Ok(block) => block,
-        Err(e) => {
-             // 获取当前使用的RPC URL
-             let error_msg = e.to_string();
-             let rpc_url = if let Some(rpc_config) = get_rpc_config(&chain).await {
-                 match rpc_config.get_random_rpc() {
-                     Ok(url) => url.to_string(),
-                     Err(e) => format!("获取RPC地址失败: {e}")
-                 }
-             } else {
-                 "未知RPC".to_string()
-             };
-             return Err(format!("获取当前区块号失败 (RPC: {rpc_url}): {error_msg}").into());
-         }
-    };
-    
-    // 计算开始查询的区块号（简化实现：从最近1000个区块开始查询）
-    let current_block_u64 = current_block;
-    let start_block = current_block_u64.saturating_sub(1000);
-    
-    // 解析预期的转账金额（如果有）
-    let expected_wei_value = if let Some(amt_str) = &amount {
-        if !amt_str.is_empty() {
-            if let Ok(val) = amt_str.parse::<f64>() {
-                // 仅当币种为base时，我们确定金额单位转换关系（Ether -> Wei）
-                // 对于Token，由于不知道Decimals，暂不进行金额严格校验
-                if coin_type == "base" {
-                    parse_ether_to_wei_f64(val).ok()
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+
+    let since = window_start(start_timestamp, lookback_secs, chrono::Utc::now().timestamp() as u64);
+    let amount = amount.filter(|amount| !amount.trim().is_empty());
+
+    match coin_type.as_str() {
+        "base" => {
+            let expected_wei = amount
+                .as_deref()
+                .and_then(|amount| parse_units(amount.trim(), NATIVE_DECIMALS, Rounding::Down).ok());
+            check_native_transfers(&chain, window_id, wallet_address, target_addr, since, expected_wei).await
         }
-    } else {
-        None
-    };
+        "token" => {
+            let contract: Address = contract_address
+                .as_deref()
+                .ok_or("缺少代币合约地址")?
+                .parse()
+                .map_err(|e| format!("合约地址格式错误: {e}"))?;
+            let from_block = start_block(&chain, window_id, since).await?;
+            check_token_transfers(&chain, window_id, wallet_address, target_addr, contract, from_block, amount.as_deref()).await
+        }
+        _ => Ok(RecentTransferResult {
+            has_recent_transfer: false,
+            transaction_count: 0,
+            latest_transaction_hash: None,
+        }),
+    }
+}
 
-    let mut transaction_count = 0u32;
-    let mut latest_transaction_hash: Option<String> = None;
-    let mut has_recent_transfer = false;
-    
-    // 查询指定区块范围内的交易
-    for block_num in start_block..=current_block_u64 {
-        if let Ok(Some(block)) = provider.get_block_by_number(block_num.into()).full().await {
-            // 检查区块时间戳是否在指定时间之后
-            if block.header.timestamp < start_timestamp {
-                continue;
-            }
-            
-            // 遍历区块中的所有交易
-            let transactions = match block.transactions {
-                alloy_rpc_types_eth::BlockTransactions::Full(txs) => txs,
-                _ => Vec::new(),
-            };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            for tx in transactions {
-                // 检查交易是否来自指定钱包地址
-                let from_address = tx.inner.signer();
-                if from_address == wallet_address {
-                    transaction_count += 1;
-                    
-                    // 根据币种类型检查交易
-                    match coin_type.as_str() {
-                        "base" => {
-                            // 基础币转账：检查to地址
-                            if let Some(to_addr) = tx.inner.to() {
-                                if to_addr == target_addr {
-                                    // 如果设置了金额校验，则需检查金额是否一致
-                                    if let Some(expected_wei) = expected_wei_value {
-                                        // 允许极小的误差（浮点数转换可能导致1wei的差异），或者严格相等
-                                        // 这里使用严格相等，因为parse_ether_to_wei_f64应该是确定性的
-                                        if tx.inner.value() != expected_wei {
-                                            continue;
-                                        }
-                                    }
-                                    
-                                    has_recent_transfer = true;
-                                    latest_transaction_hash = Some(format!("{:?}", tx.inner.hash()));
-                                }
-                            }
-                        }
-                        "token" => {
-                            // 代币转账：检查合约调用和事件日志
-                            if let Some(contract_addr) = &contract_address {
-                                let contract_address: Address = contract_addr.parse().map_err(|e| {
-                                    format!("合约地址格式错误: {e}")
-                                })?;
-                                
-                                // 检查是否是对指定合约的调用
-                                if let Some(to_addr) = tx.inner.to() {
-                                    if to_addr == contract_address {
-                                        // 获取交易回执以检查事件日志
-                                        if let Ok(Some(receipt)) = provider.get_transaction_receipt(*tx.inner.hash()).await {
-                                            // 检查Transfer事件日志
-                                            for log in receipt.inner.logs() {
-                                                // Transfer事件的topic0是keccak256("Transfer(address,address,uint256)")
-                                                let transfer_topic = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
-                                                
-                                                if !log.topics().is_empty() && 
-                                                   format!("{:?}", log.topics()[0]) == transfer_topic &&
-                                                   log.topics().len() >= 3 {
-                                                    // topics[1]是from地址，topics[2]是to地址
-                                                    let to_topic = log.topics()[2];
-                                                    // 将topic转换为地址（去掉前12个字节的0）
-                                                    let to_bytes = &to_topic.as_slice()[12..];
-                                                    let to_address = Address::from_slice(to_bytes);
-                                                    
-                                                    if to_address == target_addr {
-                                                        has_recent_transfer = true;
-                                                        latest_transaction_hash = Some(format!("{:?}", tx.inner.hash()));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => {
-                            // 未知币种类型，跳过
-                            continue;
-                        }
-                    }
-                }
-            }
+    fn fee_history(base_fees: Vec<u128>, rewards: Vec<Vec<u128>>) -> FeeHistory {
+        FeeHistory {
+            base_fee_per_gas: base_fees,
+            reward: Some(rewards),
+            ..Default::default()
         }
     }
-    
-    Ok(RecentTransferResult {
-        has_recent_transfer,
-        transaction_count,
-        latest_transaction_hash,
-    })
-}
\ No newline at end of file
+
+    #[test]
+    fn test_compute_eip1559_fees_tiers() {
+        let history = fee_history(
+            vec![100, 100, 100, 200],
+            vec![vec![1, 5, 9], vec![2, 6, 10], vec![3, 7, 11]],
+        );
+
+        let slow = compute_eip1559_fees(&history, "slow", None).unwrap();
+        assert_eq!(slow.max_priority_fee_per_gas, 2);
+        assert_eq!(slow.max_fee_per_gas, 200 * 110 / 100 + 2);
+
+        let normal = compute_eip1559_fees(&history, "", None).unwrap();
+        assert_eq!(normal.max_priority_fee_per_gas, 6);
+        assert_eq!(normal.max_fee_per_gas, 200 * 125 / 100 + 6);
+
+        let fast = compute_eip1559_fees(&history, "fast", None).unwrap();
+        assert_eq!(fast.max_priority_fee_per_gas, 10);
+        assert_eq!(fast.max_fee_per_gas, 200 * 2 + 10);
+    }
+
+    #[test]
+    fn test_compute_eip1559_fees_cap() {
+        let history = fee_history(vec![100, 200], vec![vec![10, 50, 90]]);
+
+        let capped = compute_eip1559_fees(&history, "fast", Some(230)).unwrap();
+        assert_eq!(capped.max_fee_per_gas, 230);
+        assert_eq!(capped.max_priority_fee_per_gas, 90);
+
+        assert!(compute_eip1559_fees(&history, "normal", Some(150)).is_err());
+        assert!(compute_eip1559_fees(&fee_history(vec![0, 0], vec![]), "normal", None).is_err());
+    }
+
+    #[test]
+    fn test_remaining_transfer_units() {
+        let left_range = [DecimalAmount::from("0.1"), DecimalAmount::from("0.2")];
+        // 6位精度代币，可用 10.123456，转账金额保留2位小数
+        let available = U256::from(10_123_456u64);
+        for _ in 0..50 {
+            let (amount, left) = remaining_transfer_units(available, &left_range, 6, 2).unwrap();
+            assert_eq!(amount + left, available);
+            assert!(left >= U256::from(100_000u64));
+            assert!((amount % U256::from(10_000u64)).is_zero());
+        }
+        // 可用余额不超过最大剩余数量
+        assert!(remaining_transfer_units(U256::from(200_000u64), &left_range, 6, 2).is_err());
+        // 取整后为0
+        let fixed_left = [DecimalAmount::from("0.2"), DecimalAmount::from("0.2")];
+        assert!(remaining_transfer_units(U256::from(205_000u64), &fixed_left, 6, 2).is_err());
+    }
+}
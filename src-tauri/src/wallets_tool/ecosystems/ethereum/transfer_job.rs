@@ -0,0 +1,528 @@
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use alloy_primitives::{Address, B256};
+use alloy_provider::Provider;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, LazyLock};
+use tokio::time::{sleep, Duration};
+use crate::database::get_database_manager;
+use crate::database::models::{CreateTransferJobItemRequest, CreateTransferJobRequest, TransferJob, TransferJobItem};
+use crate::database::transfer_job_service::TransferJobService;
+use crate::database::tx_replacement_service::TxReplacementService;
+use crate::wallets_tool::address_book::resolve_group_addresses;
+use super::ens::{is_ens_name, resolve_names};
+use super::alloy_utils::parse_signer;
+use super::nonce_manager::{BeforeBroadcast, BroadcastError, PreparedTx, send_signed};
+use super::transfer::{
+    TransferConfig, TransferItem, base_coin_transfer_fast_internal, check_transaction_status_internal, create_provider,
+    get_stop_flag,
+};
+use super::token_transfer::{TokenTransferConfig, token_transfer_fast_internal};
+use super::tx_tracker::emit_reorg_events;
+
+// 正在执行中的任务ID，防止同一任务被重复启动
+static RUNNING_JOBS: LazyLock<Mutex<HashSet<i64>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+// 等待交易确认的最长时间（秒），超时后保持原状态，由下次恢复时继续检查
+const CONFIRM_TIMEOUT_SECS: u64 = 60;
+
+// 任务执行期间的占用标记，Drop时自动释放
+struct JobGuard(i64);
+
+impl JobGuard {
+    fn acquire(job_id: i64) -> Result<Self, String> {
+        let mut running = RUNNING_JOBS.lock().unwrap();
+        if !running.insert(job_id) {
+            return Err(format!("任务 {job_id} 正在执行中，请勿重复启动"));
+        }
+        Ok(Self(job_id))
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        RUNNING_JOBS.lock().unwrap().remove(&self.0);
+    }
+}
+
+// 任务的转账配置（基础币或代币）
+enum JobConfig {
+    Base(TransferConfig),
+    Token(TokenTransferConfig),
+}
+
+impl JobConfig {
+    fn from_job(job: &TransferJob) -> Result<Self, String> {
+        match job.transfer_kind.as_str() {
+            "base" => serde_json::from_str(&job.config_json)
+                .map(JobConfig::Base)
+                .map_err(|e| format!("解析任务配置失败: {e}")),
+            "token" => serde_json::from_str(&job.config_json)
+                .map(JobConfig::Token)
+                .map_err(|e| format!("解析任务配置失败: {e}")),
+            other => Err(format!("未知的任务类型: {other}")),
+        }
+    }
+
+    fn delay(&self) -> [u64; 2] {
+        match self {
+            JobConfig::Base(c) => c.delay,
+            JobConfig::Token(c) => c.delay,
+        }
+    }
+}
+
+// 创建任务的参数
+#[derive(Debug, Deserialize)]
+pub struct CreateTransferJobParams {
+    pub chain: String,
+    pub transfer_kind: String, // "base" 或 "token"
+    pub config: serde_json::Value,
+    pub items: Vec<TransferItem>,
+    #[serde(default)]
+    pub window_id: Option<String>,
+    // 地址簿分组名：设置后接收地址取自分组，items 只需提供私钥
+    #[serde(default)]
+    pub to_group: Option<String>,
+}
+
+// 任务详情（任务 + 全部明细）
+#[derive(Debug, Serialize)]
+pub struct TransferJobDetail {
+    pub job: TransferJob,
+    pub items: Vec<TransferJobItem>,
+}
+
+// 任务执行结果汇总
+#[derive(Debug, Serialize)]
+pub struct TransferJobRunResult {
+    pub job_id: i64,
+    pub status: String,
+    pub pending_count: usize,
+    pub submitted_count: usize,
+    pub confirmed_count: usize,
+    pub failed_count: usize,
+}
+
+// 通知前端任务明细状态变化
+fn emit_item_update<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, job_id: i64, item: &TransferJobItem) {
+    let _ = app_handle.emit("transfer_job_update", serde_json::json!({
+        "job_id": job_id,
+        "item_index": item.item_index,
+        "status": item.status,
+        "tx_hash": item.tx_hash,
+        "error_msg": item.error_msg,
+    }));
+}
+
+// 轮询交易回执直到达到确认深度（交易被加速/取消时按实际上链的那一笔），返回 Some(执行结果)，超时返回 None
+async fn wait_for_receipt<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>, chain: &str, tx_hash: &str) -> Option<Result<(), String>> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(CONFIRM_TIMEOUT_SECS);
+    while tokio::time::Instant::now() < deadline {
+        let confirmed = match check_transaction_status_internal(chain.to_string(), tx_hash.to_string()).await {
+            Ok(status) if status.confirmed => Some(match status.success {
+                Some(true) => Ok(()),
+                _ => Err(status.error.unwrap_or_else(|| "交易执行失败".to_string())),
+            }),
+            _ => None,
+        };
+        emit_reorg_events(app_handle);
+        if confirmed.is_some() {
+            return confirmed;
+        }
+        sleep(Duration::from_secs(3)).await;
+    }
+    None
+}
+
+// 等待已提交（或发送中）的明细上链并落库
+async fn settle_submitted_item<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    service: &TransferJobService<'_>,
+    job: &TransferJob,
+    item: &mut TransferJobItem,
+) -> Result<(), String> {
+    let Some(tx_hash) = item.tx_hash.clone() else {
+        return Ok(());
+    };
+
+    match wait_for_receipt(app_handle, &job.chain_key, &tx_hash).await {
+        Some(Ok(())) => {
+            service.mark_item_confirmed(item.id).await.map_err(|e| e.to_string())?;
+            item.status = "confirmed".to_string();
+            item.error_msg = None;
+        }
+        Some(Err(error_msg)) => {
+            service.mark_item_failed(item.id, &error_msg).await.map_err(|e| e.to_string())?;
+            item.status = "failed".to_string();
+            item.error_msg = Some(error_msg);
+        }
+        None => {
+            println!("[任务 {}] 序号 {} 等待确认超时，保留 {} 状态: {tx_hash}", job.id, item.item_index, item.status);
+        }
+    }
+
+    emit_item_update(app_handle, job.id, item);
+    Ok(())
+}
+
+// 发送错误转为 (信息, 是否确定未广播)
+fn send_error(error: &(dyn std::error::Error + 'static)) -> (String, bool) {
+    let rejected = error.downcast_ref::<BroadcastError>().is_none_or(|e| !e.maybe_broadcast);
+    (error.to_string(), rejected)
+}
+
+// 恢复发送中的明细：进程可能在广播前后中断，按链上结果处理，绝不重新签名发送
+async fn recover_sending_item<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    service: &TransferJobService<'_>,
+    job: &TransferJob,
+    item: &mut TransferJobItem,
+) -> Result<(), String> {
+    let (Some(tx_hash), Some(nonce), Some(raw_tx)) = (item.tx_hash.clone(), item.nonce, item.raw_tx.clone()) else {
+        return Err(format!("序号 {} 的发送记录不完整，请核对后处理", item.item_index + 1));
+    };
+    let hash: B256 = tx_hash.parse().map_err(|e| format!("交易哈希格式错误: {e}"))?;
+    let from: Address = item.from_address.parse().map_err(|e| format!("发送地址格式错误: {e}"))?;
+    let provider = create_provider(&job.chain_key, job.window_id.as_deref()).await
+        .map_err(|e| format!("获取RPC提供商失败: {e}"))?;
+
+    // 已被加速/取消、已上链或仍在内存池中
+    let replaced = !TxReplacementService::new(get_database_manager().get_pool())
+        .get_replacements(&job.chain_key, &tx_hash)
+        .await
+        .map_err(|e| format!("查询替换交易记录失败: {e}"))?
+        .is_empty();
+    let known = replaced
+        || provider.get_transaction_receipt(hash).await.map_err(|e| format!("查询交易回执失败: {e}"))?.is_some()
+        || provider.get_transaction_by_hash(hash).await.map_err(|e| format!("查询交易失败: {e}"))?.is_some();
+
+    if !known {
+        // nonce 已被其他交易占用：原交易不会再上链，保留记录由用户核对
+        let confirmed_nonce = provider.get_transaction_count(from).latest().await
+            .map_err(|e| format!("获取nonce失败: {e}"))?;
+        if confirmed_nonce > nonce as u64 {
+            let error_msg = format!("交易 {tx_hash} 未上链且nonce {nonce} 已被其他交易占用，请核对后手动处理");
+            service.mark_item_failed(item.id, &error_msg).await.map_err(|e| e.to_string())?;
+            item.status = "failed".to_string();
+            item.error_msg = Some(error_msg);
+            emit_item_update(app_handle, job.id, item);
+            return Ok(());
+        }
+
+        // 节点上找不到该交易且nonce未被占用：重新广播同一笔已签名交易，哈希与nonce不变，不会重复付款
+        let raw = hex::decode(raw_tx.trim_start_matches("0x")).map_err(|e| format!("原始交易格式错误: {e}"))?;
+        if let Err(e) = send_signed(provider.as_ref(), &raw, hash).await {
+            println!("[任务 {}] 序号 {} 重新广播失败，保留 sending 状态: {e}", job.id, item.item_index);
+            item.error_msg = Some(e.to_string());
+            emit_item_update(app_handle, job.id, item);
+            return Ok(());
+        }
+    }
+
+    if !service.mark_item_submitted(item.id, &tx_hash).await.map_err(|e| e.to_string())? {
+        return Err(format!("序号 {} 的任务记录未能更新，请核对后再恢复", item.item_index + 1));
+    }
+    item.status = "submitted".to_string();
+    item.error_msg = None;
+    emit_item_update(app_handle, job.id, item);
+    settle_submitted_item(app_handle, service, job, item).await
+}
+
+// 为钱包分配接收地址：只有一个钱包时向每个地址各转一笔，否则按顺序一一对应
+fn pair_with_recipients(items: Vec<TransferItem>, recipients: &[String]) -> Result<Vec<TransferItem>, String> {
+    if items.len() == 1 {
+        let item = &items[0];
+        return Ok(recipients
+            .iter()
+            .map(|to_addr| TransferItem { to_addr: to_addr.clone(), ..item.clone() })
+            .collect());
+    }
+    if items.len() != recipients.len() {
+        return Err(format!("钱包数量 ({}) 与接收地址数量 ({}) 不一致", items.len(), recipients.len()));
+    }
+    Ok(items
+        .into_iter()
+        .zip(recipients)
+        .map(|(item, to_addr)| TransferItem { to_addr: to_addr.clone(), ..item })
+        .collect())
+}
+
+// 汇总任务明细状态
+fn summarize(job_id: i64, status: &str, items: &[TransferJobItem]) -> TransferJobRunResult {
+    let count = |s: &str| items.iter().filter(|i| i.status == s).count();
+    TransferJobRunResult {
+        job_id,
+        status: status.to_string(),
+        pending_count: count("pending"),
+        // 发送中的交易可能已广播，与已提交的一起等待核对
+        submitted_count: count("submitted") + count("sending"),
+        confirmed_count: count("confirmed"),
+        failed_count: count("failed"),
+    }
+}
+
+// 执行（或恢复）任务：已有交易哈希的行只检查状态，绝不重新签名发送
+async fn run_transfer_job<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    guard: &JobGuard,
+    items: Vec<TransferItem>,
+) -> Result<TransferJobRunResult, String> {
+    let job_id = guard.0;
+    let service = TransferJobService::new(get_database_manager().get_pool());
+
+    let job = service.get_job(job_id).await
+        .map_err(|e| format!("查询任务失败: {e}"))?
+        .ok_or_else(|| format!("任务不存在: {job_id}"))?;
+    let config = JobConfig::from_job(&job)?;
+
+    let mut job_items = service.get_job_items(job_id).await
+        .map_err(|e| format!("查询任务明细失败: {e}"))?;
+    // 按地址簿分组创建的任务，恢复时可以不传接收地址，使用任务记录中的地址
+    let items = if !items.is_empty() && items.iter().all(|item| item.to_addr.trim().is_empty()) {
+        let recipients: Vec<String> = job_items.iter().map(|item| item.to_address.clone()).collect();
+        pair_with_recipients(items, &recipients)?
+    } else {
+        items
+    };
+    if items.len() != job_items.len() {
+        return Err(format!("传入的钱包数量 ({}) 与任务记录 ({}) 不一致", items.len(), job_items.len()));
+    }
+
+    let window_id = job.window_id.clone().unwrap_or_default();
+    service.update_job_status(job_id, "running").await.map_err(|e| e.to_string())?;
+
+    let result = process_job_items(&app_handle, &service, &job, &config, &window_id, &items, &mut job_items).await;
+
+    // 无论是否中途出错，都不能让任务停留在 running 状态
+    let unfinished = job_items.iter().any(|i| matches!(i.status.as_str(), "pending" | "sending" | "submitted"));
+    let status = if unfinished { "stopped" } else { "completed" };
+    service.update_job_status(job_id, status).await.map_err(|e| e.to_string())?;
+    result?;
+
+    Ok(summarize(job_id, status, &job_items))
+}
+
+async fn process_job_items<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    service: &TransferJobService<'_>,
+    job: &TransferJob,
+    config: &JobConfig,
+    window_id: &str,
+    items: &[TransferItem],
+    job_items: &mut [TransferJobItem],
+) -> Result<(), String> {
+    let job_id = job.id;
+    let mut sent_count = 0usize;
+    for job_item in job_items.iter_mut() {
+        if !window_id.is_empty() && get_stop_flag(window_id) {
+            break;
+        }
+
+        match job_item.status.as_str() {
+            "confirmed" | "failed" => continue,
+            "submitted" => {
+                settle_submitted_item(app_handle, service, job, job_item).await?;
+                continue;
+            }
+            "sending" => {
+                recover_sending_item(app_handle, service, job, job_item).await?;
+                continue;
+            }
+            _ => {}
+        }
+
+        // 双重保险：任何已记录哈希的行都不允许重新发送
+        if job_item.tx_hash.is_some() {
+            continue;
+        }
+
+        let mut item = items[job_item.item_index as usize].clone();
+        // ENS名称在创建任务时已解析并记录，恢复时沿用记录的地址，不再重新解析
+        if is_ens_name(&item.to_addr) {
+            item.to_addr = job_item.to_address.clone();
+        }
+        if item.to_addr.trim() != job_item.to_address {
+            return Err(format!("序号 {} 的接收地址与任务记录不一致，请按原顺序导入", job_item.item_index + 1));
+        }
+        let signer = parse_signer(&item.private_key)?;
+        if format!("{:?}", signer.address()) != job_item.from_address {
+            return Err(format!("序号 {} 的私钥与任务记录的发送地址不一致", job_item.item_index + 1));
+        }
+
+        // 发送间隔
+        if sent_count > 0 {
+            let [min_delay, max_delay] = config.delay();
+            if max_delay > 0 {
+                let delay = rand::thread_rng().gen_range(min_delay.min(max_delay)..=max_delay);
+                sleep(Duration::from_secs(delay)).await;
+            }
+        }
+
+        service.increment_attempt(job_item.id).await.map_err(|e| e.to_string())?;
+        let index = job_item.item_index as usize + 1;
+        // 签名后、广播前先记录交易哈希与nonce，进程中断时恢复流程据此核对，不会重新发送
+        let item_id = job_item.id;
+        let record_sending: BeforeBroadcast<'_> = Box::new(move |prepared: PreparedTx| {
+            Box::pin(async move {
+                let saved = service
+                    .mark_item_sending(item_id, &prepared.tx_hash, prepared.nonce, &prepared.raw_tx)
+                    .await
+                    .map_err(|e| format!("记录发送状态失败: {e}"))?;
+                if !saved {
+                    return Err("该明细已有发送记录，放弃广播".to_string());
+                }
+                Ok(())
+            })
+        });
+        // 错误立即转为 (信息, 是否确定未广播)，避免跨 await 持有非 Send 的错误
+        let send_result = match config {
+            JobConfig::Base(c) => base_coin_transfer_fast_internal(app_handle.clone(), index, item, c.clone(), Some(record_sending))
+                .await
+                .map_err(|e| send_error(e.as_ref())),
+            JobConfig::Token(c) => token_transfer_fast_internal(app_handle.clone(), index, item, c.clone(), Some(record_sending))
+                .await
+                .map_err(|e| send_error(e.as_ref())),
+        };
+        sent_count += 1;
+
+        match send_result {
+            Ok(tx_hash) => {
+                if !service.mark_item_submitted(job_item.id, &tx_hash).await.map_err(|e| e.to_string())? {
+                    return Err(format!(
+                        "序号 {} 的交易已广播（{tx_hash}），但任务记录未能更新，已中止任务，请核对后再恢复",
+                        job_item.item_index + 1
+                    ));
+                }
+                job_item.status = "submitted".to_string();
+                job_item.tx_hash = Some(tx_hash);
+                job_item.error_msg = None;
+                emit_item_update(app_handle, job_id, job_item);
+                settle_submitted_item(app_handle, service, job, job_item).await?;
+            }
+            Err((e, rejected)) => {
+                let saved = service.get_item(job_item.id).await
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("任务明细不存在: {}", job_item.id))?;
+                if saved.status == "sending" && !rejected {
+                    // 交易可能已被节点接收：保留发送中状态，按哈希等待结果
+                    *job_item = saved;
+                    job_item.error_msg = Some(e);
+                    emit_item_update(app_handle, job_id, job_item);
+                    settle_submitted_item(app_handle, service, job, job_item).await?;
+                    continue;
+                }
+                if saved.status == "sending" {
+                    service.mark_item_rejected(job_item.id, &e).await.map_err(|e| e.to_string())?;
+                } else {
+                    service.mark_item_failed(job_item.id, &e).await.map_err(|e| e.to_string())?;
+                }
+                job_item.status = "failed".to_string();
+                job_item.tx_hash = None;
+                job_item.error_msg = Some(e);
+                emit_item_update(app_handle, job_id, job_item);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Tauri命令：创建批量转账任务（只记录发送/接收地址，私钥不落库）
+#[tauri::command]
+pub async fn create_transfer_job(params: CreateTransferJobParams) -> Result<i64, String> {
+    // 先校验配置可被解析，避免恢复时才发现配置错误
+    let config_json = params.config.to_string();
+    match params.transfer_kind.as_str() {
+        "base" => {
+            serde_json::from_str::<TransferConfig>(&config_json).map_err(|e| format!("转账配置格式错误: {e}"))?;
+        }
+        "token" => {
+            serde_json::from_str::<TokenTransferConfig>(&config_json).map_err(|e| format!("转账配置格式错误: {e}"))?;
+        }
+        other => return Err(format!("未知的任务类型: {other}")),
+    }
+
+    let items = match params.to_group.as_deref().map(str::trim).filter(|group| !group.is_empty()) {
+        Some(group) => pair_with_recipients(params.items, &resolve_group_addresses(&params.chain, group).await?)?,
+        None => params.items,
+    };
+
+    // 接收地址中的ENS名称在创建任务时解析，任务明细记录解析后的地址
+    let mut names: Vec<String> = items.iter().map(|item| item.to_addr.trim().to_string()).filter(|to| is_ens_name(to)).collect();
+    names.sort();
+    names.dedup();
+    let resolved = resolve_names(&params.chain, params.window_id.as_deref(), &names).await;
+    let resolved: HashMap<String, Result<Address, String>> = names.into_iter().zip(resolved).collect();
+
+    let mut job_items = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let signer = parse_signer(&item.private_key).map_err(|e| format!("序号 {}: {e}", index + 1))?;
+        job_items.push(CreateTransferJobItemRequest {
+            from_address: format!("{:?}", signer.address()),
+            to_address: match resolved.get(item.to_addr.trim()) {
+                Some(Ok(address)) => format!("{address:?}"),
+                Some(Err(e)) => return Err(format!("序号 {}: {e}", index + 1)),
+                None => item.to_addr.trim().to_string(),
+            },
+        });
+    }
+
+    let service = TransferJobService::new(get_database_manager().get_pool());
+    service.create_job(CreateTransferJobRequest {
+        chain_key: params.chain,
+        transfer_kind: params.transfer_kind,
+        config_json,
+        window_id: params.window_id,
+        items: job_items,
+    }).await
+    .map_err(|e| format!("创建转账任务失败: {e}"))
+}
+
+// Tauri命令：执行或恢复批量转账任务，从中断处继续
+// items 必须与创建任务时的顺序一致（私钥不落库，需重新传入）
+#[tauri::command]
+pub async fn resume_transfer_job<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    job_id: i64,
+    items: Vec<TransferItem>,
+    retry_failed: Option<bool>,
+) -> Result<TransferJobRunResult, String> {
+    // 先占用任务再重置明细，避免任务仍在执行时被改动
+    let guard = JobGuard::acquire(job_id)?;
+    if retry_failed.unwrap_or(false) {
+        let service = TransferJobService::new(get_database_manager().get_pool());
+        service.reset_failed_items(job_id).await.map_err(|e| format!("重置失败明细出错: {e}"))?;
+    }
+    run_transfer_job(app_handle, &guard, items).await
+}
+
+// Tauri命令：获取任务详情
+#[tauri::command]
+pub async fn get_transfer_job(job_id: i64) -> Result<TransferJobDetail, String> {
+    let service = TransferJobService::new(get_database_manager().get_pool());
+    let job = service.get_job(job_id).await
+        .map_err(|e| format!("查询任务失败: {e}"))?
+        .ok_or_else(|| format!("任务不存在: {job_id}"))?;
+    let items = service.get_job_items(job_id).await
+        .map_err(|e| format!("查询任务明细失败: {e}"))?;
+    Ok(TransferJobDetail { job, items })
+}
+
+// Tauri命令：获取任务列表
+#[tauri::command]
+pub async fn list_transfer_jobs() -> Result<Vec<TransferJob>, String> {
+    let service = TransferJobService::new(get_database_manager().get_pool());
+    service.list_jobs().await.map_err(|e| format!("查询任务列表失败: {e}"))
+}
+
+// Tauri命令：删除任务
+#[tauri::command]
+pub async fn delete_transfer_job(job_id: i64) -> Result<(), String> {
+    if RUNNING_JOBS.lock().unwrap().contains(&job_id) {
+        return Err(format!("任务 {job_id} 正在执行中，无法删除"));
+    }
+    let service = TransferJobService::new(get_database_manager().get_pool());
+    service.delete_job(job_id).await.map_err(|e| format!("删除任务失败: {e}"))
+}
@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use alloy::consensus::Transaction as _;
+use alloy_primitives::{B256, TxKind, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_signer::Signer;
+use crate::database::get_database_manager;
+use crate::database::models::TxReplacement;
+use crate::database::tx_replacement_service::TxReplacementService;
+use crate::wallets_tool::security::SecureMemory;
+use super::alloy_utils::{format_wei_to_gwei, parse_signer};
+use super::tx_tracker::ConfirmationTracker;
+use super::transfer::{
+    BatchTransactionStatusResult, TransactionStatusResult, create_provider, create_signer_provider,
+};
+
+// 节点替换交易的最低加价比例（geth txpool.pricebump 默认 10%）
+const MIN_FEE_BUMP_PERCENT: u64 = 10;
+const DEFAULT_FEE_BUMP_PERCENT: u64 = 15;
+
+#[derive(Debug, Serialize)]
+pub struct ReplaceTransactionResult {
+    pub original_hash: String,
+    pub replacement_hash: String,
+    pub kind: String, // "speed_up" or "cancel"
+    pub nonce: u64,
+}
+
+// 按比例加价，+1 保证整数除法后仍严格满足替换规则
+fn bump_fee(fee: u128, percent: u64) -> u128 {
+    fee * (100 + percent as u128) / 100 + 1
+}
+
+// Tauri命令：加速交易（同nonce、同内容、提高手续费重新发送）
+#[tauri::command]
+pub async fn speed_up_transaction(
+    chain: String,
+    tx_hash: String,
+    private_key: SecureMemory,
+    fee_bump_percent: Option<u64>,
+    window_id: Option<String>,
+) -> Result<ReplaceTransactionResult, String> {
+    replace_transaction_internal(&chain, &tx_hash, &private_key, fee_bump_percent, window_id.as_deref(), "speed_up")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Tauri命令：取消交易（同nonce发送0金额的自转账）
+#[tauri::command]
+pub async fn cancel_transaction(
+    chain: String,
+    tx_hash: String,
+    private_key: SecureMemory,
+    fee_bump_percent: Option<u64>,
+    window_id: Option<String>,
+) -> Result<ReplaceTransactionResult, String> {
+    replace_transaction_internal(&chain, &tx_hash, &private_key, fee_bump_percent, window_id.as_deref(), "cancel")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn replace_transaction_internal(
+    chain: &str,
+    tx_hash: &str,
+    private_key: &SecureMemory,
+    fee_bump_percent: Option<u64>,
+    window_id: Option<&str>,
+    kind: &str,
+) -> Result<ReplaceTransactionResult, Box<dyn std::error::Error>> {
+    let hash: B256 = tx_hash.trim().parse().map_err(|e| format!("哈希格式错误: {e}"))?;
+    let bump_percent = fee_bump_percent.unwrap_or(DEFAULT_FEE_BUMP_PERCENT).max(MIN_FEE_BUMP_PERCENT);
+
+    let wallet = parse_signer(private_key)?;
+    let wallet_address = wallet.address();
+
+    let provider = create_provider(chain, window_id).await
+        .map_err(|e| format!("获取RPC提供商失败: {e}"))?;
+
+    let original = provider.get_transaction_by_hash(hash).await
+        .map_err(|e| format!("查询交易失败: {e}"))?
+        .ok_or("未找到该交易，可能已被节点丢弃")?;
+
+    if original.block_number.is_some() {
+        return Err("交易已上链，无需加速或取消".into());
+    }
+    if original.inner.signer() != wallet_address {
+        return Err("私钥与交易的发送地址不一致".into());
+    }
+
+    let nonce = original.nonce();
+    let confirmed_nonce = provider.get_transaction_count(wallet_address).latest().await
+        .map_err(|e| format!("获取nonce失败: {e}"))?;
+    if confirmed_nonce > nonce {
+        return Err(format!("nonce {nonce} 已被使用，交易已上链或已被替换").into());
+    }
+
+    let chain_id = match original.chain_id() {
+        Some(id) => id,
+        None => provider.get_chain_id().await.map_err(|e| format!("获取链ID失败: {e}"))?,
+    };
+    let wallet = wallet.with_chain_id(Some(chain_id));
+
+    let mut tx = if kind == "cancel" {
+        let mut cancel = TransactionRequest {
+            from: Some(wallet_address),
+            to: Some(TxKind::Call(wallet_address)),
+            value: Some(U256::ZERO),
+            ..Default::default()
+        };
+        // 部分L2的gas包含L1数据费用，自转账也可能超过21000，需按链估算
+        let gas = provider.estimate_gas(cancel.clone()).await
+            .map_err(|e| format!("估算取消交易gas失败: {e}"))?;
+        cancel.gas = Some(gas);
+        cancel
+    } else {
+        TransactionRequest {
+            from: Some(wallet_address),
+            to: Some(original.kind()),
+            value: Some(original.value()),
+            input: original.input().clone().into(),
+            gas: Some(original.gas_limit()),
+            ..Default::default()
+        }
+    };
+    tx.nonce = Some(nonce);
+    tx.chain_id = Some(chain_id);
+
+    // 新手续费取 "原手续费加价" 与 "当前网络手续费" 中较高者
+    match original.max_priority_fee_per_gas() {
+        Some(original_priority) => {
+            let estimation = provider.estimate_eip1559_fees().await.ok();
+            let max_fee = bump_fee(original.max_fee_per_gas(), bump_percent)
+                .max(estimation.map(|e| e.max_fee_per_gas).unwrap_or(0));
+            let priority_fee = bump_fee(original_priority, bump_percent)
+                .max(estimation.map(|e| e.max_priority_fee_per_gas).unwrap_or(0))
+                .min(max_fee);
+            println!("[替换交易] {kind} nonce {nonce}: maxFeePerGas {} gwei, maxPriorityFeePerGas {} gwei",
+                format_wei_to_gwei(U256::from(max_fee)),
+                format_wei_to_gwei(U256::from(priority_fee))
+            );
+            tx.max_fee_per_gas = Some(max_fee);
+            tx.max_priority_fee_per_gas = Some(priority_fee);
+        }
+        None => {
+            let original_gas_price = original.gas_price().unwrap_or_else(|| original.max_fee_per_gas());
+            let network_gas_price = provider.get_gas_price().await.unwrap_or(0);
+            let gas_price = bump_fee(original_gas_price, bump_percent).max(network_gas_price);
+            println!("[替换交易] {kind} nonce {nonce}: gasPrice {} gwei", format_wei_to_gwei(U256::from(gas_price)));
+            tx.gas_price = Some(gas_price);
+        }
+    }
+
+    let signer_provider = create_signer_provider(chain, window_id, &wallet).await?;
+    let pending_tx = signer_provider.send_transaction(tx).await
+        .map_err(|e| format!("发送替换交易失败: {e}"))?;
+    let replacement_hash = format!("{:?}", pending_tx.tx_hash());
+    let original_hash = format!("{hash:?}");
+
+    println!("[替换交易] {kind}: {original_hash} -> {replacement_hash}");
+
+    let service = TxReplacementService::new(get_database_manager().get_pool());
+    service.record(chain, &original_hash, &replacement_hash, &format!("{wallet_address:?}"), nonce, kind).await
+        .map_err(|e| format!("替换交易已发送 ({replacement_hash})，但保存关联记录失败: {e}"))?;
+
+    Ok(ReplaceTransactionResult {
+        original_hash,
+        replacement_hash,
+        kind: kind.to_string(),
+        nonce,
+    })
+}
+
+// 原交易及其全部替换交易，附带该笔是否为取消（替换链上任意一环是取消，上链的就是取消交易）
+fn replacement_candidates(tx_hash: &str, replacements: &[TxReplacement]) -> Vec<(String, bool)> {
+    // 替换交易 -> (被替换的交易, 类型)
+    let parents: HashMap<&str, (&str, &str)> = replacements
+        .iter()
+        .map(|r| (r.replacement_hash.as_str(), (r.original_hash.as_str(), r.kind.as_str())))
+        .collect();
+
+    let original = tx_hash.to_lowercase();
+    std::iter::once(original.as_str())
+        .chain(replacements.iter().map(|r| r.replacement_hash.as_str()))
+        .map(|candidate| {
+            let mut cancelled = false;
+            let mut current = candidate;
+            while let Some((parent, kind)) = parents.get(current) {
+                cancelled |= *kind == "cancel";
+                current = parent;
+            }
+            (candidate.to_string(), cancelled)
+        })
+        .collect()
+}
+
+/// 检查被加速/取消过的交易状态
+///
+/// 依次检查原交易及其全部替换交易的回执，报告实际上链的那一笔；
+/// 没有替换记录时返回 None，由调用方按普通交易处理。
+pub(crate) async fn check_replaced_status<P: Provider>(
+    provider: &P,
+    tracker: &mut ConfirmationTracker,
+    chain: &str,
+    tx_hash: &str,
+) -> Option<BatchTransactionStatusResult> {
+    let service = TxReplacementService::new(get_database_manager().get_pool());
+    let replacements = service.get_replacements(chain, tx_hash).await.ok()?;
+    if replacements.is_empty() {
+        return None;
+    }
+
+    for (candidate, cancelled) in replacement_candidates(tx_hash, &replacements) {
+        let Ok(hash) = candidate.parse::<B256>() else {
+            continue;
+        };
+        let Ok(Some(receipt)) = provider.get_transaction_receipt(hash).await else {
+            continue;
+        };
+
+        // 同样需要满足确认深度；回执所在区块已被重组时继续检查其他候选
+        let mut status = tracker.evaluate(provider, hash, Some(&receipt)).await;
+        if status.confirmations == 0 {
+            continue;
+        }
+
+        if status.confirmed && cancelled {
+            status.success = Some(false);
+            status.error = Some("交易已取消".to_string());
+        }
+        return Some(BatchTransactionStatusResult {
+            hash: tx_hash.to_string(),
+            status,
+            mined_hash: Some(candidate),
+        });
+    }
+
+    Some(BatchTransactionStatusResult {
+        hash: tx_hash.to_string(),
+        status: TransactionStatusResult::default(),
+        mined_hash: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_fee() {
+        // 加价后必须严格高于原费用的 110%
+        assert_eq!(bump_fee(100, 10), 111);
+        assert_eq!(bump_fee(1_000_000_000, 15), 1_150_000_001);
+        assert!(bump_fee(7, MIN_FEE_BUMP_PERCENT) * 100 > 7 * 110);
+    }
+
+    #[test]
+    fn test_replacement_candidates() {
+        let record = |original: &str, replacement: &str, kind: &str| TxReplacement {
+            id: 0,
+            chain_key: "eth".to_string(),
+            original_hash: original.to_string(),
+            replacement_hash: replacement.to_string(),
+            from_address: String::new(),
+            nonce: 1,
+            kind: kind.to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        // 0xa 被加速为 0xb，0xb 又被取消为 0xc
+        let replacements = vec![record("0xa", "0xb", "speed_up"), record("0xb", "0xc", "cancel")];
+        assert_eq!(
+            replacement_candidates("0xA", &replacements),
+            vec![("0xa".to_string(), false), ("0xb".to_string(), false), ("0xc".to_string(), true)]
+        );
+    }
+}
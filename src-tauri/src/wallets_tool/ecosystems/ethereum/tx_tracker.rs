@@ -0,0 +1,191 @@
+use alloy_primitives::B256;
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::TransactionReceipt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tauri::Emitter;
+use crate::database::chain_service::ChainService;
+use crate::database::get_database_manager;
+use crate::database::tracked_receipt_service::TrackedReceiptService;
+use super::transfer::TransactionStatusResult;
+
+// 检测到、尚未通知前端的重组事件
+static PENDING_REORGS: LazyLock<Mutex<Vec<TxReorgEvent>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TxReorgEvent {
+    pub chain: String,
+    pub tx_hash: String,
+    pub previous_block_number: u64,
+    pub previous_block_hash: String,
+    pub current_block_hash: Option<String>, // None 表示回执已消失
+}
+
+// 链配置的确认深度，查询失败时按1个区块处理（与旧行为一致）
+async fn confirmation_depth(chain: &str) -> u64 {
+    let service = ChainService::new(get_database_manager().get_pool());
+    match service.get_confirmation_blocks(chain).await {
+        Ok(Some(blocks)) => blocks.max(1) as u64,
+        _ => 1,
+    }
+}
+
+/// 交易确认跟踪器
+///
+/// 单次状态检查内复用：最新区块号只取一次，主链区块哈希按区块号缓存。
+/// 回执所在区块须仍在主链上，且确认数达到链配置的深度才视为已确认；
+/// 之前见过的回执消失或区块哈希变化时，交易回到pending并记录重组事件。
+pub(crate) struct ConfirmationTracker {
+    chain: String,
+    depth: u64,
+    latest_block: u64,
+    canonical_hashes: HashMap<u64, B256>,
+}
+
+impl ConfirmationTracker {
+    pub async fn new<P: Provider>(provider: &P, chain: &str) -> Result<Self, String> {
+        let depth = confirmation_depth(chain).await;
+        let latest_block = provider
+            .get_block_number()
+            .await
+            .map_err(|e| format!("获取最新区块号失败: {e}"))?;
+        Ok(Self {
+            chain: chain.to_string(),
+            depth,
+            latest_block,
+            canonical_hashes: HashMap::new(),
+        })
+    }
+
+    // 主链上该高度的区块哈希；查询失败时返回 None，不据此判定重组
+    async fn canonical_hash<P: Provider>(&mut self, provider: &P, number: u64) -> Option<B256> {
+        if let Some(hash) = self.canonical_hashes.get(&number) {
+            return Some(*hash);
+        }
+        let block = provider.get_block_by_number(number.into()).await.ok()??;
+        self.canonical_hashes.insert(number, block.header.hash);
+        Some(block.header.hash)
+    }
+
+    /// 根据回执（可为空）计算交易状态
+    pub async fn evaluate<P: Provider>(
+        &mut self,
+        provider: &P,
+        tx_hash: B256,
+        receipt: Option<&TransactionReceipt>,
+    ) -> TransactionStatusResult {
+        let mut included = None;
+        if let Some(r) = receipt
+            && let (Some(number), Some(hash)) = (r.block_number, r.block_hash)
+        {
+            // 负载均衡的节点可能返回孤块中的回执
+            let stale = self
+                .canonical_hash(provider, number)
+                .await
+                .is_some_and(|canonical| canonical != hash);
+            if !stale {
+                included = Some((number, hash, r.status()));
+            }
+        }
+        self.settle(tx_hash, included).await
+    }
+
+    // 与上次记录的区块比对并更新跟踪状态（记录保存在数据库中，重启后仍可检测重组）
+    async fn settle(&self, tx_hash: B256, included: Option<(u64, B256, bool)>) -> TransactionStatusResult {
+        let service = TrackedReceiptService::new(get_database_manager().get_pool());
+        let hash_key = format!("{tx_hash:?}");
+        let previous = match service.get(&self.chain, &hash_key).await {
+            Ok(previous) => previous.and_then(|r| Some((r.block_number as u64, r.block_hash.parse::<B256>().ok()?))),
+            Err(e) => {
+                println!("[WARN] 读取回执跟踪记录失败 - 链: {}, 交易: {hash_key}: {e}", self.chain);
+                None
+            }
+        };
+
+        let Some((number, hash, success)) = included else {
+            if let Some((previous_number, previous_hash)) = previous {
+                self.untrack(&service, &hash_key).await;
+                self.record_reorg(tx_hash, previous_number, previous_hash, None);
+            }
+            return TransactionStatusResult {
+                required_confirmations: self.depth,
+                ..Default::default()
+            };
+        };
+
+        if let Some((previous_number, previous_hash)) = previous
+            && previous_hash != hash
+        {
+            self.record_reorg(tx_hash, previous_number, previous_hash, Some(hash));
+        }
+
+        let confirmations = confirmations_at(self.latest_block, number);
+        if confirmations >= self.depth {
+            // 已达到确认深度，不再跟踪
+            self.untrack(&service, &hash_key).await;
+            TransactionStatusResult {
+                confirmed: true,
+                success: Some(success),
+                error: if success { None } else { Some("交易执行失败".to_string()) },
+                confirmations,
+                required_confirmations: self.depth,
+            }
+        } else {
+            if let Err(e) = service.upsert(&self.chain, &hash_key, number, &format!("{hash:?}")).await {
+                println!("[WARN] 保存回执跟踪记录失败 - 链: {}, 交易: {hash_key}: {e}", self.chain);
+            }
+            TransactionStatusResult {
+                confirmations,
+                required_confirmations: self.depth,
+                ..Default::default()
+            }
+        }
+    }
+
+    async fn untrack(&self, service: &TrackedReceiptService<'_>, hash_key: &str) {
+        if let Err(e) = service.remove(&self.chain, hash_key).await {
+            println!("[WARN] 删除回执跟踪记录失败 - 链: {}, 交易: {hash_key}: {e}", self.chain);
+        }
+    }
+
+    fn record_reorg(&self, tx_hash: B256, previous_number: u64, previous_hash: B256, current: Option<B256>) {
+        println!(
+            "[WARN] 检测到区块重组 - 链: {}, 交易: {tx_hash:?}, 原区块: {previous_number} ({previous_hash:?}), 当前: {current:?}",
+            self.chain
+        );
+        PENDING_REORGS.lock().unwrap().push(TxReorgEvent {
+            chain: self.chain.clone(),
+            tx_hash: format!("{tx_hash:?}"),
+            previous_block_number: previous_number,
+            previous_block_hash: format!("{previous_hash:?}"),
+            current_block_hash: current.map(|h| format!("{h:?}")),
+        });
+    }
+}
+
+// 回执所在区块自身计1个确认；节点最新高度落后于回执时按1处理
+fn confirmations_at(latest_block: u64, receipt_block: u64) -> u64 {
+    latest_block.max(receipt_block) - receipt_block + 1
+}
+
+// 通知前端检测到的重组事件
+pub fn emit_reorg_events<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>) {
+    let events = std::mem::take(&mut *PENDING_REORGS.lock().unwrap());
+    for event in events {
+        let _ = app_handle.emit("transaction_reorged", &event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmations_at() {
+        assert_eq!(confirmations_at(100, 100), 1);
+        assert_eq!(confirmations_at(111, 100), 12);
+        // 节点落后于回执所在区块
+        assert_eq!(confirmations_at(99, 100), 1);
+    }
+}
@@ -0,0 +1,453 @@
+use serde::{Deserialize, Serialize};
+#[allow(deprecated)]
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use tauri::Emitter;
+use serde_json::json;
+use crate::database::get_database_manager;
+use crate::wallets_tool::ecosystems::ethereum::alloy_utils::{Rounding, parse_units};
+use crate::wallets_tool::ecosystems::solana::keypair::parse_keypair;
+use crate::wallets_tool::ecosystems::solana::lookup_table::{PACKET_DATA_SIZE, build_transaction, invalidate_lookup_tables, load_lookup_tables, transaction_size};
+use crate::wallets_tool::ecosystems::solana::priority_fee::{
+    ComputeBudget, MAX_COMPUTE_UNITS, resolve_unit_price, simulate_unit_limit, uses_priority_fee, writable_accounts,
+};
+use crate::wallets_tool::ecosystems::solana::provider::{SolanaProvider, get_rpc_client};
+use crate::wallets_tool::ecosystems::solana::sns::{release_locked_names, resolve_recipient};
+use crate::wallets_tool::ecosystems::solana::token_program::{MintInfo, load_mint};
+use crate::wallets_tool::ecosystems::solana::transfer::TransferConfig;
+use crate::wallets_tool::security::SecureMemory;
+
+// 各类指令的计算单元预估（略高于实际消耗），仅用于装箱和余额预检
+const SOL_TRANSFER_UNITS: u32 = 450;
+const TOKEN_TRANSFER_UNITS: u32 = 10_000;
+const CREATE_ATA_UNITS: u32 = 35_000;
+const COMPUTE_BUDGET_UNITS: u32 = 300;
+
+// 每个签名的基础手续费
+const BASE_FEE_LAMPORTS: u64 = 5_000;
+
+#[derive(Deserialize)]
+pub struct BatchRecipient {
+    pub index: usize, // 前端原始行号，结果按该行号回填
+    pub to_addr: String,
+    pub amount: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BatchTransferParams {
+    pub private_key: SecureMemory,
+    #[serde(default)]
+    pub derivation_index: Option<u32>,
+    pub recipients: Vec<BatchRecipient>,
+    pub config: TransferConfig,
+    #[serde(default)]
+    pub window_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchTransferItemResult {
+    pub index: usize,
+    pub to_addr: String,
+    pub success: bool,
+    pub tx_hash: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchTransferResult {
+    pub items: Vec<BatchTransferItemResult>,
+    pub tx_hashes: Vec<String>,
+}
+
+// 代币转账所需的上下文
+struct TokenContext {
+    mint: MintInfo,
+    from_ata: Pubkey,
+    epoch: u64, // 计算转账手续费使用的当前epoch
+}
+
+// 已解析的接收行
+struct PackRow {
+    position: usize, // 在 recipients 中的位置
+    to: Pubkey,
+    amount: u64,
+    create_ata: bool,
+}
+
+fn row_units(token: Option<&TokenContext>, row: &PackRow) -> u32 {
+    match token {
+        None => SOL_TRANSFER_UNITS,
+        Some(_) if row.create_ata => TOKEN_TRANSFER_UNITS + CREATE_ATA_UNITS,
+        Some(_) => TOKEN_TRANSFER_UNITS,
+    }
+}
+
+fn row_instructions(payer: &Pubkey, token: Option<&TokenContext>, row: &PackRow) -> Result<Vec<Instruction>, String> {
+    let Some(token) = token else {
+        return Ok(vec![system_instruction::transfer(payer, &row.to, row.amount)]);
+    };
+    let mut instructions = Vec::new();
+    if row.create_ata {
+        instructions.push(token.mint.create_associated_account(payer, &row.to));
+    }
+    let to_ata = token.mint.associated_address(&row.to);
+    instructions.push(token.mint.transfer_instruction(&token.from_ata, &to_ata, payer, row.amount, token.epoch)?);
+    Ok(instructions)
+}
+
+fn rows_instructions(payer: &Pubkey, token: Option<&TokenContext>, rows: &[&PackRow]) -> Result<Vec<Instruction>, String> {
+    let mut instructions = Vec::new();
+    for row in rows {
+        instructions.extend(row_instructions(payer, token, row)?);
+    }
+    Ok(instructions)
+}
+
+// 装箱时使用的完整指令列表：计算单元按预估值，发送前再按模拟结果调整
+fn pack_instructions(
+    payer: &Pubkey,
+    token: Option<&TokenContext>,
+    rows: &[&PackRow],
+    unit_price: Option<u64>,
+) -> Result<(Vec<Instruction>, u32), String> {
+    let budget_units = COMPUTE_BUDGET_UNITS * if unit_price.is_some() { 2 } else { 1 };
+    let units = rows.iter().map(|row| row_units(token, row)).sum::<u32>() + budget_units;
+    let budget = ComputeBudget { unit_limit: units.min(MAX_COMPUTE_UNITS), unit_price };
+    Ok((budget.apply(&rows_instructions(payer, token, rows)?), units))
+}
+
+fn fits(
+    payer: &Keypair,
+    token: Option<&TokenContext>,
+    rows: &[&PackRow],
+    priority_fee: Option<u64>,
+    tables: &[AddressLookupTableAccount],
+) -> bool {
+    let Ok((instructions, units)) = pack_instructions(&payer.pubkey(), token, rows, priority_fee) else {
+        return false;
+    };
+    units <= MAX_COMPUTE_UNITS
+        && build_transaction(payer, &instructions, tables, Hash::default())
+            .is_ok_and(|transaction| transaction_size(&transaction) <= PACKET_DATA_SIZE)
+}
+
+/// 按顺序贪心装箱：在大小和计算单元限制内尽量把更多接收行放进同一笔交易，
+/// 返回每笔交易包含的行；单独一行也放不下时该行放入 oversized
+fn pack_rows<'a>(
+    payer: &Keypair,
+    token: Option<&TokenContext>,
+    rows: &'a [PackRow],
+    priority_fee: Option<u64>,
+    tables: &[AddressLookupTableAccount],
+) -> (Vec<Vec<&'a PackRow>>, Vec<&'a PackRow>) {
+    let mut packs: Vec<Vec<&PackRow>> = Vec::new();
+    let mut oversized = Vec::new();
+    let mut current: Vec<&PackRow> = Vec::new();
+    for row in rows {
+        current.push(row);
+        if fits(payer, token, &current, priority_fee, tables) {
+            continue;
+        }
+        current.pop();
+        if !current.is_empty() {
+            packs.push(std::mem::take(&mut current));
+        }
+        if fits(payer, token, &[row], priority_fee, tables) {
+            current.push(row);
+        } else {
+            oversized.push(row);
+        }
+    }
+    if !current.is_empty() {
+        packs.push(current);
+    }
+    (packs, oversized)
+}
+
+/// 行金额（为空时使用配置金额）按精度精确换算为最小单位，无法解析时返回错误
+fn parse_amount(recipient: &BatchRecipient, config: &TransferConfig, decimals: u8) -> Result<u64, String> {
+    let amount = match recipient.amount.as_deref().map(str::trim) {
+        Some(amount) if !amount.is_empty() => amount.to_string(),
+        _ => config.transfer_amount.to_string(),
+    };
+    if amount.starts_with('-') {
+        return Err("打包模式不支持全部转出或零金额".to_string());
+    }
+    let units = parse_units(&amount, decimals, Rounding::Down)?;
+    match u64::try_from(units) {
+        Ok(0) => Err("打包模式不支持全部转出或零金额".to_string()),
+        Ok(units) => Ok(units),
+        Err(_) => Err(format!("金额超出范围: {amount}")),
+    }
+}
+
+async fn load_token_context(client: &SolanaProvider, mint: &str, owner: &Pubkey) -> Result<TokenContext, String> {
+    let mint = load_mint(client, mint).await?;
+    let epoch = if mint.has_transfer_fee() { client.get_epoch().await? } else { 0 };
+    Ok(TokenContext { from_ata: mint.associated_address(owner), mint, epoch })
+}
+
+// 发送前检查余额是否足够覆盖全部转账及手续费
+async fn check_balance(
+    client: &SolanaProvider,
+    payer: &Pubkey,
+    token: Option<&TokenContext>,
+    rows: &[&PackRow],
+    fee_lamports: u64,
+) -> Result<(), String> {
+    let total = rows
+        .iter()
+        .try_fold(0u64, |sum, row| sum.checked_add(row.amount))
+        .ok_or("金额溢出")?;
+    let balance = client.get_balance(payer).await?;
+    match token {
+        None if balance < total.checked_add(fee_lamports).ok_or("金额溢出")? => Err(format!(
+            "余额不足 (余额: {balance}, 转账合计: {total}, 预估手续费: {fee_lamports})"
+        )),
+        Some(token) => {
+            if balance < fee_lamports {
+                return Err(format!("余额不足支付手续费 (余额: {balance}, 预估手续费及租金: {fee_lamports})"));
+            }
+            let token_balance = client
+                .get_token_account_balance(&token.from_ata)
+                .await
+                .ok()
+                .and_then(|balance| balance.amount.parse::<u64>().ok())
+                .unwrap_or(0);
+            if token_balance < total {
+                return Err(format!("代币余额不足 (余额: {token_balance}, 转账合计: {total})"));
+            }
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+// 发送一笔交易并确认，确认后检查执行结果
+async fn send_pack(client: &SolanaProvider, transaction: &VersionedTransaction) -> (Option<String>, Result<(), String>) {
+    let signature = match client.send_transaction(transaction).await {
+        Ok(signature) => signature,
+        Err(e) => return (None, Err(e)),
+    };
+    let hash = signature.to_string();
+    if let Err(e) = client.confirm_transaction(&signature).await {
+        return (Some(hash), Err(format!("{e}，请稍后查询交易状态，勿重复发送")));
+    }
+    let status = client.get_signature_statuses_batch(std::slice::from_ref(&hash)).await;
+    let result = match status.as_deref().map(|statuses| statuses.first().map(|status| &status["err"])) {
+        Ok(Some(err)) if !err.is_null() => Err(format!("交易执行失败: {err}")),
+        _ => Ok(()),
+    };
+    (Some(hash), result)
+}
+
+// 按模拟结果设置计算单元后发送一笔打包交易
+async fn send_packed(
+    client: &SolanaProvider,
+    keypair: &Keypair,
+    token: Option<&TokenContext>,
+    pack: &[&PackRow],
+    unit_price: Option<u64>,
+    tables: &[AddressLookupTableAccount],
+) -> (Option<String>, Result<(), String>) {
+    let prepared = async {
+        let instructions = rows_instructions(&keypair.pubkey(), token, pack)?;
+        let unit_limit = simulate_unit_limit(client, keypair, &instructions, tables, unit_price).await?;
+        let recent_blockhash = client.get_latest_blockhash().await?;
+        build_transaction(keypair, &ComputeBudget { unit_limit, unit_price }.apply(&instructions), tables, recent_blockhash)
+    }
+    .await;
+    match prepared {
+        Ok(transaction) => send_pack(client, &transaction).await,
+        Err(e) => (None, Err(e)),
+    }
+}
+
+// Tauri命令：一对多分发，把多个接收地址打包进尽量少的交易，结果按原始行号返回
+#[tauri::command]
+pub async fn sol_batch_transfer(
+    params: BatchTransferParams,
+    window: tauri::Window,
+) -> Result<BatchTransferResult, String> {
+    let config = &params.config;
+    let chain = config.chain.as_deref().unwrap_or("sol");
+    let client = get_rpc_client(chain, Some(get_database_manager().get_pool())).await?;
+    let keypair = parse_keypair(&params.private_key, params.derivation_index)?;
+    let payer = keypair.pubkey();
+
+    let token = match config.contract_address.as_deref().filter(|mint| !mint.is_empty()) {
+        Some(mint) => Some(load_token_context(&client, mint, &payer).await?),
+        None => None,
+    };
+    let tables = load_lookup_tables(&client, chain, &config.lookup_tables).await?;
+
+    let mut items: Vec<BatchTransferItemResult> = params
+        .recipients
+        .iter()
+        .map(|recipient| BatchTransferItemResult {
+            index: recipient.index,
+            to_addr: recipient.to_addr.clone(),
+            success: false,
+            tx_hash: None,
+            error: None,
+        })
+        .collect();
+
+    // 解析接收地址与金额，无效行直接记录错误
+    let mut rows = Vec::new();
+    let decimals = token.as_ref().map_or(9, |token| token.mint.decimals);
+    for (position, recipient) in params.recipients.iter().enumerate() {
+        let amount = match parse_amount(recipient, config, decimals) {
+            Ok(amount) => amount,
+            Err(e) => {
+                items[position].error = Some(e);
+                continue;
+            }
+        };
+        match resolve_recipient(chain, params.window_id.as_deref(), &recipient.to_addr).await {
+            Ok(to) => {
+                // 有转账手续费时按含手续费的数量转出，保证接收方到账金额为填写的数量
+                let amount = match &token {
+                    Some(token) => match token.mint.gross_amount(token.epoch, amount) {
+                        Ok(amount) => amount,
+                        Err(e) => {
+                            items[position].error = Some(e);
+                            continue;
+                        }
+                    },
+                    None => amount,
+                };
+                rows.push(PackRow { position, to, amount, create_ata: false });
+            }
+            Err(e) => items[position].error = Some(e),
+        }
+    }
+    // 本批次的 .sol 域名都已解析，释放锁定结果
+    if let Some(window_id) = params.window_id.as_deref() {
+        release_locked_names(window_id);
+    }
+
+    // 代币转账需要为没有关联账户的接收地址创建账户
+    if let Some(token) = &token {
+        let atas: Vec<Pubkey> = rows.iter().map(|row| token.mint.associated_address(&row.to)).collect();
+        let accounts = client.get_multiple_accounts(&atas).await?;
+        for (row, account) in rows.iter_mut().zip(accounts) {
+            row.create_ata = account.is_null();
+        }
+    }
+    // 新建关联账户需要预存的租金，按该代币程序及扩展下的账户大小向节点查询
+    let ata_rent = match &token {
+        Some(token) if rows.iter().any(|row| row.create_ata) => {
+            client.get_minimum_balance_for_rent_exemption(token.mint.account_len()).await?
+        }
+        _ => 0,
+    };
+
+    // 装箱只取决于是否带单价指令，单价的值不影响交易大小，这里先用占位值
+    let pack_price = uses_priority_fee(config)?.then_some(0);
+    let (packs, oversized) = pack_rows(&keypair, token.as_ref(), &rows, pack_price, &tables);
+    for row in oversized {
+        items[row.position].error = Some("单笔转账超过交易大小限制".to_string());
+    }
+
+    // 每笔交易按自身写入的账户估算单价
+    let mut unit_prices = Vec::with_capacity(packs.len());
+    for pack in &packs {
+        let writable = writable_accounts(&rows_instructions(&payer, token.as_ref(), pack)?);
+        unit_prices.push(resolve_unit_price(&client, config, &writable).await?);
+    }
+
+    let fee_lamports: u64 = packs
+        .iter()
+        .zip(&unit_prices)
+        .map(|(pack, unit_price)| {
+            let units = pack.iter().map(|row| row_units(token.as_ref(), row)).sum::<u32>() as u64;
+            let rent = pack.iter().filter(|row| row.create_ata).count() as u64 * ata_rent;
+            BASE_FEE_LAMPORTS + rent + unit_price.map_or(0, |price| (units * price).div_ceil(1_000_000))
+        })
+        .sum();
+    let packed_rows: Vec<&PackRow> = packs.iter().flatten().copied().collect();
+    check_balance(&client, &payer, token.as_ref(), &packed_rows, fee_lamports).await?;
+    println!("[Solana] 批量转账 {} 个接收地址，打包为 {} 笔交易", packed_rows.len(), packs.len());
+
+    let mut tx_hashes = Vec::new();
+    for (pack_index, pack) in packs.iter().enumerate() {
+        let (hash, outcome) = send_packed(&client, &keypair, token.as_ref(), pack, unit_prices[pack_index], &tables).await;
+        if outcome.is_err() {
+            invalidate_lookup_tables(chain, &tables);
+        }
+        if let Some(hash) = &hash {
+            tx_hashes.push(hash.clone());
+        }
+        for row in pack {
+            let item = &mut items[row.position];
+            item.tx_hash = hash.clone();
+            item.success = outcome.is_ok();
+            item.error = outcome.as_ref().err().cloned();
+        }
+
+        let pack_items: Vec<&BatchTransferItemResult> = pack.iter().map(|row| &items[row.position]).collect();
+        let _ = window.emit("sol_batch_transfer_update", json!({
+            "window_id": params.window_id,
+            "pack_index": pack_index,
+            "pack_count": packs.len(),
+            "items": pack_items,
+        }));
+    }
+
+    Ok(BatchTransferResult { items, tx_hashes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_rows() {
+        let payer = Keypair::new();
+        let rows: Vec<PackRow> = (0..50)
+            .map(|position| PackRow { position, to: Pubkey::new_unique(), amount: 1_000, create_ata: false })
+            .collect();
+
+        let (packs, oversized) = pack_rows(&payer, None, &rows, Some(1_000), &[]);
+        assert!(oversized.is_empty());
+        assert!(packs.len() > 1 && packs.len() < rows.len());
+        let positions: Vec<usize> = packs.iter().flatten().map(|row| row.position).collect();
+        assert_eq!(positions, (0..50).collect::<Vec<_>>());
+        for pack in &packs {
+            assert!(fits(&payer, None, pack, Some(1_000), &[]));
+        }
+        // 除最后一笔外每笔交易都已装满：再加下一行就会超出限制
+        for window in packs.windows(2) {
+            let mut extended = window[0].clone();
+            extended.push(window[1][0]);
+            assert!(!fits(&payer, None, &extended, Some(1_000), &[]));
+        }
+
+        // 使用查找表后同样的接收地址需要更少的交易
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: rows.iter().map(|row| row.to).collect() };
+        let (table_packs, _) = pack_rows(&payer, None, &rows, Some(1_000), &[table]);
+        assert!(table_packs.len() < packs.len());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let config: TransferConfig = serde_json::from_value(json!({ "transfer_amount": 0.1 })).unwrap();
+        let row = |amount: Option<&str>| BatchRecipient { index: 0, to_addr: String::new(), amount: amount.map(str::to_string) };
+        assert_eq!(parse_amount(&row(None), &config, 9), Ok(100_000_000));
+        assert_eq!(parse_amount(&row(Some(" 1.000000001 ")), &config, 9), Ok(1_000_000_001));
+        // 0.29 * 100 按浮点计算会截断为 28
+        assert_eq!(parse_amount(&row(Some("0.29")), &config, 2), Ok(29));
+        assert!(parse_amount(&row(Some("1,5")), &config, 9).is_err());
+        assert!(parse_amount(&row(Some("-1")), &config, 9).is_err());
+        assert!(parse_amount(&row(Some("0.0000000001")), &config, 9).is_err());
+        assert!(parse_amount(&row(Some("99999999999")), &config, 9).is_err());
+    }
+}
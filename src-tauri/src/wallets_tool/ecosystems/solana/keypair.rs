@@ -0,0 +1,140 @@
+use bip39::{Language, Mnemonic};
+use serde::{Deserialize, Serialize};
+#[allow(deprecated)]
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::{Keypair, Signer},
+    signer::keypair::keypair_from_seed_and_derivation_path,
+};
+use zeroize::Zeroize;
+use crate::wallets_tool::security::SecureMemory;
+
+/// 私钥的输入格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyFormat {
+    Base58,      // Phantom 等钱包导出的 base58 私钥
+    KeygenJson,  // solana-keygen 生成的 JSON 字节数组
+    Mnemonic,    // BIP39 助记词，按 m/44'/501'/n'/0' 派生
+}
+
+/// 识别私钥格式
+pub fn detect_format(secret: &str) -> KeyFormat {
+    let secret = secret.trim();
+    if secret.starts_with('[') {
+        KeyFormat::KeygenJson
+    } else if secret.split_whitespace().nth(1).is_some() {
+        KeyFormat::Mnemonic
+    } else {
+        KeyFormat::Base58
+    }
+}
+
+// 错误信息只带位置，不回显助记词内容
+fn mnemonic_error(error: bip39::Error) -> String {
+    match error {
+        bip39::Error::BadWordCount(count) => format!("助记词单词数应为12/15/18/21/24，实际为 {count}"),
+        bip39::Error::UnknownWord(index) => format!("助记词第 {} 个单词不在BIP39英文单词表中", index + 1),
+        bip39::Error::InvalidChecksum => "助记词校验和错误，请检查单词是否输错或顺序是否正确".to_string(),
+        e => format!("助记词无效: {e}"),
+    }
+}
+
+/// 解析任一格式的私钥，derivation_index 为助记词的账户序号 n（默认 0），其它格式忽略
+fn keypair_from_str(secret: &str, derivation_index: Option<u32>) -> Result<(KeyFormat, Keypair), String> {
+    let format = detect_format(secret);
+    let secret = secret.trim();
+    let keypair = match format {
+        KeyFormat::Base58 => {
+            let mut bytes = bs58::decode(secret).into_vec().map_err(|_| "私钥不是有效的base58编码".to_string())?;
+            let keypair = Keypair::try_from(bytes.as_slice()).map_err(|_| "base58私钥长度应为64字节".to_string());
+            bytes.zeroize();
+            keypair?
+        }
+        KeyFormat::KeygenJson => {
+            let mut bytes: Vec<u8> = serde_json::from_str(secret).map_err(|_| "JSON私钥应为0-255的数字数组".to_string())?;
+            let keypair = Keypair::try_from(bytes.as_slice()).map_err(|_| "JSON私钥应包含64个字节".to_string());
+            bytes.zeroize();
+            keypair?
+        }
+        KeyFormat::Mnemonic => {
+            // 校验BIP39英文单词表与校验和，输错的助记词不会派生出另一个钱包
+            let mut phrase = secret.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+            let mnemonic = Mnemonic::parse_in_normalized(Language::English, &phrase).map_err(mnemonic_error);
+            phrase.zeroize();
+            let mut seed = mnemonic?.to_seed_normalized("");
+            let path = DerivationPath::new_bip44(Some(derivation_index.unwrap_or(0)), Some(0));
+            let keypair = keypair_from_seed_and_derivation_path(&seed, Some(path)).map_err(|e| format!("助记词派生失败: {e}"));
+            seed.zeroize();
+            keypair?
+        }
+    };
+    Ok((format, keypair))
+}
+
+/// 从 SecureMemory 中解析签名密钥，明文只在闭包内使用
+pub fn parse_keypair(private_key: &SecureMemory, derivation_index: Option<u32>) -> Result<Keypair, String> {
+    private_key
+        .use_secret(|secret| keypair_from_str(secret, derivation_index))?
+        .map(|(_, keypair)| keypair)
+}
+
+#[derive(Deserialize)]
+pub struct KeyInput {
+    pub private_key: SecureMemory,
+    #[serde(default)]
+    pub derivation_index: Option<u32>,
+}
+
+/// 私钥解析结果，只返回格式与公钥
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyInfo {
+    pub format: Option<KeyFormat>,
+    pub pubkey: Option<String>,
+    pub error: Option<String>,
+}
+
+// Tauri命令：解析私钥并返回对应公钥，用于确认导入的是预期钱包
+#[tauri::command]
+pub async fn sol_derive_pubkeys(keys: Vec<KeyInput>) -> Result<Vec<KeyInfo>, String> {
+    Ok(keys
+        .iter()
+        .map(|key| match key.private_key.use_secret(|secret| keypair_from_str(secret, key.derivation_index)) {
+            Ok(Ok((format, keypair))) => KeyInfo { format: Some(format), pubkey: Some(keypair.pubkey().to_string()), error: None },
+            Ok(Err(e)) | Err(e) => KeyInfo { format: None, pubkey: None, error: Some(e) },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_formats() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+
+        let (format, parsed) = keypair_from_str(&keypair.to_base58_string(), None).unwrap();
+        assert_eq!(format, KeyFormat::Base58);
+        assert_eq!(parsed.pubkey(), pubkey);
+
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        let (format, parsed) = keypair_from_str(&format!("{json}\n"), None).unwrap();
+        assert_eq!(format, KeyFormat::KeygenJson);
+        assert_eq!(parsed.pubkey(), pubkey);
+        assert!(keypair_from_str("[1, 2, 3]", None).is_err());
+
+        // Phantom/Solflare 对该测试助记词派生的首个账户
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let (format, first) = keypair_from_str(phrase, None).unwrap();
+        assert_eq!(format, KeyFormat::Mnemonic);
+        assert_eq!(first.pubkey().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+        let (_, second) = keypair_from_str(&phrase.replace(' ', "  "), Some(1)).unwrap();
+        assert_ne!(second.pubkey(), first.pubkey());
+        assert!(keypair_from_str("abandon about", None).is_err());
+        // 单词表之外的单词与校验和错误都要报错，不能派生出另一个钱包
+        assert!(keypair_from_str(&phrase.replace("about", "abuot"), None).unwrap_err().contains("第 12 个单词"));
+        assert!(keypair_from_str(&phrase.replace("about", "abandon"), None).unwrap_err().contains("校验和"));
+    }
+}
@@ -0,0 +1,315 @@
+use base64::Engine;
+use serde::Serialize;
+#[allow(deprecated)]
+use solana_sdk::address_lookup_table::{
+    instruction::{create_lookup_table, extend_lookup_table},
+    state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+    AddressLookupTableAccount,
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::{Message, VersionedMessage, v0},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::VersionedTransaction,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use crate::database::{get_database_manager, lookup_table_service::LookupTableService, models::SolanaLookupTable};
+use crate::wallets_tool::ecosystems::solana::keypair::parse_keypair;
+use crate::wallets_tool::ecosystems::solana::provider::{SolanaProvider, get_rpc_client};
+use crate::wallets_tool::security::SecureMemory;
+
+// 单笔交易的最大序列化大小
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+// 每笔扩展交易写入的地址数量，保证交易不超过大小限制
+const EXTEND_CHUNK_SIZE: usize = 20;
+
+// 缓存的查找表有效期，表可能被其它工具扩展或停用，过期后重新读取链上数据
+const TABLE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+// 已加载的查找表：(链, 查找表地址) -> (表内地址, 读取时间)，创建或扩展后刷新，使用它的交易发送失败时移除
+type CachedTable = (AddressLookupTableAccount, Instant);
+static TABLE_CACHE: LazyLock<Mutex<HashMap<(String, Pubkey), CachedTable>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LookupTableInfo {
+    pub table_address: String,
+    pub authority: String,
+    pub addresses: Vec<String>,
+    pub added: usize,
+    pub tx_hashes: Vec<String>,
+}
+
+/// 构建并签名交易：未使用查找表时为legacy交易，否则编译为v0交易
+pub fn build_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction, String> {
+    let message = if tables.is_empty() {
+        VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(&payer.pubkey()), &recent_blockhash))
+    } else {
+        let message = v0::Message::try_compile(&payer.pubkey(), instructions, tables, recent_blockhash)
+            .map_err(|e| format!("编译v0交易失败: {e}"))?;
+        VersionedMessage::V0(message)
+    };
+    let transaction = VersionedTransaction::try_new(message, &[payer]).map_err(|e| format!("交易签名失败: {e}"))?;
+    let size = transaction_size(&transaction);
+    if size > PACKET_DATA_SIZE {
+        return Err(format!("交易大小 {size} 字节超过上限 {PACKET_DATA_SIZE} 字节"));
+    }
+    Ok(transaction)
+}
+
+/// 交易序列化后的字节数
+pub fn transaction_size(transaction: &VersionedTransaction) -> usize {
+    bincode::serialized_size(transaction).map(|size| size as usize).unwrap_or(usize::MAX)
+}
+
+// 读取链上查找表，返回表内地址及最后一次扩展的slot
+async fn fetch_table(client: &SolanaProvider, key: &Pubkey) -> Result<(AddressLookupTableAccount, Option<Pubkey>, u64), String> {
+    let account = client.get_account(key).await?;
+    let data = account["value"]["data"][0]
+        .as_str()
+        .ok_or_else(|| format!("查找表不存在: {key}"))?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("查找表数据解析失败: {e}"))?;
+    let table = AddressLookupTable::deserialize(&data).map_err(|e| format!("查找表数据解析失败: {e}"))?;
+    if table.meta.deactivation_slot != u64::MAX {
+        return Err(format!("查找表已停用: {key}"));
+    }
+    let account = AddressLookupTableAccount { key: *key, addresses: table.addresses.to_vec() };
+    Ok((account, table.meta.authority, table.meta.last_extended_slot))
+}
+
+/// 加载批量转账使用的查找表，优先使用缓存
+pub async fn load_lookup_tables(
+    client: &SolanaProvider,
+    chain: &str,
+    table_addresses: &[String],
+) -> Result<Vec<AddressLookupTableAccount>, String> {
+    let mut tables = Vec::with_capacity(table_addresses.len());
+    for address in table_addresses {
+        let key = Pubkey::from_str(address.trim()).map_err(|_| format!("无效的查找表地址: {address}"))?;
+        let cached = TABLE_CACHE
+            .lock()
+            .unwrap()
+            .get(&(chain.to_string(), key))
+            .filter(|(_, loaded_at)| loaded_at.elapsed() < TABLE_CACHE_TTL)
+            .map(|(table, _)| table.clone());
+        let table = match cached {
+            Some(table) => table,
+            None => {
+                let (table, _, _) = fetch_table(client, &key).await?;
+                TABLE_CACHE.lock().unwrap().insert((chain.to_string(), key), (table.clone(), Instant::now()));
+                table
+            }
+        };
+        tables.push(table);
+    }
+    Ok(tables)
+}
+
+/// 使用查找表的交易发送失败时移除缓存，下次重新读取链上数据
+pub fn invalidate_lookup_tables(chain: &str, tables: &[AddressLookupTableAccount]) {
+    let mut cache = TABLE_CACHE.lock().unwrap();
+    for table in tables {
+        cache.remove(&(chain.to_string(), table.key));
+    }
+}
+
+// 扩展后的地址要到下一个slot才能被交易引用
+async fn wait_until_usable(client: &SolanaProvider, last_extended_slot: u64) -> Result<(), String> {
+    for _ in 0..30 {
+        if client.get_slot("confirmed").await? > last_extended_slot {
+            return Ok(());
+        }
+        sleep(Duration::from_millis(400)).await;
+    }
+    Err("等待查找表生效超时".to_string())
+}
+
+// 分批写入地址，每批一笔交易；首批可附带创建指令
+//
+// 每批确认后立即更新数据库记录，中途失败时已创建的查找表（已支付租金）不会丢失，
+// 错误信息中带有查找表地址，可用追加地址继续写入。
+#[allow(clippy::too_many_arguments)]
+async fn extend_in_chunks(
+    client: &SolanaProvider,
+    chain: &str,
+    keypair: &Keypair,
+    table: Pubkey,
+    label: Option<&str>,
+    existing: usize,
+    mut create: Option<Instruction>,
+    addresses: &[Pubkey],
+) -> Result<Vec<String>, String> {
+    let service = LookupTableService::new(get_database_manager().get_pool());
+    let authority = keypair.pubkey().to_string();
+    let mut written = 0;
+    let mut tx_hashes = Vec::new();
+    let mut chunks: Vec<&[Pubkey]> = addresses.chunks(EXTEND_CHUNK_SIZE).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for chunk in chunks {
+        let mut instructions: Vec<Instruction> = create.take().into_iter().collect();
+        if !chunk.is_empty() {
+            instructions.push(extend_lookup_table(table, keypair.pubkey(), Some(keypair.pubkey()), chunk.to_vec()));
+        }
+        if instructions.is_empty() {
+            continue;
+        }
+        let recent_blockhash = client.get_latest_blockhash().await?;
+        let transaction = build_transaction(keypair, &instructions, &[], recent_blockhash)?;
+        let signature = client.send_and_confirm_transaction(&transaction).await.map_err(|e| {
+            format!("写入查找表 {table} 失败（已完成 {} 笔，已写入 {written} 个地址）: {e}", tx_hashes.len())
+        })?;
+        tx_hashes.push(signature.to_string());
+        written += chunk.len();
+        service
+            .save(chain, &table.to_string(), &authority, label, existing + written)
+            .await
+            .map_err(|e| format!("保存查找表 {table} 记录失败: {e}"))?;
+    }
+    Ok(tx_hashes)
+}
+
+// 去重并排除表内已有的地址
+fn new_addresses(existing: &[Pubkey], addresses: &[String]) -> Result<Vec<Pubkey>, String> {
+    let mut result: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        let pubkey = Pubkey::from_str(address.trim()).map_err(|_| format!("无效的Solana地址: {address}"))?;
+        if !existing.contains(&pubkey) && !result.contains(&pubkey) {
+            result.push(pubkey);
+        }
+    }
+    if existing.len() + result.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(format!(
+            "查找表最多容纳 {LOOKUP_TABLE_MAX_ADDRESSES} 个地址，当前 {} 个，新增 {} 个",
+            existing.len(),
+            result.len()
+        ));
+    }
+    Ok(result)
+}
+
+// 写入完成后刷新缓存与数据库记录
+async fn finish(
+    client: &SolanaProvider,
+    chain: &str,
+    key: Pubkey,
+    label: Option<&str>,
+    added: usize,
+    tx_hashes: Vec<String>,
+) -> Result<LookupTableInfo, String> {
+    let (table, authority, last_extended_slot) = fetch_table(client, &key).await?;
+    wait_until_usable(client, last_extended_slot).await?;
+    let authority = authority.map(|authority| authority.to_string()).unwrap_or_default();
+    LookupTableService::new(get_database_manager().get_pool())
+        .save(chain, &key.to_string(), &authority, label, table.addresses.len())
+        .await
+        .map_err(|e| format!("保存查找表记录失败: {e}"))?;
+
+    let addresses = table.addresses.iter().map(Pubkey::to_string).collect();
+    TABLE_CACHE.lock().unwrap().insert((chain.to_string(), key), (table, Instant::now()));
+    Ok(LookupTableInfo { table_address: key.to_string(), authority, addresses, added, tx_hashes })
+}
+
+// Tauri命令：为常用接收地址创建查找表，由该钱包支付租金并管理
+#[tauri::command]
+pub async fn sol_create_lookup_table(
+    chain: Option<String>,
+    private_key: SecureMemory,
+    addresses: Vec<String>,
+    label: Option<String>,
+) -> Result<LookupTableInfo, String> {
+    let chain = chain.unwrap_or_else(|| "sol".to_string());
+    let client = get_rpc_client(&chain, Some(get_database_manager().get_pool())).await?;
+    let keypair = parse_keypair(&private_key, None)?;
+    let addresses = new_addresses(&[], &addresses)?;
+
+    // 创建指令需要一个较新的slot来派生查找表地址
+    let recent_slot = client.get_slot("finalized").await?;
+    let (create, key) = create_lookup_table(keypair.pubkey(), keypair.pubkey(), recent_slot);
+    let label = label.as_deref().map(str::trim).filter(|label| !label.is_empty());
+    let tx_hashes = extend_in_chunks(&client, &chain, &keypair, key, label, 0, Some(create), &addresses).await?;
+    println!("[Solana] 创建查找表 {key}，写入 {} 个地址", addresses.len());
+
+    finish(&client, &chain, key, label, addresses.len(), tx_hashes).await
+}
+
+// Tauri命令：向已有查找表追加地址，已存在的地址会跳过
+#[tauri::command]
+pub async fn sol_extend_lookup_table(
+    chain: Option<String>,
+    private_key: SecureMemory,
+    table_address: String,
+    addresses: Vec<String>,
+) -> Result<LookupTableInfo, String> {
+    let chain = chain.unwrap_or_else(|| "sol".to_string());
+    let client = get_rpc_client(&chain, Some(get_database_manager().get_pool())).await?;
+    let keypair = parse_keypair(&private_key, None)?;
+    let key = Pubkey::from_str(table_address.trim()).map_err(|_| format!("无效的查找表地址: {table_address}"))?;
+
+    let (table, authority, _) = fetch_table(&client, &key).await?;
+    if authority != Some(keypair.pubkey()) {
+        return Err(format!("该钱包不是查找表 {key} 的管理者"));
+    }
+    let addresses = new_addresses(&table.addresses, &addresses)?;
+    let tx_hashes = extend_in_chunks(&client, &chain, &keypair, key, None, table.addresses.len(), None, &addresses).await?;
+    finish(&client, &chain, key, None, addresses.len(), tx_hashes).await
+}
+
+// Tauri命令：获取已创建的查找表记录
+#[tauri::command]
+pub async fn sol_list_lookup_tables(chain: Option<String>, authority: Option<String>) -> Result<Vec<SolanaLookupTable>, String> {
+    let chain = chain.unwrap_or_else(|| "sol".to_string());
+    LookupTableService::new(get_database_manager().get_pool())
+        .list(&chain, authority.as_deref())
+        .await
+        .map_err(|e| format!("查询查找表失败: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[allow(deprecated)]
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_build_transaction_with_lookup_table() {
+        let payer = Keypair::new();
+        let recipients: Vec<Pubkey> = (0..30).map(|_| Pubkey::new_unique()).collect();
+        let instructions: Vec<Instruction> = recipients
+            .iter()
+            .map(|to| system_instruction::transfer(&payer.pubkey(), to, 1))
+            .collect();
+
+        // 30个接收地址在legacy交易中超出大小限制，通过查找表引用后可以放进一笔交易
+        assert!(build_transaction(&payer, &instructions, &[], Hash::default()).is_err());
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: recipients };
+        let transaction = build_transaction(&payer, &instructions, &[table], Hash::default()).unwrap();
+        match &transaction.message {
+            VersionedMessage::V0(message) => {
+                assert_eq!(message.address_table_lookups.len(), 1);
+                assert_eq!(message.address_table_lookups[0].writable_indexes.len(), 30);
+            }
+            VersionedMessage::Legacy(_) => panic!("expected v0 message"),
+        }
+        assert!(transaction_size(&transaction) <= PACKET_DATA_SIZE);
+
+        let existing = vec![Pubkey::new_unique()];
+        let added = new_addresses(&existing, &[existing[0].to_string(), payer.pubkey().to_string(), payer.pubkey().to_string()]).unwrap();
+        assert_eq!(added, vec![payer.pubkey()]);
+    }
+}
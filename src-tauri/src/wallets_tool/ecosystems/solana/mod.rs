@@ -1,3 +1,9 @@
 pub mod transfer;
 pub mod provider;
+pub mod sns;
+pub mod lookup_table;
+pub mod batch_transfer;
+pub mod priority_fee;
+pub mod token_program;
+pub mod keypair;
 pub use provider::test_solana_rpc_connection;
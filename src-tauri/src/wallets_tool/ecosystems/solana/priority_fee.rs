@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+#[allow(deprecated)]
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Keypair,
+};
+use std::str::FromStr;
+use crate::database::get_database_manager;
+use crate::wallets_tool::ecosystems::solana::lookup_table::build_transaction;
+use crate::wallets_tool::ecosystems::solana::provider::{SolanaProvider, get_rpc_client};
+use crate::wallets_tool::ecosystems::solana::transfer::TransferConfig;
+
+// 单笔交易的计算单元上限
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+// 未设置上限时估算单价的封顶值（微lamports/CU）
+pub const DEFAULT_MAX_PRIORITY_FEE: u64 = 1_000_000;
+
+/// 优先费档位，对应近期优先费的百分位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityFeeTier {
+    Low,
+    Medium,
+    High,
+    Turbo,
+}
+
+impl PriorityFeeTier {
+    pub const ALL: [PriorityFeeTier; 4] = [Self::Low, Self::Medium, Self::High, Self::Turbo];
+
+    pub fn percentile(self) -> usize {
+        match self {
+            Self::Low => 25,
+            Self::Medium => 50,
+            Self::High => 75,
+            Self::Turbo => 95,
+        }
+    }
+}
+
+impl FromStr for PriorityFeeTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            "turbo" => Ok(Self::Turbo),
+            other => Err(format!("不支持的优先费档位: {other}")),
+        }
+    }
+}
+
+/// 按最近邻秩法取百分位，空列表返回0
+pub fn fee_percentile(fees: &[u64], percentile: usize) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    let mut sorted = fees.to_vec();
+    sorted.sort_unstable();
+    let rank = (percentile.min(100) * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// 交易的计算预算：单元上限与单价（微lamports/CU）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    /// 在业务指令前加上计算预算指令
+    pub fn apply(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut result = vec![ComputeBudgetInstruction::set_compute_unit_limit(self.unit_limit)];
+        if let Some(price) = self.unit_price {
+            result.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        result.extend_from_slice(instructions);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityFeeEstimate {
+    pub tier: PriorityFeeTier,
+    pub unit_price: u64,
+}
+
+/// 指令中会被写入的账户，用于查询这些账户上的优先费竞争情况
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in instructions.iter().flat_map(|instruction| &instruction.accounts) {
+        if meta.is_writable && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts
+}
+
+/// 按档位估算计算单元价格，结果不超过 max_price
+pub async fn estimate_unit_price(client: &SolanaProvider, accounts: &[Pubkey], tier: PriorityFeeTier, max_price: u64) -> Result<u64, String> {
+    let fees = client.get_recent_prioritization_fees(accounts).await?;
+    Ok(fee_percentile(&fees, tier.percentile()).min(max_price))
+}
+
+/// 是否付优先费：填写了 gas_price 或选择了档位（同时校验档位名称）
+pub fn uses_priority_fee(config: &TransferConfig) -> Result<bool, String> {
+    if config.gas_price.is_some_and(|price| price > 0) {
+        return Ok(true);
+    }
+    match config.priority_fee_tier.as_deref().filter(|tier| !tier.is_empty()) {
+        Some(tier) => PriorityFeeTier::from_str(tier).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// 确定计算单元单价：手动填写的 gas_price 优先，否则按档位估算，都没有时不付优先费
+pub async fn resolve_unit_price(
+    client: &SolanaProvider,
+    config: &TransferConfig,
+    writable: &[Pubkey],
+) -> Result<Option<u64>, String> {
+    if let Some(price) = config.gas_price.filter(|price| *price > 0) {
+        return Ok(Some(price));
+    }
+    let Some(tier) = config.priority_fee_tier.as_deref().filter(|tier| !tier.is_empty()) else {
+        return Ok(None);
+    };
+    let tier = PriorityFeeTier::from_str(tier)?;
+    let max_price = config.max_priority_fee.unwrap_or(DEFAULT_MAX_PRIORITY_FEE);
+    let price = estimate_unit_price(client, writable, tier, max_price).await?;
+    Ok(Some(price).filter(|price| *price > 0))
+}
+
+// 模拟消耗加一成余量，并覆盖计算预算指令自身的消耗
+fn unit_limit_from_simulation(units_consumed: u64) -> u32 {
+    let limit = units_consumed + (units_consumed / 10).max(300);
+    limit.min(MAX_COMPUTE_UNITS as u64) as u32
+}
+
+/// 模拟交易得到所需计算单元
+pub async fn simulate_unit_limit(
+    client: &SolanaProvider,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    tables: &[AddressLookupTableAccount],
+    unit_price: Option<u64>,
+) -> Result<u32, String> {
+    let probe = ComputeBudget { unit_limit: MAX_COMPUTE_UNITS, unit_price };
+    let transaction = build_transaction(payer, &probe.apply(instructions), tables, Hash::default())?;
+    let units_consumed = client.simulate_compute_units(&transaction).await?;
+    Ok(unit_limit_from_simulation(units_consumed))
+}
+
+/// 为一组业务指令确定计算预算
+pub async fn plan_compute_budget(
+    client: &SolanaProvider,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    tables: &[AddressLookupTableAccount],
+    config: &TransferConfig,
+) -> Result<ComputeBudget, String> {
+    let unit_price = resolve_unit_price(client, config, &writable_accounts(instructions)).await?;
+    let unit_limit = simulate_unit_limit(client, payer, instructions, tables, unit_price).await?;
+    Ok(ComputeBudget { unit_limit, unit_price })
+}
+
+// Tauri命令：查询各档位的优先费估算，accounts 为将被写入的账户
+#[tauri::command]
+pub async fn sol_estimate_priority_fees(
+    chain: Option<String>,
+    accounts: Vec<String>,
+    max_priority_fee: Option<u64>,
+) -> Result<Vec<PriorityFeeEstimate>, String> {
+    let chain = chain.unwrap_or_else(|| "sol".to_string());
+    let client = get_rpc_client(&chain, Some(get_database_manager().get_pool())).await?;
+    let accounts = accounts
+        .iter()
+        .map(|account| Pubkey::from_str(account.trim()).map_err(|_| format!("无效的Solana地址: {account}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let fees = client.get_recent_prioritization_fees(&accounts).await?;
+    let max_price = max_priority_fee.unwrap_or(DEFAULT_MAX_PRIORITY_FEE);
+    Ok(PriorityFeeTier::ALL
+        .iter()
+        .map(|tier| PriorityFeeEstimate {
+            tier: *tier,
+            unit_price: fee_percentile(&fees, tier.percentile()).min(max_price),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_tiers() {
+        let fees = [0, 0, 100, 200, 300, 400, 500, 600, 700, 10_000];
+        assert_eq!(fee_percentile(&fees, PriorityFeeTier::Low.percentile()), 100);
+        assert_eq!(fee_percentile(&fees, PriorityFeeTier::Medium.percentile()), 300);
+        assert_eq!(fee_percentile(&fees, PriorityFeeTier::High.percentile()), 600);
+        assert_eq!(fee_percentile(&fees, PriorityFeeTier::Turbo.percentile()), 10_000);
+        assert_eq!(fee_percentile(&[], 50), 0);
+        assert_eq!("High".parse::<PriorityFeeTier>().unwrap(), PriorityFeeTier::High);
+
+        assert_eq!(unit_limit_from_simulation(150), 450);
+        assert_eq!(unit_limit_from_simulation(50_000), 55_000);
+        assert_eq!(unit_limit_from_simulation(2_000_000), MAX_COMPUTE_UNITS);
+    }
+}
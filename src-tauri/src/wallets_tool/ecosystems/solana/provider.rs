@@ -2,9 +2,10 @@ use reqwest::Client;
 use serde_json::{json, Value};
 use solana_sdk::{
     hash::Hash,
+    message::VersionedMessage,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::Transaction,
+    transaction::VersionedTransaction,
 };
 use sqlx::{SqlitePool, Row};
 use std::str::FromStr;
@@ -16,6 +17,9 @@ use crate::wallets_tool::ecosystems::ethereum::proxy_manager::PROXY_MANAGER;
 
 static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
+// getRecentPrioritizationFees 单次最多查询的账户数
+pub const MAX_FEE_ACCOUNTS: usize = 128;
+
 #[derive(Debug, Serialize)]
 pub struct RpcTestResult {
     pub success: bool,
@@ -93,21 +97,55 @@ impl SolanaProvider {
         Hash::from_str(hash_str).map_err(|e| e.to_string())
     }
 
+    pub async fn get_slot(&self, commitment: &str) -> Result<u64, String> {
+        let res = self.request("getSlot", json!([{"commitment": commitment}])).await?;
+        res.as_u64().ok_or_else(|| "无效的slot格式".to_string())
+    }
+
+    pub async fn get_epoch(&self) -> Result<u64, String> {
+        let res = self.request("getEpochInfo", json!([{"commitment": "confirmed"}])).await?;
+        res["epoch"].as_u64().ok_or_else(|| "无效的epoch格式".to_string())
+    }
+
     pub async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64, String> {
         let res = self.request("getBalance", json!([pubkey.to_string(), {"commitment": "confirmed"}])).await?;
         res["value"].as_u64().ok_or_else(|| "无效的余额格式".to_string())
     }
 
+    pub async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64, String> {
+        let res = self.request("getMinimumBalanceForRentExemption", json!([data_len])).await?;
+        res.as_u64().ok_or_else(|| "无效的租金格式".to_string())
+    }
+
     pub async fn get_account(&self, pubkey: &Pubkey) -> Result<Value, String> {
          self.request("getAccountInfo", json!([pubkey.to_string(), {"encoding": "base64", "commitment": "confirmed"}])).await
     }
 
+    // getMultipleAccounts 单次最多100个账户，按顺序返回，不存在的账户为 null
+    pub async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Value>, String> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(100) {
+            let keys: Vec<String> = chunk.iter().map(Pubkey::to_string).collect();
+            let res = self.request("getMultipleAccounts", json!([keys, {"encoding": "base64", "commitment": "confirmed"}])).await?;
+            let values = res["value"].as_array().ok_or("无效的账户列表响应")?;
+            accounts.extend(values.iter().cloned());
+        }
+        Ok(accounts)
+    }
+
+    // 代币余额最多的20个账户，NFT 只有一个账户余额为1
+    pub async fn get_token_largest_accounts(&self, mint: &Pubkey) -> Result<Vec<Value>, String> {
+        let res = self.request("getTokenLargestAccounts", json!([mint.to_string(), {"commitment": "confirmed"}])).await?;
+        res["value"].as_array().cloned().ok_or_else(|| "无效的代币账户列表响应".to_string())
+    }
+
     pub async fn get_token_account_balance(&self, pubkey: &Pubkey) -> Result<TokenBalance, String> {
         let res = self.request("getTokenAccountBalance", json!([pubkey.to_string()])).await?;
         serde_json::from_value(res["value"].clone()).map_err(|e| format!("解析Token余额失败: {}", e))
     }
 
-    pub async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, String> {
+    // legacy交易通过 VersionedTransaction::from 转换后发送，两者的线上格式一致
+    pub async fn send_transaction(&self, transaction: &VersionedTransaction) -> Result<Signature, String> {
         let serialized = bincode::serialize(transaction).map_err(|e| e.to_string())?;
         let base64_tx = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, serialized);
         
@@ -136,14 +174,56 @@ impl SolanaProvider {
         Err("交易确认超时".to_string())
     }
 
-    pub async fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<Signature, String> {
+    pub async fn send_and_confirm_transaction(&self, transaction: &VersionedTransaction) -> Result<Signature, String> {
         let sig = self.send_transaction(transaction).await?;
         self.confirm_transaction(&sig).await?;
         Ok(sig)
     }
 
+    // 最近150个slot内写入这些账户的交易所付的优先费（微lamports/CU），账户超过上限时报错
+    pub async fn get_recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, String> {
+        if accounts.len() > MAX_FEE_ACCOUNTS {
+            return Err(format!("优先费查询最多支持 {MAX_FEE_ACCOUNTS} 个账户，实际 {} 个", accounts.len()));
+        }
+        let keys: Vec<String> = accounts.iter().map(Pubkey::to_string).collect();
+        let res = self.request("getRecentPrioritizationFees", json!([keys])).await?;
+        let fees = res.as_array().ok_or("无效的优先费响应")?;
+        Ok(fees.iter().filter_map(|fee| fee["prioritizationFee"].as_u64()).collect())
+    }
+
+    /// 模拟交易并返回消耗的计算单元，模拟失败时返回错误及最后几行日志
+    pub async fn simulate_compute_units(&self, transaction: &VersionedTransaction) -> Result<u64, String> {
+        let serialized = bincode::serialize(transaction).map_err(|e| e.to_string())?;
+        let base64_tx = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, serialized);
+        let res = self.request("simulateTransaction", json!([base64_tx, {
+            "encoding": "base64",
+            "sigVerify": false,
+            "replaceRecentBlockhash": true,
+            "commitment": "confirmed"
+        }])).await?;
+
+        let value = &res["value"];
+        if !value["err"].is_null() {
+            let logs: Vec<&str> = value["logs"]
+                .as_array()
+                .map(|logs| logs.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let tail = logs[logs.len().saturating_sub(3)..].join("; ");
+            return Err(format!("交易模拟失败: {} {}", value["err"], tail));
+        }
+        value["unitsConsumed"].as_u64().ok_or_else(|| "模拟结果缺少计算单元消耗".to_string())
+    }
+
+    /// 查询消息的实际手续费（基础费用加优先费），blockhash 过期时报错
+    pub async fn get_fee_for_message(&self, message: &VersionedMessage) -> Result<u64, String> {
+        let base64_msg = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, message.serialize());
+        let res = self.request("getFeeForMessage", json!([base64_msg, {"commitment": "confirmed"}])).await?;
+        res["value"].as_u64().ok_or_else(|| "无法获取交易手续费（blockhash可能已过期）".to_string())
+    }
+
     pub async fn get_signature_statuses_batch(&self, signatures: &[String]) -> Result<Vec<Value>, String> {
         // Solana RPC getSignatureStatuses supports up to 256 signatures
+        // 签名状态与交易版本无关，legacy与v0交易都可以直接查询
         let res = self.request("getSignatureStatuses", json!([signatures, {"searchTransactionHistory": true}])).await?;
         res["value"].as_array().cloned().ok_or_else(|| "无效的签名状态响应".to_string())
     }
@@ -0,0 +1,296 @@
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{LazyLock, Mutex};
+use crate::database::get_database_manager;
+use crate::wallets_tool::ecosystems::solana::provider::{SolanaProvider, get_rpc_client};
+
+// SPL Name Service 程序及 .sol 顶级域账户
+const NAME_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+const SOL_TLD: Pubkey = solana_sdk::pubkey!("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+const HASH_PREFIX: &str = "SPL Name Service";
+// 记录 v2 账户的 class，以及域名代币化（NFT）程序
+const SNS_RECORDS_CLASS: Pubkey = solana_sdk::pubkey!("2pMnqHvei2N5oDcVGCRdZx48gqti199wr5CsyTTafsbo");
+const NAME_TOKENIZER_ID: Pubkey = solana_sdk::pubkey!("nftD3vbNkNqfj2Sd3HZwbpw4BxxKWr4AjGb9X38JeZk");
+const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+// 域名账户数据头：parent_name(32) + owner(32) + class(32)
+const OWNER_OFFSET: usize = 32;
+const NAME_HEADER_LEN: usize = 96;
+
+// 记录 v2 的校验类型：0 无，1 Solana，2 以太坊，3 未签名的 Solana 地址
+const VALIDATION_SOLANA: u16 = 1;
+
+// 已锁定的解析结果按批次（窗口ID）隔离：窗口 -> (链, 名称) -> 地址，批次结束后释放；
+// 没有窗口ID的调用无法释放，只解析不锁定
+type LockedBatch = HashMap<(String, String), Pubkey>;
+static LOCKED_NAMES: LazyLock<Mutex<HashMap<String, LockedBatch>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnsResolution {
+    pub name: String,
+    pub address: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+/// 是否为 .sol 域名
+pub fn is_sns_name(value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    value.ends_with(".sol") && value.len() > 4
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+fn name_account(hashed_name: &[u8], class: &Pubkey, parent: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[hashed_name, class.as_ref(), parent.as_ref()], &NAME_PROGRAM_ID).0
+}
+
+fn hashed_name(name: &str) -> [u8; 32] {
+    hashv(&[HASH_PREFIX.as_bytes(), name.as_bytes()]).to_bytes()
+}
+
+/// 域名对应的链上账户：一级域名挂在 .sol 下，子域名的标签前加 \0 后挂在父域名下
+pub fn domain_key(name: &str) -> Result<Pubkey, String> {
+    let name = normalize_name(name);
+    let labels: Vec<&str> = name.trim_end_matches(".sol").split('.').collect();
+    let (domain, sub) = match labels.as_slice() {
+        [domain] => (*domain, None),
+        [sub, domain] => (*domain, Some(*sub)),
+        _ => return Err(format!("不支持的域名格式: {name}")),
+    };
+    if domain.is_empty() || sub.is_some_and(str::is_empty) {
+        return Err(format!("不支持的域名格式: {name}"));
+    }
+
+    let domain_key = name_account(&hashed_name(domain), &Pubkey::default(), &SOL_TLD);
+    Ok(match sub {
+        Some(sub) => name_account(&hashed_name(&format!("\0{sub}")), &Pubkey::default(), &domain_key),
+        None => domain_key,
+    })
+}
+
+/// 域名的 SOL 记录（v2）账户
+fn sol_record_key(domain_key: &Pubkey) -> Pubkey {
+    name_account(&hashed_name("\x02SOL"), &SNS_RECORDS_CLASS, domain_key)
+}
+
+/// 域名代币化后持有域名账户的 NFT 记录，以及对应的 NFT mint
+fn nft_record_key(domain_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"nft_record", domain_key.as_ref()], &NAME_TOKENIZER_ID).0
+}
+
+fn nft_mint(domain_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"tokenized_name", domain_key.as_ref()], &NAME_TOKENIZER_ID).0
+}
+
+fn account_data(account: &Value) -> Option<Vec<u8>> {
+    let data = account["data"][0].as_str()?;
+    base64::engine::general_purpose::STANDARD.decode(data).ok()
+}
+
+fn pubkey_at(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(offset..offset + 32)?).ok()
+}
+
+fn validation_len(validation: u16) -> Option<usize> {
+    match validation {
+        0 => Some(0),
+        1 | 3 => Some(32),
+        2 => Some(20),
+        _ => None,
+    }
+}
+
+/// 读取 SOL 记录 v2 的地址：记录须由当前所有者写入（未过期），且内容地址签名确认过关联，否则忽略
+/// 数据布局：域名账户头(96) + staleness校验(u16) + 关联校验(u16) + 内容长度(u32) + staleness_id + roa_id + 内容
+fn sol_record_address(data: &[u8], owner: &Pubkey) -> Option<Pubkey> {
+    let header = data.get(NAME_HEADER_LEN..NAME_HEADER_LEN + 8)?;
+    let staleness = u16::from_le_bytes([header[0], header[1]]);
+    let roa = u16::from_le_bytes([header[2], header[3]]);
+    let content_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let body = data.get(NAME_HEADER_LEN + 8..)?;
+    let (staleness_id, rest) = body.split_at_checked(validation_len(staleness)?)?;
+    let (roa_id, rest) = rest.split_at_checked(validation_len(roa)?)?;
+    let content = rest.get(..content_len)?;
+    if staleness != VALIDATION_SOLANA || staleness_id != owner.as_ref() || roa != VALIDATION_SOLANA || roa_id != content {
+        return None;
+    }
+    Pubkey::try_from(content).ok()
+}
+
+/// 已代币化域名的 NFT 当前持有人
+async fn nft_holder(client: &SolanaProvider, name: &str, domain_key: &Pubkey) -> Result<Pubkey, String> {
+    let largest = client.get_token_largest_accounts(&nft_mint(domain_key)).await?;
+    let token_account = largest
+        .iter()
+        .find(|account| account["amount"].as_str() == Some("1"))
+        .and_then(|account| Pubkey::from_str(account["address"].as_str()?).ok())
+        .ok_or_else(|| format!("{name} 已代币化，但找不到 NFT 持有人"))?;
+    // 代币账户数据：mint(32) + owner(32)
+    let account = client.get_account(&token_account).await?;
+    account_data(&account["value"])
+        .and_then(|data| pubkey_at(&data, 32))
+        .ok_or_else(|| format!("{name} 的 NFT 账户数据格式错误"))
+}
+
+/// 按 SNS 的解析顺序确定接收地址：SOL 记录 v2 → 代币化域名的 NFT 持有人 → 域名账户所有者
+async fn resolve_owner(client: &SolanaProvider, name: &str) -> Result<Pubkey, String> {
+    let key = domain_key(name)?;
+    let accounts = client.get_multiple_accounts(&[key, sol_record_key(&key)]).await?;
+    let registry = accounts
+        .first()
+        .and_then(account_data)
+        .ok_or_else(|| format!("{name} 未注册"))?;
+    let registry_owner = pubkey_at(&registry, OWNER_OFFSET).ok_or_else(|| format!("{name} 的账户数据格式错误"))?;
+
+    // 代币化后域名账户归 NFT 记录所有，实际所有者为 NFT 持有人
+    let owner = if registry_owner == nft_record_key(&key) {
+        nft_holder(client, name, &key).await?
+    } else {
+        registry_owner
+    };
+    let address = accounts
+        .get(1)
+        .and_then(account_data)
+        .and_then(|data| sol_record_address(&data, &owner))
+        .unwrap_or(owner);
+    ensure_wallet(client, name, &address).await?;
+    Ok(address)
+}
+
+/// 拒绝不能作为接收地址的解析结果：空地址、程序派生地址（不在曲线上）、由其它程序持有的账户
+async fn ensure_wallet(client: &SolanaProvider, name: &str, address: &Pubkey) -> Result<(), String> {
+    if *address == Pubkey::default() {
+        return Err(format!("{name} 没有所有者"));
+    }
+    if !address.is_on_curve() {
+        return Err(format!("{name} 解析到程序派生地址 {address}，不能作为接收地址"));
+    }
+    let account = client.get_account(address).await?;
+    let program = account["value"]["owner"].as_str();
+    if program.is_some_and(|program| program != SYSTEM_PROGRAM_ID) {
+        return Err(format!("{name} 解析到的地址 {address} 由程序 {} 持有，不能作为接收地址", program.unwrap_or_default()));
+    }
+    Ok(())
+}
+
+fn lock_name(chain: &str, window_id: Option<&str>, name: String, owner: Pubkey) {
+    if let Some(window_id) = window_id {
+        LOCKED_NAMES.lock().unwrap().entry(window_id.to_string()).or_default().insert((chain.to_string(), name), owner);
+    }
+}
+
+fn locked_owner(chain: &str, window_id: Option<&str>, name: &str) -> Option<Pubkey> {
+    LOCKED_NAMES.lock().unwrap().get(window_id?)?.get(&(chain.to_string(), name.to_string())).copied()
+}
+
+/// 解析一批 .sol 域名并锁定到窗口对应的批次，已锁定的同名结果会被刷新
+pub async fn resolve_and_lock(chain: &str, window_id: Option<&str>, names: &[String]) -> Vec<Result<Pubkey, String>> {
+    let client = match get_rpc_client(chain, Some(get_database_manager().get_pool())).await {
+        Ok(client) => client,
+        Err(e) => return vec![Err(format!("RPC连接失败: {e}")); names.len()],
+    };
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        let name = normalize_name(name);
+        let result = resolve_owner(&client, &name).await;
+        if let Ok(owner) = &result {
+            lock_name(chain, window_id, name, *owner);
+        }
+        results.push(result);
+    }
+    results
+}
+
+/// 释放窗口对应批次锁定的解析结果
+pub fn release_locked_names(window_id: &str) {
+    LOCKED_NAMES.lock().unwrap().remove(window_id);
+}
+
+/// 解析接收地址：base58地址直接使用，.sol 域名优先使用本批次已锁定的解析结果
+pub async fn resolve_recipient(chain: &str, window_id: Option<&str>, to_addr: &str) -> Result<Pubkey, String> {
+    let to_addr = to_addr.trim();
+    if !is_sns_name(to_addr) {
+        return Pubkey::from_str(to_addr).map_err(|_| "Invalid To Address".to_string());
+    }
+    let name = normalize_name(to_addr);
+    if let Some(owner) = locked_owner(chain, window_id, &name) {
+        return Ok(owner);
+    }
+    resolve_and_lock(chain, window_id, &[name])
+        .await
+        .pop()
+        .unwrap_or_else(|| Err("缺少RPC响应".to_string()))
+}
+
+// Tauri命令：批量开始前解析并锁定接收地址中的 .sol 域名
+#[tauri::command]
+pub async fn sol_resolve_recipient_names(
+    chain: Option<String>,
+    names: Vec<String>,
+    window_id: Option<String>,
+) -> Result<Vec<SnsResolution>, String> {
+    let chain = chain.unwrap_or_else(|| "sol".to_string());
+    let names: Vec<String> = names.into_iter().filter(|name| is_sns_name(name)).collect();
+    let results = resolve_and_lock(&chain, window_id.as_deref(), &names).await;
+    Ok(names
+        .into_iter()
+        .zip(results)
+        .map(|(name, result)| SnsResolution {
+            name,
+            address: result.as_ref().ok().map(Pubkey::to_string),
+            error_msg: result.err(),
+        })
+        .collect())
+}
+
+// Tauri命令：批量结束后释放本窗口锁定的 .sol 解析结果
+#[tauri::command]
+pub async fn sol_release_recipient_names(window_id: String) -> Result<(), String> {
+    release_locked_names(&window_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_key() {
+        assert_eq!(domain_key("bonfida.sol").unwrap().to_string(), "Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb");
+        assert_eq!(domain_key("Bonfida.SOL").unwrap(), domain_key("bonfida.sol").unwrap());
+        assert_ne!(domain_key("dex.bonfida.sol").unwrap(), domain_key("bonfida.sol").unwrap());
+        assert!(domain_key("a.b.c.sol").is_err());
+        assert!(is_sns_name("bonfida.sol"));
+        assert!(!is_sns_name("Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb"));
+    }
+
+    #[test]
+    fn test_sol_record_address() {
+        let owner = Pubkey::new_unique();
+        let target = Pubkey::new_unique();
+        let record = |staleness_id: &Pubkey, roa_id: &Pubkey| {
+            let mut data = vec![0u8; NAME_HEADER_LEN];
+            data.extend_from_slice(&VALIDATION_SOLANA.to_le_bytes());
+            data.extend_from_slice(&VALIDATION_SOLANA.to_le_bytes());
+            data.extend_from_slice(&32u32.to_le_bytes());
+            data.extend_from_slice(staleness_id.as_ref());
+            data.extend_from_slice(roa_id.as_ref());
+            data.extend_from_slice(target.as_ref());
+            data
+        };
+        assert_eq!(sol_record_address(&record(&owner, &target), &owner), Some(target));
+        // 域名转手后旧所有者写入的记录已过期
+        assert_eq!(sol_record_address(&record(&owner, &target), &Pubkey::new_unique()), None);
+        // 目标地址未签名确认关联
+        assert_eq!(sol_record_address(&record(&owner, &owner), &owner), None);
+        assert_eq!(sol_record_address(&record(&owner, &target)[..120], &owner), None);
+    }
+}
@@ -0,0 +1,224 @@
+use base64::Engine;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use solana_sdk::program_pack::Pack;
+use spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    non_transferable::NonTransferable,
+    pausable::PausableConfig,
+    transfer_fee::{TransferFeeConfig, instruction::transfer_checked_with_fee},
+    transfer_hook::TransferHook,
+};
+use std::str::FromStr;
+use crate::wallets_tool::ecosystems::solana::provider::SolanaProvider;
+
+/// 代币Mint信息：所属程序（经典 SPL Token 或 Token-2022）、精度及转账手续费配置
+#[derive(Debug, Clone)]
+pub struct MintInfo {
+    pub mint: Pubkey,
+    pub program_id: Pubkey,
+    pub decimals: u8,
+    transfer_fee: Option<TransferFeeConfig>,
+    restriction: Option<&'static str>,
+    account_len: usize,
+}
+
+impl MintInfo {
+    /// 解析Mint账户数据，owner 为账户所属程序
+    pub fn parse(mint: Pubkey, owner: Pubkey, data: &[u8]) -> Result<Self, String> {
+        if owner != spl_token::id() && owner != spl_token_2022::id() {
+            return Err(format!("{mint} 不是SPL代币Mint账户（所属程序: {owner}）"));
+        }
+        // Token-2022 的Mint在经典布局之后附加扩展，经典Mint没有扩展
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)
+            .map_err(|_| "无法解析Mint数据".to_string())?;
+
+        // 不能转账的扩展只影响转账，余额查询仍然可用
+        let restriction = if state.get_extension::<NonTransferable>().is_ok() {
+            Some("该代币不可转让")
+        } else if state
+            .get_extension::<TransferHook>()
+            .is_ok_and(|hook| Option::<Pubkey>::from(hook.program_id).is_some())
+        {
+            Some("暂不支持带转账钩子（Transfer Hook）的代币")
+        } else if state
+            .get_extension::<PausableConfig>()
+            .is_ok_and(|pausable| bool::from(pausable.paused))
+        {
+            Some("该代币已暂停转账")
+        } else {
+            None
+        };
+
+        // 关联账户的大小：Token-2022 账户带有Mint扩展要求的账户扩展，关联账户程序还会加上 ImmutableOwner
+        let account_len = if owner == spl_token_2022::id() {
+            let mint_extensions = state.get_extension_types().map_err(|_| "无法解析Mint扩展".to_string())?;
+            let mut extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+            if !extensions.contains(&ExtensionType::ImmutableOwner) {
+                extensions.push(ExtensionType::ImmutableOwner);
+            }
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)
+                .map_err(|_| "无法计算代币账户大小".to_string())?
+        } else {
+            spl_token::state::Account::LEN
+        };
+
+        Ok(Self {
+            mint,
+            program_id: owner,
+            decimals: state.base.decimals,
+            transfer_fee: state.get_extension::<TransferFeeConfig>().ok().copied(),
+            restriction,
+            account_len,
+        })
+    }
+
+    pub fn has_transfer_fee(&self) -> bool {
+        self.transfer_fee.is_some()
+    }
+
+    /// 新建关联代币账户的数据大小（字节），用于计算需要预存的租金
+    pub fn account_len(&self) -> usize {
+        self.account_len
+    }
+
+    /// 钱包在该代币程序下的关联代币账户
+    pub fn associated_address(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.program_id)
+    }
+
+    pub fn create_associated_account(&self, payer: &Pubkey, owner: &Pubkey) -> Instruction {
+        create_associated_token_account_idempotent(payer, owner, &self.mint, &self.program_id)
+    }
+
+    /// 转出 amount 时被扣留的手续费
+    pub fn transfer_fee(&self, epoch: u64, amount: u64) -> u64 {
+        self.transfer_fee
+            .and_then(|config| config.calculate_epoch_fee(epoch, amount))
+            .unwrap_or(0)
+    }
+
+    /// 让接收方实际到账 net_amount 所需转出的数量（含手续费）
+    pub fn gross_amount(&self, epoch: u64, net_amount: u64) -> Result<u64, String> {
+        match &self.transfer_fee {
+            Some(config) => config
+                .get_epoch_fee(epoch)
+                .calculate_pre_fee_amount(net_amount)
+                .ok_or_else(|| "转账手续费计算溢出".to_string()),
+            None => Ok(net_amount),
+        }
+    }
+
+    /// 构建转账指令：统一使用 transfer_checked，有转账手续费时带上预期手续费，手续费变化时交易会失败
+    pub fn transfer_instruction(
+        &self,
+        source: &Pubkey,
+        destination: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+        epoch: u64,
+    ) -> Result<Instruction, String> {
+        if let Some(restriction) = self.restriction {
+            return Err(restriction.to_string());
+        }
+        let instruction = if self.has_transfer_fee() {
+            transfer_checked_with_fee(
+                &self.program_id,
+                source,
+                &self.mint,
+                destination,
+                authority,
+                &[],
+                amount,
+                self.decimals,
+                self.transfer_fee(epoch, amount),
+            )
+        } else {
+            spl_token_2022::instruction::transfer_checked(
+                &self.program_id,
+                source,
+                &self.mint,
+                destination,
+                authority,
+                &[],
+                amount,
+                self.decimals,
+            )
+        };
+        instruction.map_err(|e| e.to_string())
+    }
+}
+
+/// 读取链上Mint账户并识别所属代币程序
+pub async fn load_mint(client: &SolanaProvider, mint: &str) -> Result<MintInfo, String> {
+    let mint = Pubkey::from_str(mint.trim()).map_err(|_| "Invalid Mint Address".to_string())?;
+    let account_info = client.get_account(&mint).await.map_err(|e| format!("无法获取代币信息: {e}"))?;
+    if account_info["value"].is_null() {
+        return Err("代币Mint账户不存在".to_string());
+    }
+    let owner = account_info["value"]["owner"]
+        .as_str()
+        .and_then(|owner| Pubkey::from_str(owner).ok())
+        .ok_or("无效的代币数据格式")?;
+    let data_str = account_info["value"]["data"][0].as_str().ok_or("无效的代币数据格式")?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data_str)
+        .map_err(|_| "Base64解码失败")?;
+    MintInfo::parse(mint, owner, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::{StateWithExtensionsMut, BaseStateWithExtensionsMut};
+
+    #[test]
+    fn test_parse_mint() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        // 经典 SPL Token Mint
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint { decimals: 6, is_initialized: true, ..Default::default() }.pack_into_slice(&mut data);
+        let classic = MintInfo::parse(mint, spl_token::id(), &data).unwrap();
+        assert_eq!(classic.program_id, spl_token::id());
+        assert_eq!(classic.decimals, 6);
+        assert_eq!(classic.gross_amount(0, 1_000).unwrap(), 1_000);
+        assert_eq!(classic.account_len(), 165);
+        assert_eq!(classic.associated_address(&owner), spl_associated_token_account::get_associated_token_address(&owner, &mint));
+        assert!(MintInfo::parse(mint, Pubkey::new_unique(), &data).is_err());
+
+        // 带1%转账手续费（上限 5_000）的 Token-2022 Mint
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0u8; len];
+        let mut state = StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data).unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+        config.newer_transfer_fee.maximum_fee = 5_000.into();
+        config.older_transfer_fee = config.newer_transfer_fee;
+        state.base = spl_token_2022::state::Mint { decimals: 9, is_initialized: true, ..Default::default() };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let token_2022 = MintInfo::parse(mint, spl_token_2022::id(), &data).unwrap();
+        assert_eq!(token_2022.program_id, spl_token_2022::id());
+        assert_eq!(token_2022.decimals, 9);
+        assert_eq!(token_2022.transfer_fee(0, 10_000), 100);
+        let gross = token_2022.gross_amount(0, 9_900).unwrap();
+        assert_eq!(gross - token_2022.transfer_fee(0, gross), 9_900);
+        assert_eq!(token_2022.transfer_fee(0, 10_000_000), 5_000);
+        // 经典账户165字节 + 账户类型 + ImmutableOwner 与 TransferFeeAmount 扩展
+        let expected_len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+            ExtensionType::TransferFeeAmount,
+            ExtensionType::ImmutableOwner,
+        ])
+        .unwrap();
+        assert_eq!(token_2022.account_len(), expected_len);
+        assert!(expected_len > 165);
+        assert_ne!(token_2022.associated_address(&owner), classic.associated_address(&owner));
+        assert!(token_2022.transfer_instruction(&owner, &owner, &owner, gross, 0).is_ok());
+    }
+}
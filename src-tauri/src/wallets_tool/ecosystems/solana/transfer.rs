@@ -3,39 +3,46 @@ use crate::wallets_tool::security::SecureMemory;
 use crate::database::chain_service::ChainService;
 #[allow(deprecated)]
 use solana_sdk::{
-    signature::{Keypair, Signer},
+    signature::Signer,
     pubkey::Pubkey,
     system_instruction,
-    transaction::Transaction,
-    compute_budget::ComputeBudgetInstruction,
-    program_pack::Pack,
-};
-use spl_token::state::Mint;
-use spl_associated_token_account::{
-    get_associated_token_address,
-    instruction::create_associated_token_account,
 };
 use std::str::FromStr;
 use crate::wallets_tool::ecosystems::solana::provider::get_rpc_client;
+use crate::wallets_tool::ecosystems::solana::sns::resolve_recipient;
+use crate::wallets_tool::ecosystems::solana::lookup_table::{build_transaction, invalidate_lookup_tables, load_lookup_tables};
+use crate::wallets_tool::ecosystems::solana::priority_fee::plan_compute_budget;
+use crate::wallets_tool::ecosystems::solana::token_program::load_mint;
+use crate::wallets_tool::ecosystems::solana::keypair::parse_keypair;
+use crate::wallets_tool::address_book::{group_recipient, resolve_group_addresses};
 use tauri::Emitter;
 use serde_json::json;
-use base64::Engine;
 
 #[derive(Deserialize)]
 pub struct TransferItem {
     pub private_key: SecureMemory,
     pub to_addr: String,
     pub amount: Option<String>,
+    #[serde(default)]
+    pub derivation_index: Option<u32>, // 助记词的账户序号 n（m/44'/501'/n'/0'）
 }
 
 #[derive(Deserialize)]
 pub struct TransferConfig {
     pub transfer_amount: f64,
-    pub gas_price: Option<u64>,
+    pub gas_price: Option<u64>, // 手动指定的计算单元价格（微lamports/CU），优先于档位估算
+    #[serde(default)]
+    pub priority_fee_tier: Option<String>, // low / medium / high / turbo，按近期优先费估算单价
+    #[serde(default)]
+    pub max_priority_fee: Option<u64>, // 估算单价的上限（微lamports/CU）
     pub contract_address: Option<String>,
     #[allow(dead_code)]
     pub amount_precision: Option<u8>,
     pub chain: Option<String>,
+    #[serde(default)]
+    pub lookup_tables: Vec<String>, // 地址查找表，非空时发送v0交易
+    #[serde(default)]
+    pub window_id: Option<String>, // 同一窗口的批次共用 .sol 域名的锁定解析结果
 }
 
 #[derive(Serialize)]
@@ -47,23 +54,26 @@ pub struct TransferResult {
 
 #[tauri::command]
 pub async fn sol_transfer(
-    _index: usize,
-    item: TransferItem,
+    index: usize,
+    mut item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
     chain_service: tauri::State<'_, ChainService<'_>>,
 ) -> Result<TransferResult, String> {
     let chain = config.chain.as_deref().unwrap_or("sol");
+    match group_recipient(chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
     let client = match get_rpc_client(chain, Some(chain_service.get_pool())).await {
         Ok(c) => c,
         Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(format!("RPC连接失败: {e}")) }),
     };
     
-    let keypair = item.private_key.use_secret(|secret_str| {
-        let bytes = bs58::decode(secret_str).into_vec().map_err(|e| e.to_string())?;
-        Keypair::try_from(bytes.as_slice()).map_err(|e| e.to_string())
-    }).map_err(|e| e.to_string())??;
+    let keypair = parse_keypair(&item.private_key, item.derivation_index)?;
 
-    let to_pubkey = Pubkey::from_str(&item.to_addr).map_err(|e| e.to_string())?;
+    let to_pubkey = resolve_recipient(chain, config.window_id.as_deref(), &item.to_addr).await?;
     
     let input_amount = if let Some(amt) = &item.amount {
          if !amt.is_empty() { amt.parse::<f64>().unwrap_or(config.transfer_amount) } else { config.transfer_amount }
@@ -71,109 +81,87 @@ pub async fn sol_transfer(
         config.transfer_amount
     };
 
-    let mut instructions = vec![];
-    let lamports;
+    let tables = load_lookup_tables(&client, chain, &config.lookup_tables).await?;
+    let send_all = input_amount < 0.0;
+    let lamports = if send_all { 0 } else { (input_amount * 1_000_000_000.0) as u64 };
+    let transfer = |lamports| vec![system_instruction::transfer(&keypair.pubkey(), &to_pubkey, lamports)];
 
-    if input_amount < 0.0 {
-        // Send All Logic
-        let balance = client.get_balance(&keypair.pubkey()).await.map_err(|e| e.to_string())?;
-        
-        let base_fee = 5000;
-        let mut priority_fee = 0;
-        // Simple transfer usually takes < 500 CU. Set 1000 to be safe and deterministic.
-        let compute_unit_limit = 1000; 
-
-        if let Some(price) = config.gas_price {
-            if price > 0 {
-                instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
-                instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit));
-                priority_fee = (compute_unit_limit as u64 * price + 999_999) / 1_000_000;
-            }
-        }
+    // 计算单元由模拟得到，单价按配置或链上近期优先费估算
+    let budget = match plan_compute_budget(&client, &keypair, &transfer(lamports), &tables, &config).await {
+        Ok(budget) => budget,
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    };
+    let recent_blockhash = client.get_latest_blockhash().await?;
 
-        let total_fee = base_fee + priority_fee;
-        
+    let lamports = if send_all {
+        // Send All Logic：按最终交易查询实际手续费，余额扣除手续费后全部转出
+        let balance = client.get_balance(&keypair.pubkey()).await.map_err(|e| e.to_string())?;
+        let probe = build_transaction(&keypair, &budget.apply(&transfer(balance)), &tables, recent_blockhash)?;
+        let total_fee = client.get_fee_for_message(&probe.message).await?;
         if balance <= total_fee {
             return Ok(TransferResult { success: false, tx_hash: None, error: Some(format!("余额不足支付手续费 (余额: {}, 手续费: {})", balance, total_fee)) });
         }
-
-        lamports = balance - total_fee;
+        balance - total_fee
     } else {
-        // Normal Transfer
-        lamports = (input_amount * 1_000_000_000.0) as u64;
-        
-        if let Some(fee) = config.gas_price {
-            if fee > 0 {
-                 instructions.push(ComputeBudgetInstruction::set_compute_unit_price(fee));
-            }
-        }
-    }
-
-    instructions.push(system_instruction::transfer(&keypair.pubkey(), &to_pubkey, lamports));
+        lamports
+    };
 
-    let recent_blockhash = client.get_latest_blockhash().await?;
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&keypair.pubkey()),
-        &[&keypair],
-        recent_blockhash,
-    );
+    let transaction = build_transaction(&keypair, &budget.apply(&transfer(lamports)), &tables, recent_blockhash)?;
 
     match client.send_and_confirm_transaction(&transaction).await {
         Ok(sig) => Ok(TransferResult { success: true, tx_hash: Some(sig.to_string()), error: None }),
-        Err(e) => Ok(TransferResult { success: false, tx_hash: None, error: Some(e.to_string()) }),
+        Err(e) => {
+            invalidate_lookup_tables(chain, &tables);
+            Ok(TransferResult { success: false, tx_hash: None, error: Some(e.to_string()) })
+        }
     }
 }
 
 #[tauri::command]
 pub async fn sol_token_transfer(
-    _index: usize,
-    item: TransferItem,
+    index: usize,
+    mut item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
     chain_service: tauri::State<'_, ChainService<'_>>,
 ) -> Result<TransferResult, String> {
     let chain = config.chain.as_deref().unwrap_or("sol");
+    match group_recipient(chain, to_group.as_deref(), index).await {
+        Ok(Some(to_addr)) => item.to_addr = to_addr,
+        Ok(None) => {}
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    }
     let client = match get_rpc_client(chain, Some(chain_service.get_pool())).await {
         Ok(c) => c,
         Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(format!("RPC连接失败: {e}")) }),
     };
-    let mint_str = config.contract_address.ok_or("Missing Mint Address")?;
-    let mint = Pubkey::from_str(&mint_str).map_err(|_| "Invalid Mint Address")?;
+    let mint_str = config.contract_address.clone().ok_or("Missing Mint Address")?;
     
-    let keypair = item.private_key.use_secret(|secret_str| {
-        let bytes = bs58::decode(secret_str).into_vec().map_err(|e| e.to_string())?;
-        Keypair::try_from(bytes.as_slice()).map_err(|e| e.to_string())
-    }).map_err(|e| e.to_string())??;
+    let keypair = parse_keypair(&item.private_key, item.derivation_index)?;
+
+    let to_pubkey = resolve_recipient(chain, config.window_id.as_deref(), &item.to_addr).await?;
 
-    let to_pubkey = Pubkey::from_str(&item.to_addr).map_err(|_| "Invalid To Address")?;
+    // 自动识别代币所属程序（SPL Token / Token-2022）并获取精度
+    // (忽略 config.amount_precision，因为它通常是前端UI的随机数保留位数，而非Token精度)
+    let mint = match load_mint(&client, &mint_str).await {
+        Ok(mint) => mint,
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    };
+    let decimals = mint.decimals;
+    let epoch = if mint.has_transfer_fee() { client.get_epoch().await? } else { 0 };
     
-    let from_ata = get_associated_token_address(&keypair.pubkey(), &mint);
-    let to_ata = get_associated_token_address(&to_pubkey, &mint);
+    let from_ata = mint.associated_address(&keypair.pubkey());
+    let to_ata = mint.associated_address(&to_pubkey);
     
     let amount_val = if let Some(amt) = &item.amount {
          if !amt.is_empty() { amt.parse::<f64>().unwrap_or(config.transfer_amount) } else { config.transfer_amount }
     } else {
         config.transfer_amount
     };
-    
-    let decimals = {
-        // 自动获取代币精度 (忽略 config.amount_precision，因为它通常是前端UI的随机数保留位数，而非Token精度)
-        let account_info = client.get_account(&mint).await.map_err(|e| format!("无法获取代币信息: {e}"))?;
-        if account_info["value"].is_null() {
-            return Ok(TransferResult { success: false, tx_hash: None, error: Some("代币Mint账户不存在".to_string()) });
-        }
-        let data_str = account_info["value"]["data"][0].as_str().ok_or("无效的代币数据格式")?;
-        let data_bytes = base64::engine::general_purpose::STANDARD.decode(data_str)
-            .map_err(|_| "Base64解码失败")?;
-            
-        let mint_state = Mint::unpack(&data_bytes).map_err(|_| "无法解析Mint数据")?;
-        mint_state.decimals
-    };
 
     let amount_u64 = if amount_val < 0.0 {
         // Send All Logic for Token
-        // 查询ATA余额
-        let from_ata = get_associated_token_address(&keypair.pubkey(), &mint);
+        // 查询ATA余额，有转账手续费时手续费从转出数量中扣除
         match client.get_token_account_balance(&from_ata).await {
             Ok(balance_res) => {
                  // 优先使用 amount 字段（原始u64字符串），如果不存在则尝试从 uiAmount 计算（不推荐，有精度风险）
@@ -188,15 +176,11 @@ pub async fn sol_token_transfer(
             Err(_) => 0,
         }
     } else {
-        (amount_val * 10f64.powi(decimals as i32)) as u64
+        // 有转账手续费时按含手续费的数量转出，保证接收方到账金额为填写的数量
+        mint.gross_amount(epoch, (amount_val * 10f64.powi(decimals as i32)) as u64)?
     };
 
     let mut instructions = vec![];
-    if let Some(fee) = config.gas_price {
-        if fee > 0 {
-             instructions.push(ComputeBudgetInstruction::set_compute_unit_price(fee));
-        }
-    }
 
     // Check if destination account exists
     let should_create_ata = match client.get_account(&to_ata).await {
@@ -205,34 +189,25 @@ pub async fn sol_token_transfer(
     };
 
     if should_create_ata {
-        instructions.push(create_associated_token_account(
-            &keypair.pubkey(),
-            &to_pubkey,
-            &mint,
-            &spl_token::id(),
-        ));
+        instructions.push(mint.create_associated_account(&keypair.pubkey(), &to_pubkey));
     }
     
-    instructions.push(spl_token::instruction::transfer(
-        &spl_token::id(),
-        &from_ata,
-        &to_ata,
-        &keypair.pubkey(),
-        &[],
-        amount_u64,
-    ).map_err(|e| e.to_string())?);
+    instructions.push(mint.transfer_instruction(&from_ata, &to_ata, &keypair.pubkey(), amount_u64, epoch)?);
 
+    let tables = load_lookup_tables(&client, chain, &config.lookup_tables).await?;
+    let budget = match plan_compute_budget(&client, &keypair, &instructions, &tables, &config).await {
+        Ok(budget) => budget,
+        Err(e) => return Ok(TransferResult { success: false, tx_hash: None, error: Some(e) }),
+    };
     let recent_blockhash = client.get_latest_blockhash().await?;
-    let transaction = Transaction::new_signed_with_payer(
-        &instructions,
-        Some(&keypair.pubkey()),
-        &[&keypair],
-        recent_blockhash,
-    );
+    let transaction = build_transaction(&keypair, &budget.apply(&instructions), &tables, recent_blockhash)?;
 
     match client.send_and_confirm_transaction(&transaction).await {
         Ok(sig) => Ok(TransferResult { success: true, tx_hash: Some(sig.to_string()), error: None }),
-        Err(e) => Ok(TransferResult { success: false, tx_hash: None, error: Some(e.to_string()) }),
+        Err(e) => {
+            invalidate_lookup_tables(chain, &tables);
+            Ok(TransferResult { success: false, tx_hash: None, error: Some(e.to_string()) })
+        }
     }
 }
 
@@ -251,6 +226,7 @@ pub async fn sol_check_recent_transfers(
     _coin_type: String,
     _contract_address: Option<String>,
     _amount: Option<String>,
+    derivation_index: Option<u32>,
     chain_service: tauri::State<'_, ChainService<'_>>,
 ) -> Result<CheckResult, String> {
     let client = match get_rpc_client(&chain, Some(chain_service.get_pool())).await {
@@ -258,10 +234,7 @@ pub async fn sol_check_recent_transfers(
         Err(e) => return Err(format!("RPC连接失败: {e}")),
     };
 
-    let keypair = private_key.use_secret(|secret_str| {
-        let bytes = bs58::decode(secret_str).into_vec().map_err(|e| e.to_string())?;
-        Keypair::try_from(bytes.as_slice()).map_err(|e| e.to_string())
-    }).map_err(|e| e.to_string())??;
+    let keypair = parse_keypair(&private_key, derivation_index)?;
 
     // 获取最近的20条交易记录
     let signatures = client.get_signatures_for_address(&keypair.pubkey(), 20).await
@@ -349,9 +322,10 @@ pub async fn sol_transfer_fast(
     index: usize,
     item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
     chain_service: tauri::State<'_, ChainService<'_>>,
 ) -> Result<TransferResult, String> {
-    sol_transfer(index, item, config, chain_service).await
+    sol_transfer(index, item, config, to_group, chain_service).await
 }
 
 #[tauri::command]
@@ -359,9 +333,10 @@ pub async fn sol_token_transfer_fast(
     index: usize,
     item: TransferItem,
     config: TransferConfig,
+    to_group: Option<String>,
     chain_service: tauri::State<'_, ChainService<'_>>,
 ) -> Result<TransferResult, String> {
-    sol_token_transfer(index, item, config, chain_service).await
+    sol_token_transfer(index, item, config, to_group, chain_service).await
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -379,6 +354,9 @@ pub struct BalanceQueryParams {
     pub query_id: String,
     pub chain: Option<String>,
     pub coin_config: Option<CoinConfig>,
+    // 地址簿分组名，后端展开为查询地址并追加在 items 之后
+    #[serde(default)]
+    pub address_group: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -404,24 +382,38 @@ pub async fn sol_query_balances_with_updates(
         Err(e) => return Err(format!("无法连接到 Solana RPC: {e}")),
     };
 
-    // Determine if we are querying a token
-    let mut mint_pubkey = None;
+    // Determine if we are querying a token（自动识别 SPL Token / Token-2022）
+    let mut token_mint = None;
     if let Some(config) = &params.coin_config {
         if config.coin_type == "token" {
             if let Some(addr) = &config.contract_address {
                 if !addr.is_empty() {
-                    match Pubkey::from_str(addr) {
-                        Ok(pk) => mint_pubkey = Some(pk),
-                        Err(_) => return Err("无效的代币合约地址".to_string()),
+                    match load_mint(&client, addr).await {
+                        Ok(mint) => token_mint = Some(mint),
+                        Err(e) => return Err(format!("无效的代币合约地址: {e}")),
                     }
                 }
             }
         }
     }
     
+    let mut items = params.items;
+    if let Some(group) = params.address_group.as_deref().map(str::trim).filter(|group| !group.is_empty()) {
+        let addresses = resolve_group_addresses(chain, group).await?;
+        items.extend(addresses.into_iter().map(|address| BalanceItem {
+            key: address.clone(),
+            address,
+            private_key: None,
+            plat_balance: None,
+            coin_balance: None,
+            exec_status: "0".to_string(),
+            error_msg: None,
+        }));
+    }
+
     let mut results = vec![];
     
-    for mut item in params.items {
+    for mut item in items {
         if let Ok(pubkey) = Pubkey::from_str(&item.address) {
             match client.get_balance(&pubkey).await {
                 Ok(bal) => {
@@ -435,9 +427,9 @@ pub async fn sol_query_balances_with_updates(
             }
 
             // Query SPL Token Balance (If configured)
-            if let Some(mint) = mint_pubkey {
+            if let Some(mint) = &token_mint {
                 if item.exec_status != "3" { 
-                    let ata = get_associated_token_address(&pubkey, &mint);
+                    let ata = mint.associated_address(&pubkey);
                     match client.get_token_account_balance(&ata).await {
                         Ok(balance_res) => {
                             if let Some(amount_str) = balance_res.ui_amount_string {
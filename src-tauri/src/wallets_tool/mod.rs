@@ -1,5 +1,7 @@
 pub mod utils;
 pub mod security;
+pub mod address_validation;
+pub mod address_book;
 
 pub mod ecosystems {
     pub mod ethereum {
@@ -9,6 +11,21 @@ pub mod ecosystems {
         pub mod provider;
         pub mod token_transfer;
         pub mod transfer;
+        pub mod transfer_job;
+        pub mod nonce_manager;
+        pub mod tx_replacement;
+        pub mod tx_tracker;
+        pub mod multicall;
+        pub mod rpc_batch;
+        pub mod historical;
+        pub mod activity_profile;
+        pub mod token_discovery;
+        pub mod nft;
+        pub mod balance_snapshot;
+        pub mod pricing;
+        pub mod recent_transfer;
+        pub mod ens;
+        pub mod dry_run;
         pub mod proxy_manager;
         pub mod proxy_commands;
         pub mod alloy_utils;
@@ -16,6 +33,12 @@ pub mod ecosystems {
     pub mod solana {
         pub mod provider;
         pub mod transfer;
+        pub mod sns;
+        pub mod lookup_table;
+        pub mod batch_transfer;
+        pub mod priority_fee;
+        pub mod token_program;
+        pub mod keypair;
     }
 }
 
@@ -190,6 +190,7 @@ export function useTransfer(options = {}) {
                 ? currentCoin.value.contract_address
                 : null,
         amount: amount,
+        windowId: transferConfig.value?.window_id,
       });
 
       return result.has_recent_transfer || false;
@@ -253,6 +254,13 @@ export function useTransfer(options = {}) {
           stopStatus.value = true;
           showProgress.value = false;
         });
+
+    // 批次结束后释放本窗口锁定的域名解析结果
+    if (transferConfig.value && transferConfig.value.window_id) {
+      invoke('release_recipient_names', { windowId: transferConfig.value.window_id }).catch(e => {
+        console.error('释放域名解析结果失败:', e);
+      });
+    }
   }
 
   async function iterTransfer(accountData) {
@@ -837,7 +845,8 @@ export function useTransfer(options = {}) {
         try {
             const results = await invoke('check_transactions_status_batch', {
                 chain: chainValue.value,
-                tx_hashes: txHashes
+                tx_hashes: txHashes,
+                windowId: transferConfig.value?.window_id
             });
             
             // 处理结果
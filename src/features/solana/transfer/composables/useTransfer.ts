@@ -380,6 +380,13 @@ export function useTransfer(options = {}) {
           stopStatus.value = true;
           showProgress.value = false;
         });
+
+    // 批次结束后释放本窗口锁定的域名解析结果
+    if (transferConfig.value && transferConfig.value.window_id) {
+      invoke('sol_release_recipient_names', { windowId: transferConfig.value.window_id }).catch(e => {
+        console.error('释放域名解析结果失败:', e);
+      });
+    }
   }
 
   async function iterTransfer(accountData) {